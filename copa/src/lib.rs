@@ -659,7 +659,7 @@ mod tests {
 
     #[test]
     fn issue_191() {
-        use crate::std::string::{String, ToString};
+        use std::string::{String, ToString};
         use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
 
         // https://github.com/raphamorim/rio/issues/191
@@ -203,3 +203,15 @@ pub fn selection_background() -> ColorArray {
         .unwrap()
         .to_arr()
 }
+
+pub fn search_match_foreground() -> ColorArray {
+    ColorBuilder::from_hex(String::from("#0F0D0E"), Format::SRGB0_1)
+        .unwrap()
+        .to_arr()
+}
+
+pub fn search_match_background() -> ColorArray {
+    ColorBuilder::from_hex(String::from("#FCBA28"), Format::SRGB0_1)
+        .unwrap()
+        .to_arr()
+}
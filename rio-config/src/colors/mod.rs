@@ -11,7 +11,7 @@ pub type ColorWGPU = wgpu::Color;
 pub type ColorArray = [f32; 4];
 pub type ColorComposition = (ColorArray, ColorWGPU);
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct ColorRgb {
     pub r: u8,
     pub g: u8,
@@ -21,9 +21,9 @@ pub struct ColorRgb {
 impl ColorRgb {
     pub fn from_color_arr(arr: ColorArray) -> ColorRgb {
         ColorRgb {
-            r: (arr[0] * 255.0) as u8,
-            g: (arr[1] * 255.0) as u8,
-            b: (arr[2] * 255.0) as u8,
+            r: (arr[0] * 255.0).round() as u8,
+            g: (arr[1] * 255.0).round() as u8,
+            b: (arr[2] * 255.0).round() as u8,
         }
     }
 
@@ -46,7 +46,7 @@ pub enum Format {
     SRGB0_1,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AnsiColor {
     Named(NamedColor),
     Spec(ColorRgb),
@@ -215,6 +215,21 @@ pub struct Colors {
         rename = "selection-foreground"
     )]
     pub selection_foreground: ColorArray,
+    #[serde(
+        default = "defaults::search_match_background",
+        deserialize_with = "deserialize_to_arr",
+        rename = "search-match-background"
+    )]
+    pub search_match_background: ColorArray,
+    #[serde(
+        default = "defaults::search_match_foreground",
+        deserialize_with = "deserialize_to_arr",
+        rename = "search-match-foreground"
+    )]
+    pub search_match_foreground: ColorArray,
+    /// Undercurl color used when a cell has no explicit underline color (SGR 58).
+    #[serde(default, deserialize_with = "deserialize_to_arr_opt")]
+    pub undercurl: Option<ColorArray>,
 }
 
 impl Default for Colors {
@@ -253,6 +268,9 @@ impl Default for Colors {
             light_yellow: defaults::light_yellow(),
             selection_background: defaults::selection_background(),
             selection_foreground: defaults::selection_foreground(),
+            search_match_background: defaults::search_match_background(),
+            search_match_foreground: defaults::search_match_foreground(),
+            undercurl: None,
         }
     }
 }
@@ -269,7 +287,9 @@ pub fn hex_to_color_wgpu(s: &str) -> ColorWGPU {
         .to_wgpu()
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(
+    Debug, Copy, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
 pub enum NamedColor {
     /// Black.
     Black = 0,
@@ -357,6 +377,28 @@ impl NamedColor {
         }
     }
 
+    /// RGB value in the default xterm palette, as light text on a dark background.
+    ///
+    /// This is the fallback when no palette is configured.
+    pub fn default_rgb(&self) -> ColorRgb {
+        match *self {
+            NamedColor::Foreground | NamedColor::Cursor => term::indexed_to_rgb(7),
+            NamedColor::Background => term::indexed_to_rgb(0),
+            NamedColor::LightForeground => term::indexed_to_rgb(15),
+            color if (color as usize) < 16 => term::indexed_to_rgb(color as u8),
+            // Dim colors are derived from the color they dim.
+            color => {
+                let ColorRgb { r, g, b } = color.to_light().default_rgb();
+                let dim = |value: u8| (value as f32 * 0.66) as u8;
+                ColorRgb {
+                    r: dim(r),
+                    g: dim(g),
+                    b: dim(b),
+                }
+            }
+        }
+    }
+
     #[must_use]
     pub fn to_dim(self) -> Self {
         match self {
@@ -577,6 +619,15 @@ where
     }
 }
 
+pub fn deserialize_to_arr_opt<'de, D>(
+    deserializer: D,
+) -> Result<Option<ColorArray>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    deserialize_to_arr(deserializer).map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -594,6 +645,24 @@ mod tests {
         assert_eq!(invalid_character_color, "Error: Character is not valid");
     }
 
+    #[test]
+    fn test_named_color_default_rgb() {
+        let rgb = |r, g, b| ColorRgb { r, g, b };
+
+        assert_eq!(NamedColor::Black.default_rgb(), rgb(0, 0, 0));
+        assert_eq!(NamedColor::Red.default_rgb(), rgb(0xcd, 0, 0));
+        assert_eq!(NamedColor::LightRed.default_rgb(), rgb(0xff, 0, 0));
+        assert_eq!(NamedColor::LightBlue.default_rgb(), rgb(0x5c, 0x5c, 0xff));
+        assert_eq!(NamedColor::DimRed.default_rgb(), rgb(135, 0, 0));
+
+        // Light text on a dark background.
+        let foreground = NamedColor::Foreground.default_rgb();
+        let background = NamedColor::Background.default_rgb();
+        assert_eq!(background, rgb(0, 0, 0));
+        assert!(foreground.r > 0xc0 && foreground.g > 0xc0 && foreground.b > 0xc0);
+        assert!(NamedColor::DimForeground.default_rgb().r < foreground.r);
+    }
+
     #[test]
     fn test_default_as_black() {
         let default_color: ColorBuilder = ColorBuilder::default();
@@ -1,4 +1,4 @@
-use crate::colors::{ColorArray, ColorBuilder, ColorRgb, Format};
+use crate::colors::{AnsiColor, ColorArray, ColorBuilder, ColorRgb, Format};
 use std::ops::{Index, IndexMut};
 
 use crate::colors::defaults;
@@ -7,6 +7,57 @@ use crate::colors::NamedColor;
 /// Number of terminal colors.
 pub const COUNT: usize = 269;
 
+/// Default RGB values of the 16 base colors in xterm.
+const XTERM_BASE_COLORS: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f),
+    (0xff, 0x00, 0x00),
+    (0x00, 0xff, 0x00),
+    (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff),
+    (0xff, 0x00, 0xff),
+    (0x00, 0xff, 0xff),
+    (0xff, 0xff, 0xff),
+];
+
+/// RGB value of an indexed color following the xterm 256 color table.
+///
+/// The 16 base colors use the xterm defaults, terminals usually let them be configured.
+pub fn indexed_to_rgb(index: u8) -> ColorRgb {
+    match index {
+        0..=15 => {
+            let (r, g, b) = XTERM_BASE_COLORS[index as usize];
+            ColorRgb { r, g, b }
+        }
+        16..=231 => {
+            let cube = index - 16;
+            let level = |value: u8| if value == 0 { 0 } else { value * 40 + 55 };
+            ColorRgb {
+                r: level(cube / 36),
+                g: level(cube / 6 % 6),
+                b: level(cube % 6),
+            }
+        }
+        232..=255 => {
+            let value = (index - 232) * 10 + 8;
+            ColorRgb {
+                r: value,
+                g: value,
+                b: value,
+            }
+        }
+    }
+}
+
+/// Colors changed at runtime by the terminal, `None` keeps the configured color.
+///
 /// > The 256 color table and its partitioning
 ///
 /// The color range of a 256 color terminal consists of 4 parts,
@@ -35,9 +86,8 @@ pub const COUNT: usize = 269;
 /// 256 indexed colors, giving an additional two configurable colors.
 /// You get them when not setting any other color or disabling other colors
 /// (i.e. print '\e[m').
-
 #[derive(Copy, Debug, Clone)]
-pub struct TermColors([Option<ColorArray>; COUNT]);
+pub struct TermColors([Option<ColorRgb>; COUNT]);
 
 impl Default for TermColors {
     fn default() -> Self {
@@ -46,7 +96,7 @@ impl Default for TermColors {
 }
 
 impl Index<usize> for TermColors {
-    type Output = Option<ColorArray>;
+    type Output = Option<ColorRgb>;
 
     #[inline]
     fn index(&self, index: usize) -> &Self::Output {
@@ -62,7 +112,7 @@ impl IndexMut<usize> for TermColors {
 }
 
 impl Index<NamedColor> for TermColors {
-    type Output = Option<ColorArray>;
+    type Output = Option<ColorRgb>;
 
     #[inline]
     fn index(&self, index: NamedColor) -> &Self::Output {
@@ -126,43 +176,37 @@ impl List {
     }
 
     pub fn fill_cube(&mut self) {
-        let mut index: usize = 16;
-        // Build colors.
-        for r in 0..6 {
-            for g in 0..6 {
-                for b in 0..6 {
-                    let rgb = ColorRgb {
-                        r: if r == 0 { 0 } else { r * 40 + 55 },
-                        b: if b == 0 { 0 } else { b * 40 + 55 },
-                        g: if g == 0 { 0 } else { g * 40 + 55 },
-                    };
-
-                    let arr = ColorBuilder::from_rgb(rgb, Format::SRGB0_1).to_arr();
-                    self[index] = arr;
-                    index += 1;
-                }
-            }
+        for index in 16..=231 {
+            let rgb = indexed_to_rgb(index);
+            self[index as usize] = ColorBuilder::from_rgb(rgb, Format::SRGB0_1).to_arr();
         }
-
-        debug_assert!(index == 232);
     }
 
     pub fn fill_gray_ramp(&mut self) {
-        let mut index: usize = 232;
+        for index in 232..=255 {
+            let rgb = indexed_to_rgb(index);
+            self[index as usize] = ColorBuilder::from_rgb(rgb, Format::SRGB0_1).to_arr();
+        }
+    }
 
-        for i in 0..24 {
-            let value = i * 10 + 8;
-            let rgb = ColorRgb {
-                r: value,
-                g: value,
-                b: value,
-            };
-            let arr = ColorBuilder::from_rgb(rgb, Format::SRGB0_1).to_arr();
-            self[index] = arr;
-            index += 1;
+    /// Color of `color` in this list, falling back to the color itself for RGB values.
+    pub fn resolve(&self, color: AnsiColor) -> ColorArray {
+        match color {
+            AnsiColor::Named(named) => self[named],
+            AnsiColor::Indexed(index) => self[index as usize],
+            AnsiColor::Spec(rgb) => rgb.to_arr(),
         }
+    }
 
-        debug_assert!(index == 256);
+    /// Like [`List::resolve`], but bold text uses the bright variant of the eight standard
+    /// named colors.
+    pub fn resolve_with_bold(&self, color: AnsiColor, bold: bool) -> ColorArray {
+        match color {
+            AnsiColor::Named(named) if bold && (named as usize) < 8 => {
+                self[named.to_light()]
+            }
+            color => self.resolve(color),
+        }
     }
 }
 
@@ -197,3 +241,59 @@ impl IndexMut<NamedColor> for List {
         &mut self.0[idx as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u8, g: u8, b: u8) -> ColorRgb {
+        ColorRgb { r, g, b }
+    }
+
+    #[test]
+    fn indexed_to_rgb_follows_xterm_table() {
+        assert_eq!(indexed_to_rgb(0), rgb(0, 0, 0));
+        assert_eq!(indexed_to_rgb(15), rgb(255, 255, 255));
+        assert_eq!(indexed_to_rgb(16), rgb(0, 0, 0));
+        assert_eq!(indexed_to_rgb(17), rgb(0, 0, 95));
+        assert_eq!(indexed_to_rgb(196), rgb(255, 0, 0));
+        assert_eq!(indexed_to_rgb(231), rgb(255, 255, 255));
+        assert_eq!(indexed_to_rgb(232), rgb(8, 8, 8));
+        assert_eq!(indexed_to_rgb(255), rgb(238, 238, 238));
+    }
+
+    #[test]
+    fn bold_brightens_standard_named_colors() {
+        let list = List::from(&TermColors::default());
+        let red = AnsiColor::Named(NamedColor::Red);
+
+        assert_eq!(list.resolve_with_bold(red, true), defaults::light_red());
+        assert_eq!(list.resolve_with_bold(red, false), defaults::red());
+
+        let spec = AnsiColor::Spec(rgb(10, 20, 30));
+        assert_eq!(list.resolve_with_bold(spec, true), list.resolve(spec));
+        let light_red = AnsiColor::Named(NamedColor::LightRed);
+        assert_eq!(
+            list.resolve_with_bold(light_red, true),
+            defaults::light_red()
+        );
+    }
+
+    #[test]
+    fn list_resolves_every_kind_of_color() {
+        let list = List::from(&TermColors::default());
+
+        assert_eq!(
+            list.resolve(AnsiColor::Named(NamedColor::Red)),
+            defaults::red()
+        );
+        assert_eq!(
+            list.resolve(AnsiColor::Indexed(231)),
+            rgb(255, 255, 255).to_arr()
+        );
+        assert_eq!(
+            list.resolve(AnsiColor::Spec(rgb(1, 2, 3))),
+            rgb(1, 2, 3).to_arr()
+        );
+    }
+}
@@ -68,6 +68,26 @@ pub fn default_cursor() -> char {
     '▇'
 }
 
+pub fn default_visual_blink() -> bool {
+    true
+}
+
+pub fn default_history_size() -> usize {
+    10_000
+}
+
+pub fn default_bold_is_bright() -> bool {
+    true
+}
+
+pub fn default_word_separators() -> String {
+    String::from(",│`|:\"' ()[]{}<>\t")
+}
+
+pub fn default_osc52_max_size() -> usize {
+    4 * 1024 * 1024
+}
+
 pub fn default_theme() -> String {
     String::from("")
 }
@@ -101,6 +121,73 @@ blinking-cursor = false
 #
 # ignore-selection-fg-color = false
 
+# Visual blink
+#
+# Blinking text (SGR 5 and 6) is animated by default,
+# if disabled it is rendered with the bright foreground color instead.
+#
+# Default is true
+#
+visual-blink = true
+
+# Reset mode
+#
+# Attributes kept by erased cells (clear screen, clear line, scrolling)
+# BcePreserveBg: Keep the current background color.
+# BceFull: Keep the current background, foreground and reverse video.
+# NoBce: Always erase to the default colors.
+#
+# Default is BcePreserveBg
+#
+reset-mode = "BcePreserveBg"
+
+# History size
+#
+# Number of lines kept in the scrollback history,
+# the alternate screen never keeps history.
+#
+# Default is 10000
+#
+history-size = 10000
+
+# Bold is bright
+#
+# Bold text using one of the eight standard colors
+# is drawn with the bright variant of the color.
+#
+# Default is true
+#
+bold-is-bright = true
+
+# Word separators
+#
+# Characters ending a word when selecting it with a double click.
+#
+# Default is ",│`|:\"' ()[]{}<>\t"
+#
+word-separators = ",│`|:\"' ()[]{}<>\t"
+
+# Expand tabs on copy
+#
+# Copy tabs as the spaces they cover up to the next tab stop,
+# instead of a literal tab character.
+#
+# Default is false
+#
+expand-tabs-on-copy = false
+
+# OSC 52
+#
+# Programs like tmux and neovim can write to the clipboard through
+# OSC 52. Payloads larger than `osc52-max-size` bytes of base64 are
+# ignored. Reading the clipboard back is disabled unless `osc52-read`
+# is true, since any program in the terminal could then read it.
+#
+# Default is osc52-max-size = 4194304 and osc52-read = false
+#
+osc52-max-size = 4194304
+osc52-read = false
+
 # Performance
 #
 # Set WGPU rendering performance
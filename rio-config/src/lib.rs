@@ -30,6 +30,18 @@ pub enum Performance {
     Low,
 }
 
+/// How erased cells (ED, EL, ECH, scrolling) inherit the current attributes.
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum ResetMode {
+    /// Background color erase, only the background color is kept.
+    #[default]
+    BcePreserveBg,
+    /// Background color erase keeping the foreground and reverse video as well.
+    BceFull,
+    /// Erased cells always use the default colors.
+    NoBce,
+}
+
 impl std::fmt::Display for Performance {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -116,6 +128,22 @@ pub struct Config {
     pub bindings: bindings::Bindings,
     #[serde(default = "bool::default", rename = "ignore-selection-fg-color")]
     pub ignore_selection_fg_color: bool,
+    #[serde(default = "default_visual_blink", rename = "visual-blink")]
+    pub visual_blink: bool,
+    #[serde(default = "ResetMode::default", rename = "reset-mode")]
+    pub reset_mode: ResetMode,
+    #[serde(default = "default_history_size", rename = "history-size")]
+    pub history_size: usize,
+    #[serde(default = "default_bold_is_bright", rename = "bold-is-bright")]
+    pub bold_is_bright: bool,
+    #[serde(default = "default_word_separators", rename = "word-separators")]
+    pub word_separators: String,
+    #[serde(default = "bool::default", rename = "expand-tabs-on-copy")]
+    pub expand_tabs_on_copy: bool,
+    #[serde(default = "default_osc52_max_size", rename = "osc52-max-size")]
+    pub osc52_max_size: usize,
+    #[serde(default = "bool::default", rename = "osc52-read")]
+    pub osc52_read: bool,
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -361,6 +389,14 @@ impl Default for Config {
             window: Window::default(),
             working_dir: default_working_dir(),
             ignore_selection_fg_color: false,
+            visual_blink: default_visual_blink(),
+            reset_mode: ResetMode::default(),
+            history_size: default_history_size(),
+            bold_is_bright: default_bold_is_bright(),
+            word_separators: default_word_separators(),
+            expand_tabs_on_copy: false,
+            osc52_max_size: default_osc52_max_size(),
+            osc52_read: false,
         }
     }
 }
@@ -453,6 +489,14 @@ mod tests {
         assert!(!result.disable_unfocused_render);
         assert_eq!(result.use_fork, default_use_fork());
         assert_eq!(result.line_height, default_line_height());
+        assert_eq!(result.visual_blink, default_visual_blink());
+        assert_eq!(result.reset_mode, ResetMode::default());
+        assert_eq!(result.history_size, default_history_size());
+        assert_eq!(result.bold_is_bright, default_bold_is_bright());
+        assert_eq!(result.word_separators, default_word_separators());
+        assert!(!result.expand_tabs_on_copy);
+        assert_eq!(result.osc52_max_size, default_osc52_max_size());
+        assert!(!result.osc52_read);
 
         // Colors
         assert_eq!(result.colors, Colors::default());
@@ -532,6 +576,14 @@ mod tests {
             result.colors.selection_foreground,
             colors::defaults::selection_foreground()
         );
+        assert_eq!(
+            result.colors.search_match_background,
+            colors::defaults::search_match_background()
+        );
+        assert_eq!(
+            result.colors.search_match_foreground,
+            colors::defaults::search_match_foreground()
+        );
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
@@ -718,6 +770,7 @@ mod tests {
         assert_eq!(result.colors.foreground, [0.0, 0.0, 0.0, 1.0]);
         assert_eq!(result.colors.tabs_active, colors::defaults::tabs_active());
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
+        assert_eq!(result.colors.undercurl, None);
     }
 
     #[test]
@@ -730,6 +783,9 @@ mod tests {
             tabs-active      = '#E6DB74'
             selection-background = '#111111'
             selection-foreground = '#222222'
+            search-match-background = '#333333'
+            search-match-foreground = '#444444'
+            undercurl        = '#FF0000'
             foreground       = '#F8F8F2'
             cursor           = '#E6DB74'
             black            = '#FFFFFF'
@@ -809,6 +865,15 @@ mod tests {
             result.colors.selection_foreground,
             hex_to_color_arr("#222222")
         );
+        assert_eq!(
+            result.colors.search_match_background,
+            hex_to_color_arr("#333333")
+        );
+        assert_eq!(
+            result.colors.search_match_foreground,
+            hex_to_color_arr("#444444")
+        );
+        assert_eq!(result.colors.undercurl, Some(hex_to_color_arr("#FF0000")));
     }
 
     #[test]
@@ -834,6 +899,80 @@ mod tests {
         assert_eq!(result.colors.cursor, colors::defaults::cursor());
     }
 
+    #[test]
+    fn test_reset_mode() {
+        let result = create_temporary_config(
+            "change-reset-mode",
+            r#"
+            reset-mode = "NoBce"
+        "#,
+        );
+
+        assert_eq!(result.reset_mode, ResetMode::NoBce);
+    }
+
+    #[test]
+    fn test_history_size() {
+        let result = create_temporary_config(
+            "change-history-size",
+            r#"
+            history-size = 500
+        "#,
+        );
+
+        assert_eq!(result.history_size, 500);
+    }
+
+    #[test]
+    fn test_bold_is_bright() {
+        let result = create_temporary_config(
+            "change-bold-is-bright",
+            r#"
+            bold-is-bright = false
+        "#,
+        );
+
+        assert!(!result.bold_is_bright);
+    }
+
+    #[test]
+    fn test_word_separators() {
+        let result = create_temporary_config(
+            "change-word-separators",
+            r#"
+            word-separators = " ,;"
+        "#,
+        );
+
+        assert_eq!(result.word_separators, " ,;");
+    }
+
+    #[test]
+    fn test_expand_tabs_on_copy() {
+        let result = create_temporary_config(
+            "change-expand-tabs-on-copy",
+            r#"
+            expand-tabs-on-copy = true
+        "#,
+        );
+
+        assert!(result.expand_tabs_on_copy);
+    }
+
+    #[test]
+    fn test_osc52() {
+        let result = create_temporary_config(
+            "change-osc52",
+            r#"
+            osc52-max-size = 1024
+            osc52-read = true
+        "#,
+        );
+
+        assert_eq!(result.osc52_max_size, 1024);
+        assert!(result.osc52_read);
+    }
+
     #[test]
     fn test_shell() {
         let result = create_temporary_config(
@@ -26,6 +26,13 @@ impl CursorShape {
     }
 }
 
+/// Cursor shape along with whether it blinks, as requested by DECSCUSR.
+#[derive(Debug, Default, Eq, PartialEq, Copy, Clone)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
 #[derive(Debug)]
 pub enum ClearMode {
     /// Clear below cursor.
@@ -38,6 +45,17 @@ pub enum ClearMode {
     Saved,
 }
 
+/// Area of a rectangular operation (DECFRA, DECERA, DECSERA, DECCRA) as 1-based coordinates.
+///
+/// Omitted bounds are `None` and extend the area to the edge of the screen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Rectangle {
+    pub top: Option<usize>,
+    pub left: Option<usize>,
+    pub bottom: Option<usize>,
+    pub right: Option<usize>,
+}
+
 #[derive(Debug)]
 pub enum TabulationClearMode {
     /// Clear stop under cursor.
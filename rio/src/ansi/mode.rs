@@ -36,6 +36,12 @@ pub enum Mode {
     LineFeedNewLine = 20,
     /// ?25
     ShowCursor = 25,
+    /// ?47
+    SwapScreen = 47,
+    /// ?69
+    ///
+    /// Left and right margin mode (DECLRMM), which makes `CSI Pl ; Pr s` set the margins.
+    LeftRightMargin = 69,
     /// ?1000
     ReportMouseClicks = 1000,
     /// ?1002
@@ -52,10 +58,18 @@ pub enum Mode {
     AlternateScroll = 1007,
     /// ?1042
     UrgencyHints = 1042,
+    /// ?1047
+    ///
+    /// Like ?47, the alternate screen is cleared when leaving it.
+    SwapScreenAndClear = 1047,
+    /// ?1048
+    SaveCursor = 1048,
     /// ?1049
     SwapScreenAndSetRestoreCursor = 1049,
     /// ?2004
     BracketedPaste = 2004,
+    /// ?2027
+    GraphemeClustering = 2027,
 }
 
 impl Mode {
@@ -75,6 +89,8 @@ impl Mode {
                 7 => Mode::LineWrap,
                 12 => Mode::BlinkingCursor,
                 25 => Mode::ShowCursor,
+                47 => Mode::SwapScreen,
+                69 => Mode::LeftRightMargin,
                 1000 => Mode::ReportMouseClicks,
                 1002 => Mode::ReportSquareMouseMotion,
                 1003 => Mode::ReportAllMouseMotion,
@@ -83,8 +99,11 @@ impl Mode {
                 1006 => Mode::SgrMouse,
                 1007 => Mode::AlternateScroll,
                 1042 => Mode::UrgencyHints,
+                1047 => Mode::SwapScreenAndClear,
+                1048 => Mode::SaveCursor,
                 1049 => Mode::SwapScreenAndSetRestoreCursor,
                 2004 => Mode::BracketedPaste,
+                2027 => Mode::GraphemeClustering,
                 _ => {
                     warn!("[unimplemented] primitive mode: {}", num);
                     return None;
@@ -1,6 +1,6 @@
 use rio_config::colors::AnsiColor;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum Attr {
     /// Clear all special abilities.
     Reset,
@@ -20,9 +20,9 @@ pub enum Attr {
     DottedUnderline,
     /// Dashed underlined text.
     DashedUnderline,
-    /// Blink cursor slowly.
+    /// Blink text slowly.
     BlinkSlow,
-    /// Blink cursor fast.
+    /// Blink text fast.
     BlinkFast,
     /// Invert colors.
     Reverse,
@@ -30,6 +30,8 @@ pub enum Attr {
     Hidden,
     /// Strikeout text.
     Strike,
+    /// Overlined text.
+    Overline,
     /// Cancel bold.
     CancelBold,
     /// Cancel bold and dim.
@@ -46,6 +48,8 @@ pub enum Attr {
     CancelHidden,
     /// Cancel strikeout.
     CancelStrike,
+    /// Cancel overline.
+    CancelOverline,
     /// Set indexed foreground color.
     Foreground(AnsiColor),
     /// Set indexed background color.
@@ -9,7 +9,7 @@ pub mod storage;
 #[cfg(test)]
 mod tests;
 
-use crate::crosswords::pos::Pos;
+use crate::crosswords::pos::{Pos, SavedCursor};
 use crate::crosswords::square::Flags;
 use crate::crosswords::square::ResetDiscriminant;
 use crate::crosswords::Cursor;
@@ -31,6 +31,13 @@ pub enum Scroll {
     Bottom,
 }
 
+/// Rectangular area of the visible grid, used by scrolling with left and right margins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub lines: Range<Line>,
+    pub columns: Range<Column>,
+}
+
 pub trait GridSquare: Sized {
     fn is_empty(&self) -> bool;
     fn reset(&mut self, template: &Self);
@@ -44,7 +51,7 @@ pub struct Grid<T> {
     pub cursor: Cursor<T>,
 
     /// Last saved cursor.
-    pub saved_cursor: Cursor<T>,
+    pub saved_cursor: SavedCursor<T>,
 
     /// Lines in the grid. Each row holds a list of cells corresponding to the
     /// columns in that row.
@@ -73,15 +80,43 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
             raw: Storage::with_capacity(lines, columns),
             max_scroll_limit,
             display_offset: 0,
-            saved_cursor: Cursor::default(),
+            saved_cursor: SavedCursor::default(),
+            cursor: Cursor::default(),
+            lines,
+            columns,
+        }
+    }
+
+    /// Create a grid from rows ordered from the oldest history line to the bottom of the screen.
+    ///
+    /// Every row must have exactly `columns` cells and there must be at least `lines` rows.
+    pub fn from_rows(
+        mut rows: Vec<Row<T>>,
+        lines: usize,
+        columns: usize,
+        max_scroll_limit: usize,
+    ) -> Grid<T> {
+        // Drop the oldest history lines exceeding the scrollback limit.
+        let excess = rows.len().saturating_sub(lines + max_scroll_limit);
+        rows.drain(..excess);
+
+        Grid {
+            raw: Storage::from_rows(rows, lines),
+            max_scroll_limit,
+            display_offset: 0,
+            saved_cursor: SavedCursor::default(),
             cursor: Cursor::default(),
             lines,
             columns,
         }
     }
 
+    #[inline]
+    pub fn max_scroll_limit(&self) -> usize {
+        self.max_scroll_limit
+    }
+
     /// Update the size of the scrollback history.
-    #[allow(dead_code)]
     pub fn update_history(&mut self, history_size: usize) {
         let current_history_size = self.history_size();
         if current_history_size > history_size {
@@ -200,6 +235,13 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
             return;
         }
 
+        // Scrolling regions smaller than the screen never create scrollback history.
+        let screen_lines = self.screen_lines();
+        if region.start != 0 || region.end != screen_lines {
+            self.rotate_region_up(region, positions);
+            return;
+        }
+
         // Update display offset when not pinned to active area.
         if self.display_offset != 0 {
             self.display_offset =
@@ -209,30 +251,64 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
         // Create scrollback for the new lines.
         self.increase_scroll_limit(positions);
 
-        // Swap the lines fixed at the top to their target positions after rotation.
-        //
-        // Since we've made sure that the rotation will never rotate away the entire region, we
-        // know that the position of the fixed lines before the rotation must already be
-        // visible.
-        //
-        // We need to start from the bottom, to make sure the fixed lines aren't swapped with each
-        // other.
-        for i in (0..region.start.0).rev().map(Line::from) {
-            self.raw.swap(i, i + positions);
-        }
-
         // Rotate the entire line buffer upward.
         self.raw.rotate(-(positions as isize));
 
         // Ensure all new lines are fully cleared.
-        let screen_lines = self.screen_lines();
         for i in ((screen_lines - positions)..screen_lines).map(Line::from) {
             self.raw[i].reset(&self.cursor.template);
         }
+    }
 
-        // Swap the fixed lines at the bottom back into position.
-        for i in (region.end.0..(screen_lines as i32)).rev().map(Line::from) {
-            self.raw.swap(i, i - positions);
+    /// Move the lines of `region` up by `positions`, resetting the lines left at its bottom.
+    ///
+    /// Unlike [`Grid::scroll_up`], lines leaving the region are dropped instead of being
+    /// pushed into the scrollback history.
+    pub fn rotate_region_up<D>(&mut self, region: &Range<Line>, positions: usize)
+    where
+        T: ResetDiscriminant<D>,
+        D: PartialEq,
+    {
+        let positions = min(positions, (region.end - region.start).0 as usize);
+
+        let range = region.start.0..(region.end - positions).0;
+        for line in range.map(Line::from) {
+            self.raw.swap(line, line + positions);
+        }
+
+        let range = (region.end - positions).0..region.end.0;
+        for line in range.map(Line::from) {
+            self.raw[line].reset(&self.cursor.template);
+        }
+    }
+
+    /// Move the squares inside `rect` up by `positions`, or down when it is negative.
+    ///
+    /// Only the columns of `rect` are touched and no scrollback history is created.
+    /// Vacated squares are reset with the cursor template.
+    pub fn scroll_region(&mut self, rect: &Rect, positions: i32) {
+        let height = (rect.lines.end - rect.lines.start).0;
+        let positions = positions.clamp(-height, height);
+
+        // Copy from the top when moving up and from the bottom when moving down, so
+        // every source line is read before it is overwritten.
+        let lines = (rect.lines.start.0..rect.lines.end.0).map(Line::from);
+        let lines: Vec<Line> = if positions > 0 {
+            lines.collect()
+        } else {
+            lines.rev().collect()
+        };
+
+        for line in lines {
+            let source = line + positions;
+            for column in (rect.columns.start.0..rect.columns.end.0).map(Column) {
+                if rect.lines.contains(&source) {
+                    let square = self.raw[source][column].clone();
+                    self.raw[line][column] = square;
+                } else {
+                    self.raw[line][column].reset(&self.cursor.template);
+                }
+            }
         }
     }
 
@@ -271,7 +347,7 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
     {
         self.clear_history();
 
-        self.saved_cursor = Cursor::default();
+        self.saved_cursor = SavedCursor::default();
         self.cursor = Cursor::default();
         self.display_offset = 0;
 
@@ -315,6 +391,9 @@ impl<T> Grid<T> {
         // Explicitly purge all lines from history.
         self.raw.shrink_lines(self.history_size());
 
+        // Give the memory of the purged lines back.
+        self.raw.shrink_to_fit();
+
         // Reset display offset.
         self.display_offset = 0;
     }
@@ -377,6 +456,12 @@ impl<T> Grid<T> {
         self.display_offset
     }
 
+    /// Number of rows allocated in storage, including rows cached for reuse.
+    #[inline]
+    pub fn allocated_rows(&self) -> usize {
+        self.raw.capacity()
+    }
+
     #[inline]
     pub fn cursor_cell(&mut self) -> &mut T {
         let point = self.cursor.pos;
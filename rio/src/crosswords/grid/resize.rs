@@ -62,7 +62,7 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
         }
 
         // Move cursor down for every line pulled from history.
-        self.saved_cursor.pos.row += from_history;
+        self.saved_cursor.cursor.pos.row += from_history;
         self.cursor.pos.row += from_history;
 
         self.display_offset = self.display_offset.saturating_sub(lines_added);
@@ -89,11 +89,15 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
 
             // Clamp cursors to the new viewport size.
             self.cursor.pos.row = min(self.cursor.pos.row, Line(target as i32 - 1));
+
+            // Keep the saved cursor on the same content, as far as it stays visible.
+            let saved = self.saved_cursor.cursor.pos.row - required_scrolling;
+            self.saved_cursor.cursor.pos.row = max(saved, Line(0));
         }
 
-        // Clamp saved cursor, since only primary cursor is scrolled into viewport.
-        self.saved_cursor.pos.row =
-            min(self.saved_cursor.pos.row, Line(target as i32 - 1));
+        // Clamp saved cursor, which might sit below the cursor being scrolled into viewport.
+        self.saved_cursor.cursor.pos.row =
+            min(self.saved_cursor.cursor.pos.row, Line(target as i32 - 1));
 
         self.raw.rotate((self.lines - target) as isize);
         self.raw.shrink_visible_lines(target);
@@ -403,6 +407,7 @@ impl<T: GridSquare + Default + PartialEq + Clone> Grid<T> {
         }
 
         // Clamp the saved cursor to the grid.
-        self.saved_cursor.pos.col = min(self.saved_cursor.pos.col, Column(columns - 1));
+        self.saved_cursor.cursor.pos.col =
+            min(self.saved_cursor.cursor.pos.col, Column(columns - 1));
     }
 }
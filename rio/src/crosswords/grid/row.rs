@@ -5,13 +5,38 @@
 use crate::crosswords::grid::GridSquare;
 use crate::crosswords::square::ResetDiscriminant;
 use crate::crosswords::Column;
+use crate::crosswords::PromptMarks;
 use core::cmp::min;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp::max;
 use std::ops::{Index, IndexMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
 use std::{ptr, slice};
 
+/// Line attribute set by DECSWL, DECDWL and DECDHL.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LineAttr {
+    #[default]
+    Normal,
+    DoubleWidth,
+    /// Top half of a double-height line, also double width.
+    DoubleHeightTop,
+    /// Bottom half of a double-height line, also double width.
+    DoubleHeightBottom,
+}
+
+impl LineAttr {
+    /// Whether every square of the line is drawn two columns wide.
+    #[inline]
+    pub fn is_double_width(self) -> bool {
+        self != LineAttr::Normal
+    }
+}
+
 /// A row in the grid.
 #[derive(Default, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Row<T> {
     pub inner: Vec<T>,
 
@@ -20,6 +45,14 @@ pub struct Row<T> {
     /// This is the upper bound on the number of elements in the row, which have been modified
     /// since the last reset. All cells after this point are guaranteed to be equal.
     pub(crate) occ: usize,
+
+    /// Shell integration marks (OSC 133) attached to this line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    prompt_marks: PromptMarks,
+
+    /// Double-width and double-height rendering of this line.
+    #[cfg_attr(feature = "serde", serde(default))]
+    line_attr: LineAttr,
 }
 
 impl<T: PartialEq> PartialEq for Row<T> {
@@ -50,7 +83,12 @@ impl<T: Clone + Default> Row<T> {
             inner.set_len(columns);
         }
 
-        Row { inner, occ: 0 }
+        Row {
+            inner,
+            occ: 0,
+            prompt_marks: PromptMarks::default(),
+            line_attr: LineAttr::Normal,
+        }
     }
 
     /// Increase the number of columns in the row.
@@ -60,6 +98,9 @@ impl<T: Clone + Default> Row<T> {
             return;
         }
 
+        // New cells are default rather than a copy of the last reset template, so the old
+        // cells can no longer be assumed equal to them.
+        self.occ = self.inner.len();
         self.inner.resize_with(columns, T::default);
     }
 
@@ -109,6 +150,33 @@ impl<T: Clone + Default> Row<T> {
         }
 
         self.occ = 0;
+        self.prompt_marks = PromptMarks::default();
+        self.line_attr = LineAttr::Normal;
+    }
+
+    /// Reset the cells from `at` to the end of the row to the `template` cell.
+    ///
+    /// Like [`Row::reset`], cells after the occupied length are only touched when the
+    /// template changed.
+    #[inline]
+    pub fn reset_from<D>(&mut self, at: Column, template: &T)
+    where
+        T: ResetDiscriminant<D> + GridSquare,
+        D: PartialEq,
+    {
+        let len = self.inner.len();
+        let at = min(at.0, len);
+        let end = if self.inner[len - 1].discriminant() != template.discriminant() {
+            len
+        } else {
+            max(at, self.occ)
+        };
+
+        for item in &mut self.inner[at..end] {
+            item.reset(template);
+        }
+
+        self.occ = if end == len { at } else { min(self.occ, at) };
     }
 }
 
@@ -116,7 +184,12 @@ impl<T: Clone + Default> Row<T> {
 impl<T> Row<T> {
     #[inline]
     pub fn from_vec(vec: Vec<T>, occ: usize) -> Row<T> {
-        Row { inner: vec, occ }
+        Row {
+            inner: vec,
+            occ,
+            prompt_marks: PromptMarks::default(),
+            line_attr: LineAttr::Normal,
+        }
     }
 
     #[inline]
@@ -124,6 +197,39 @@ impl<T> Row<T> {
         self.inner.len()
     }
 
+    /// Iterate over the non-empty cells of the row along with their column.
+    #[inline]
+    pub fn occupied(&self) -> impl Iterator<Item = (Column, &T)>
+    where
+        T: GridSquare,
+    {
+        self.inner
+            .iter()
+            .enumerate()
+            .filter(|(_, square)| !square.is_empty())
+            .map(|(column, square)| (Column(column), square))
+    }
+
+    #[inline]
+    pub fn prompt_marks(&self) -> PromptMarks {
+        self.prompt_marks
+    }
+
+    #[inline]
+    pub fn prompt_marks_mut(&mut self) -> &mut PromptMarks {
+        &mut self.prompt_marks
+    }
+
+    #[inline]
+    pub fn line_attr(&self) -> LineAttr {
+        self.line_attr
+    }
+
+    #[inline]
+    pub fn set_line_attr(&mut self, attr: LineAttr) {
+        self.line_attr = attr;
+    }
+
     #[inline]
     pub fn last(&self) -> Option<&T> {
         self.inner.last()
@@ -140,8 +246,12 @@ impl<T> Row<T> {
     where
         T: GridSquare,
     {
-        self.occ += vec.len();
+        if vec.is_empty() {
+            return;
+        }
+
         self.inner.append(vec);
+        self.occ = self.inner.len();
     }
 
     #[inline]
@@ -288,7 +398,7 @@ impl<T> Index<RangeToInclusive<Column>> for Row<T> {
 impl<T> IndexMut<RangeToInclusive<Column>> for Row<T> {
     #[inline]
     fn index_mut(&mut self, index: RangeToInclusive<Column>) -> &mut [T] {
-        self.occ = max(self.occ, *index.end);
+        self.occ = max(self.occ, *index.end + 1);
         &mut self.inner[..=(index.end.0)]
     }
 }
@@ -4,7 +4,6 @@
 
 use std::cmp::{max, PartialEq};
 use std::mem;
-use std::mem::MaybeUninit;
 use std::ops::{Index, IndexMut};
 
 use super::Row;
@@ -26,6 +25,10 @@ const MAX_CACHE_SIZE: usize = 1_000;
 /// implementation is provided. Anything from [`Vec`] that should be exposed must be done so
 /// manually.
 ///
+/// Scrollback rows are kept as plain rows rather than run-length compressed, since rendering,
+/// search and selection all borrow history rows through [`Index`], which a compressed row
+/// could only serve by being decompressed on every access.
+///
 /// [`slice::rotate_left`]: https://doc.rust-lang.org/std/primitive.slice.html#method.rotate_left
 /// [`Deref`]: std::ops::Deref
 /// [`zero`]: #structfield.zero
@@ -80,6 +83,22 @@ impl<T> Storage<T> {
         }
     }
 
+    /// Create storage from rows ordered from the oldest history line to the bottom of the screen.
+    #[inline]
+    pub fn from_rows(mut rows: Vec<Row<T>>, visible_lines: usize) -> Storage<T> {
+        debug_assert!(rows.len() >= visible_lines);
+
+        // The ring buffer is indexed from the bottommost line upwards.
+        rows.reverse();
+
+        Storage {
+            len: rows.len(),
+            inner: rows,
+            zero: 0,
+            visible_lines,
+        }
+    }
+
     /// Increase the number of lines in the buffer.
     #[allow(dead_code)]
     #[inline]
@@ -129,6 +148,20 @@ impl<T> Storage<T> {
         self.inner.truncate(self.len);
     }
 
+    /// Truncate the invisible elements and release the memory they were using.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.truncate();
+
+        self.inner.shrink_to_fit();
+    }
+
+    /// Number of rows the raw buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
     /// Dynamically grow the storage buffer at runtime.
     #[inline]
     pub fn initialize(&mut self, additional_rows: usize, columns: usize)
@@ -156,37 +189,16 @@ impl<T> Storage<T> {
         self.len == 0
     }
 
-    /// Swap implementation for Row<T>.
-    ///
-    /// Exploits the known size of Row<T> to produce a slightly more efficient
-    /// swap than going through slice::swap.
+    /// Swap two lines of the buffer.
     ///
-    /// The default implementation from swap generates 8 movups and 4 movaps
-    /// instructions. This implementation achieves the swap in only 8 movups
-    /// instructions.
+    /// Only the rows themselves are moved, their squares stay in place on the heap. This keeps
+    /// scrolling a region smaller than the screen linear in the number of lines.
+    #[inline]
     pub fn swap(&mut self, a: Line, b: Line) {
-        debug_assert_eq!(mem::size_of::<Row<T>>(), mem::size_of::<usize>() * 4);
-
         let a = self.compute_index(a);
         let b = self.compute_index(b);
 
-        unsafe {
-            // Cast to a qword array to opt out of copy restrictions and avoid
-            // drop hazards. Byte array is no good here since for whatever
-            // reason LLVM won't optimized it.
-            let a_ptr = self.inner.as_mut_ptr().add(a) as *mut MaybeUninit<usize>;
-            let b_ptr = self.inner.as_mut_ptr().add(b) as *mut MaybeUninit<usize>;
-
-            // Copy 1 qword at a time.
-            //
-            // The optimizer unrolls this loop and vectorizes it.
-            let mut tmp: MaybeUninit<usize>;
-            for i in 0..4 {
-                tmp = *a_ptr.offset(i);
-                *a_ptr.offset(i) = *b_ptr.offset(i);
-                *b_ptr.offset(i) = tmp;
-            }
-        }
+        self.inner.swap(a, b);
     }
 
     /// Rotate the grid, moving all lines up/down in history.
@@ -286,9 +298,9 @@ impl<T> IndexMut<Line> for Storage<T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::crosswords::grid::row::Row;
+    use crate::crosswords::grid::row::{LineAttr, Row};
     use crate::crosswords::grid::storage::{Storage, MAX_CACHE_SIZE};
-    use crate::crosswords::{Column, Line};
+    use crate::crosswords::{Column, Line, PromptKind, PromptMarks};
 
     #[test]
     fn with_capacity() {
@@ -628,6 +640,21 @@ mod tests {
         assert_eq!(storage.len, expected.len);
     }
 
+    /// Release the hidden rows and the spare capacity of the buffer.
+    #[test]
+    fn shrink_to_fit_releases_memory() {
+        let mut storage: Storage<char> = Storage::with_capacity(1, 1);
+        storage.initialize(10, 1);
+        assert!(storage.capacity() > 11);
+
+        storage.shrink_lines(10);
+        storage.shrink_to_fit();
+
+        assert_eq!(storage.len(), 1);
+        assert_eq!(storage.inner.len(), 1);
+        assert_eq!(storage.capacity(), 1);
+    }
+
     /// First shrink the buffer and then grow it again.
     ///
     /// Before:
@@ -815,6 +842,41 @@ mod tests {
         assert!(storage.zero < storage.inner.len());
     }
 
+    /// Swapping lines moves the whole row, including its prompt marks.
+    #[test]
+    fn swap_moves_whole_rows() {
+        let mut marks = PromptMarks::default();
+        marks.insert(PromptKind::PromptStart);
+        let mut storage: Storage<char> = Storage::with_capacity(3, 1);
+        storage.rotate(1);
+        storage[Line(0)] = filled_row('0');
+        storage[Line(2)] = filled_row('2');
+        *storage[Line(2)].prompt_marks_mut() = marks;
+
+        storage.swap(Line(0), Line(2));
+
+        assert_eq!(storage[Line(0)][Column(0)], '2');
+        assert_eq!(storage[Line(0)].prompt_marks(), marks);
+        assert_eq!(storage[Line(2)][Column(0)], '0');
+        assert_eq!(storage[Line(2)].prompt_marks(), PromptMarks::default());
+    }
+
+    /// Swapping lines moves their line attributes along with the squares.
+    #[test]
+    fn swap_moves_line_attributes() {
+        let mut storage: Storage<char> = Storage::with_capacity(3, 1);
+        storage[Line(0)] = filled_row('0');
+        storage[Line(1)] = filled_row('1');
+        storage[Line(1)].set_line_attr(LineAttr::DoubleWidth);
+
+        storage.swap(Line(0), Line(1));
+
+        assert_eq!(storage[Line(0)][Column(0)], '1');
+        assert_eq!(storage[Line(0)].line_attr(), LineAttr::DoubleWidth);
+        assert_eq!(storage[Line(1)][Column(0)], '0');
+        assert_eq!(storage[Line(1)].line_attr(), LineAttr::Normal);
+    }
+
     fn filled_row(content: char) -> Row<char> {
         let mut row = Row::new(1);
         row[Column(0)] = content;
@@ -4,7 +4,8 @@
 
 use super::*;
 
-use crate::crosswords::square::Square;
+use crate::crosswords::square::{LineLength, Square};
+use rio_config::colors::{AnsiColor, NamedColor};
 
 impl GridSquare for usize {
     fn is_empty(&self) -> bool {
@@ -56,7 +57,80 @@ fn scroll_up() {
     assert_eq!(grid[Line(9)].occ, 0);
 }
 
+// Scrolling a region smaller than the screen keeps the history untouched.
+#[test]
+fn scroll_up_region_without_history() {
+    let mut grid = Grid::<usize>::new(5, 1, 10);
+    for i in 0..5 {
+        grid[Line(i as i32)][Column(0)] = i + 1;
+    }
+
+    grid.scroll_up::<usize>(&(Line(0)..Line(3)), 1);
+
+    assert_eq!(grid.history_size(), 0);
+    assert_eq!(grid[Line(0)][Column(0)], 2);
+    assert_eq!(grid[Line(1)][Column(0)], 3);
+    assert_eq!(grid[Line(2)][Column(0)], 0);
+    assert_eq!(grid[Line(3)][Column(0)], 4);
+    assert_eq!(grid[Line(4)][Column(0)], 5);
+
+    grid.scroll_up::<usize>(&(Line(1)..Line(5)), 2);
+
+    assert_eq!(grid.history_size(), 0);
+    assert_eq!(grid[Line(0)][Column(0)], 2);
+    assert_eq!(grid[Line(1)][Column(0)], 4);
+    assert_eq!(grid[Line(2)][Column(0)], 5);
+    assert_eq!(grid[Line(3)][Column(0)], 0);
+    assert_eq!(grid[Line(4)][Column(0)], 0);
+}
+
 // Scroll down moves lines downward.
+// Scrolling a rectangle moves only the squares inside of it.
+#[test]
+fn scroll_region_within_margins() {
+    let mut grid = Grid::<usize>::new(4, 4, 0);
+    for line in 0..4 {
+        for column in 0..4 {
+            grid[Line(line)][Column(column)] = 10 * line as usize + column + 1;
+        }
+    }
+
+    let rect = Rect {
+        lines: Line(1)..Line(4),
+        columns: Column(1)..Column(3),
+    };
+    grid.scroll_region(&rect, 1);
+
+    let rows = |grid: &Grid<usize>| -> Vec<Vec<usize>> {
+        (0..4).map(|line| grid[Line(line)][..].to_vec()).collect()
+    };
+    assert_eq!(
+        rows(&grid),
+        vec![
+            vec![1, 2, 3, 4],
+            vec![11, 22, 23, 14],
+            vec![21, 32, 33, 24],
+            vec![31, 0, 0, 34],
+        ]
+    );
+    assert_eq!(grid.history_size(), 0);
+
+    grid.scroll_region(&rect, -2);
+    assert_eq!(
+        rows(&grid),
+        vec![
+            vec![1, 2, 3, 4],
+            vec![11, 0, 0, 14],
+            vec![21, 0, 0, 24],
+            vec![31, 22, 23, 34],
+        ]
+    );
+
+    // Scrolling by more than the height clears the rectangle.
+    grid.scroll_region(&rect, 10);
+    assert_eq!(grid[Line(3)][..].to_vec(), vec![31, 0, 0, 34]);
+}
+
 #[test]
 fn scroll_down() {
     let mut grid = Grid::<usize>::new(10, 1, 0);
@@ -312,6 +386,95 @@ fn grow_reflow_multiline() {
     }
 }
 
+#[test]
+fn grow_reflow_leading_wide_char_spacer() {
+    let mut grid = Grid::<Square>::new(2, 3, 0);
+    grid[Line(0)][Column(0)] = cell('1');
+    grid[Line(0)][Column(1)] = cell('2');
+    grid[Line(0)][Column(2)] = wrap_cell(' ');
+    grid[Line(0)][Column(2)]
+        .flags
+        .insert(Flags::LEADING_WIDE_CHAR_SPACER);
+    grid[Line(1)][Column(0)] = cell('\u{4f60}');
+    grid[Line(1)][Column(0)].flags.insert(Flags::WIDE_CHAR);
+    grid[Line(1)][Column(1)]
+        .flags
+        .insert(Flags::WIDE_CHAR_SPACER);
+
+    // The leading spacer counts as content of the wrapped line.
+    assert_eq!(grid[Line(0)].line_length(), Column(3));
+
+    grid.resize(true, 2, 5);
+
+    assert_eq!(grid[Line(0)][Column(0)], cell('1'));
+    assert_eq!(grid[Line(0)][Column(1)], cell('2'));
+    assert_eq!(grid[Line(0)][Column(2)].c, '\u{4f60}');
+    assert_eq!(grid[Line(0)][Column(2)].flags, Flags::WIDE_CHAR);
+    assert_eq!(grid[Line(0)][Column(3)].flags, Flags::WIDE_CHAR_SPACER);
+    assert_eq!(grid[Line(0)][Column(4)], Square::default());
+
+    // The wide char must not be duplicated on the next line.
+    for c in 0..5 {
+        assert_eq!(grid[Line(1)][Column(c)], Square::default());
+    }
+}
+
+#[test]
+fn reflow_wrapped_paragraph_preserves_glyphs() {
+    let mut grid = Grid::<Square>::new(3, 6, 10);
+    for (i, c) in "abcdef".chars().enumerate() {
+        grid[Line(0)][Column(i)] = cell(c);
+    }
+    grid[Line(0)][Column(1)].flags.insert(Flags::BOLD);
+    grid[Line(0)][Column(2)].push_zerowidth('\u{301}');
+    grid[Line(0)][Column(5)].flags.insert(Flags::WRAPLINE);
+    grid[Line(1)][Column(0)] = cell('g');
+    grid[Line(1)][Column(1)] = cell('\u{4f60}');
+    grid[Line(1)][Column(1)].flags.insert(Flags::WIDE_CHAR);
+    grid[Line(1)][Column(2)]
+        .flags
+        .insert(Flags::WIDE_CHAR_SPACER);
+    // The cursor sits below the paragraph, as after printing it.
+    grid.cursor.pos = Pos::new(Line(2), Column(0));
+
+    let paragraph = "abc\u{301}defg\u{4f60}";
+    let text = |grid: &Grid<Square>| {
+        let rows = (grid.topmost_line().0..=grid.bottommost_line().0)
+            .map(|line| &grid[Line(line)]);
+        crate::crosswords::text::to_plain_text(rows)
+            .trim_end()
+            .to_string()
+    };
+    let squares = |grid: &Grid<Square>| {
+        (grid.topmost_line().0..=grid.bottommost_line().0)
+            .flat_map(|line| grid[Line(line)][..].to_vec())
+            .collect::<Vec<Square>>()
+    };
+
+    // Narrowing splits "efg" from the wide char, which must not straddle the wrap.
+    grid.resize(true, 3, 4);
+
+    assert_eq!(text(&grid), paragraph);
+    let spacer = squares(&grid)
+        .into_iter()
+        .find(|square| square.flags.contains(Flags::LEADING_WIDE_CHAR_SPACER))
+        .expect("leading wide char spacer");
+    assert!(spacer.flags.contains(Flags::WRAPLINE));
+
+    // Widening joins the paragraph back into a single row.
+    grid.resize(true, 3, 10);
+
+    assert_eq!(text(&grid), paragraph);
+    let squares = squares(&grid);
+    assert!(squares.iter().all(|square| !square
+        .flags
+        .intersects(Flags::WRAPLINE | Flags::LEADING_WIDE_CHAR_SPACER)));
+    let bold = squares.iter().find(|square| square.c == 'b').unwrap();
+    assert!(bold.flags.contains(Flags::BOLD));
+    let accented = squares.iter().find(|square| square.c == 'c').unwrap();
+    assert_eq!(accented.zerowidth(), Some(&['\u{301}'][..]));
+}
+
 #[test]
 fn grow_reflow_disabled() {
     let mut grid = Grid::<Square>::new(2, 2, 0);
@@ -353,6 +516,66 @@ fn shrink_reflow_disabled() {
     assert_eq!(grid[Line(0)][Column(1)], cell('2'));
 }
 
+// Occupancy covers squares changed through a mutable reference, even without content.
+#[test]
+fn occupancy_tracks_flag_on_trailing_space() {
+    let mut row = Row::<Square>::new(10);
+    assert_eq!(row.line_length(), Column(0));
+
+    row[Column(6)].flags_mut().insert(Flags::UNDERLINE);
+    assert_eq!(row.occ, 7);
+    assert_eq!(row.line_length(), Column(7));
+
+    row[..=Column(7)][7].flags_mut().insert(Flags::OVERLINE);
+    assert_eq!(row.occ, 8);
+    assert_eq!(row.line_length(), Column(8));
+
+    row.reset(&Square::default());
+    assert_eq!(row.occ, 0);
+    assert_eq!(row.line_length(), Column(0));
+}
+
+#[test]
+fn occupancy_after_append_and_grow() {
+    let mut row = Row::<Square>::new(2);
+    row.append(&mut vec![cell('a'), Square::default()]);
+    assert_eq!(row.occ, 4);
+    assert_eq!(row.line_length(), Column(3));
+
+    // Growing after a colored reset must not leave the colored squares behind.
+    let colored = Square {
+        bg: AnsiColor::Named(NamedColor::Red),
+        ..Square::default()
+    };
+    let mut row = Row::<Square>::new(2);
+    row.reset(&colored);
+    row.grow(4);
+    row.reset(&Square::default());
+    assert!(row[..].iter().all(|square| *square == Square::default()));
+}
+
+#[test]
+fn reset_from_keeps_leading_squares() {
+    let mut row = Row::<Square>::new(6);
+    for (column, c) in "abcd".chars().enumerate() {
+        row[Column(column)] = cell(c);
+    }
+
+    row.reset_from(Column(2), &Square::default());
+    assert_eq!(row.occ, 2);
+    assert_eq!(row.line_length(), Column(2));
+    assert_eq!(row[Column(1)], cell('b'));
+
+    // A different template resets every square up to the end of the row.
+    let colored = Square {
+        bg: AnsiColor::Named(NamedColor::Red),
+        ..Square::default()
+    };
+    row.reset_from(Column(4), &colored);
+    assert_eq!(row[Column(5)].bg, colored.bg);
+    assert_eq!(row[Column(3)].bg, AnsiColor::Named(NamedColor::Background));
+}
+
 // https://github.com/rust-lang/rust-clippy/pull/6375
 #[allow(clippy::all)]
 fn cell(c: char) -> Square {
@@ -366,3 +589,30 @@ fn wrap_cell(c: char) -> Square {
     cell.flags.insert(Flags::WRAPLINE);
     cell
 }
+
+// Run with `cargo test --release -- --ignored --nocapture bench_scroll_throughput`.
+#[test]
+#[ignore]
+fn bench_scroll_throughput() {
+    const SCROLLS: usize = 1_000_000;
+    let mut grid = Grid::<Square>::new(24, 80, 10_000);
+
+    let full_screen = Line(0)..Line(24);
+    let start = std::time::Instant::now();
+    for _ in 0..SCROLLS {
+        grid.scroll_up(&full_screen, 1);
+    }
+    let full_screen_time = start.elapsed();
+
+    // A DECSTBM region, which swaps rows instead of rotating the buffer.
+    let region = Line(2)..Line(22);
+    let start = std::time::Instant::now();
+    for _ in 0..SCROLLS {
+        grid.scroll_up(&region, 1);
+    }
+    let region_time = start.elapsed();
+
+    println!(
+        "{SCROLLS} scrolls, full screen: {full_screen_time:?}, region: {region_time:?}"
+    );
+}
@@ -0,0 +1,177 @@
+// Export of grid rows as styled HTML, used to copy terminal content with its colors.
+
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::square::{Flags, LineLength, Square};
+use rio_config::colors::term::List;
+use rio_config::colors::{AnsiColor, ColorArray, NamedColor};
+use std::fmt::Write;
+
+/// Render `rows` as an HTML `<pre>` block.
+///
+/// Runs of identically styled squares are coalesced into a single `<span>` with inline CSS,
+/// colors are resolved through `palette` and the default background is left to the page.
+#[allow(dead_code)]
+pub fn to_html(rows: &[Row<Square>], palette: &List) -> String {
+    let mut html = String::from("<pre>");
+
+    for (index, row) in rows.iter().enumerate() {
+        if index > 0 {
+            html.push('\n');
+        }
+
+        let mut run: Option<(String, String)> = None;
+        for square in &row[..row.line_length()] {
+            let style = style(square, palette);
+
+            let mut text = String::new();
+            if square.flags.contains(Flags::HIDDEN) {
+                push_hidden(square, &mut text);
+            } else {
+                square.write_text_to(&mut text);
+            }
+
+            match &mut run {
+                Some((run_style, run_text)) if *run_style == style => {
+                    run_text.push_str(&text)
+                }
+                _ => {
+                    if let Some((run_style, run_text)) = run.replace((style, text)) {
+                        push_span(&mut html, &run_style, &run_text);
+                    }
+                }
+            }
+        }
+
+        if let Some((run_style, run_text)) = run {
+            push_span(&mut html, &run_style, &run_text);
+        }
+    }
+
+    html.push_str("</pre>");
+    html
+}
+
+/// Inline CSS for a square, with `INVERSE` already applied to its colors.
+fn style(square: &Square, palette: &List) -> String {
+    let (fg, bg) = square.resolved_colors();
+
+    let mut style = format!("color:{}", hex(palette.resolve(fg)));
+    if bg != AnsiColor::Named(NamedColor::Background) {
+        let _ = write!(style, ";background-color:{}", hex(palette.resolve(bg)));
+    }
+    if square.flags.contains(Flags::BOLD) {
+        style.push_str(";font-weight:bold");
+    }
+    if square.flags.contains(Flags::ITALIC) {
+        style.push_str(";font-style:italic");
+    }
+    if square.underline_style().is_some() {
+        style.push_str(";text-decoration:underline");
+    }
+
+    style
+}
+
+/// Hidden squares keep their width but none of their content.
+fn push_hidden(square: &Square, text: &mut String) {
+    if square
+        .flags
+        .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+    {
+        return;
+    }
+
+    text.push(' ');
+    if square.is_wide() {
+        text.push(' ');
+    }
+}
+
+fn push_span(html: &mut String, style: &str, text: &str) {
+    let _ = write!(html, "<span style=\"{style}\">");
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+    html.push_str("</span>");
+}
+
+fn hex(color: ColorArray) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2])
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crosswords::pos::Column;
+    use rio_config::colors::term::TermColors;
+
+    fn palette() -> List {
+        let mut palette = List::from(&TermColors::default());
+        palette[NamedColor::Red] = [1., 0., 0., 1.];
+        palette[NamedColor::Foreground] = [1., 1., 1., 1.];
+        palette[NamedColor::Background] = [0., 0., 0., 1.];
+        palette
+    }
+
+    fn row(text: &str, template: Square) -> Row<Square> {
+        let mut row = Row::new(text.chars().count());
+        for (column, c) in text.chars().enumerate() {
+            row[Column(column)] = Square {
+                c,
+                ..template.clone()
+            };
+        }
+        row
+    }
+
+    #[test]
+    fn bold_red_word_on_default_background() {
+        let mut row = row("hi x", Square::default());
+        for column in 0..2 {
+            row[Column(column)].fg = AnsiColor::Named(NamedColor::Red);
+            row[Column(column)].flags = Flags::BOLD;
+        }
+
+        assert_eq!(
+            to_html(&[row], &palette()),
+            "<pre><span style=\"color:#ff0000;font-weight:bold\">hi</span>\
+             <span style=\"color:#ffffff\"> x</span></pre>"
+        );
+    }
+
+    #[test]
+    fn inverse_hidden_and_escaping() {
+        let inverse = row(
+            "<&>",
+            Square {
+                flags: Flags::INVERSE,
+                ..Square::default()
+            },
+        );
+        let hidden = row(
+            "ab",
+            Square {
+                flags: Flags::HIDDEN,
+                ..Square::default()
+            },
+        );
+
+        assert_eq!(
+            to_html(&[inverse, hidden], &palette()),
+            "<pre><span style=\"color:#000000;background-color:#ffffff\">&lt;&amp;&gt;</span>\n\
+             <span style=\"color:#000000\">  </span></pre>"
+        );
+    }
+}
@@ -0,0 +1,161 @@
+// Hit-testing of OSC 8 hyperlinks over rows, used to underline and open hovered links.
+
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::pos::{Column, Line, Pos};
+use crate::crosswords::square::{Hyperlink, Square};
+use crate::crosswords::text;
+use std::sync::Arc;
+
+/// URI of the hyperlink on the square at `pos`, the first row being at `Line(0)`.
+#[allow(dead_code)]
+pub fn hyperlink_at(rows: &[Row<Square>], pos: Pos) -> Option<Arc<str>> {
+    hyperlink_of(rows, pos).map(|hyperlink| Arc::from(hyperlink.uri()))
+}
+
+/// First and last square of the hyperlink on the square at `pos`.
+///
+/// The link goes on while the neighbouring squares hold the same hyperlink, following wrapped
+/// rows, so a link broken by the line width is covered whole.
+#[allow(dead_code)]
+pub fn hyperlink_range(rows: &[Row<Square>], pos: Pos) -> Option<(Pos, Pos)> {
+    let hyperlink = hyperlink_of(rows, pos)?;
+    let is_same_link = |pos: &Pos| hyperlink_of(rows, *pos).as_ref() == Some(&hyperlink);
+
+    let mut start = pos;
+    while let Some(previous) = previous_pos(rows, start).filter(is_same_link) {
+        start = previous;
+    }
+
+    let mut end = pos;
+    while let Some(next) = next_pos(rows, end).filter(is_same_link) {
+        end = next;
+    }
+
+    Some((start, end))
+}
+
+fn hyperlink_of(rows: &[Row<Square>], pos: Pos) -> Option<Hyperlink> {
+    let row = rows.get(usize::try_from(pos.row.0).ok()?)?;
+    row.inner.get(pos.col.0)?.hyperlink()
+}
+
+/// Square before `pos`, going back to the previous row only when it wraps.
+fn previous_pos(rows: &[Row<Square>], pos: Pos) -> Option<Pos> {
+    if pos.col > 0 {
+        return Some(Pos::new(pos.row, pos.col - 1));
+    }
+
+    let row = rows.get(usize::try_from(pos.row.0 - 1).ok()?)?;
+    if !text::is_wrapped(row) {
+        return None;
+    }
+
+    Some(Pos::new(Line(pos.row.0 - 1), Column(row.len() - 1)))
+}
+
+/// Square after `pos`, going on to the next row only when this one wraps.
+fn next_pos(rows: &[Row<Square>], pos: Pos) -> Option<Pos> {
+    let line = usize::try_from(pos.row.0).ok()?;
+    let row = rows.get(line)?;
+    if pos.col + 1 < row.len() {
+        return Some(Pos::new(pos.row, pos.col + 1));
+    }
+
+    if !text::is_wrapped(row) || line + 1 >= rows.len() {
+        return None;
+    }
+
+    Some(Pos::new(Line(pos.row.0 + 1), Column(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crosswords::square::Flags;
+
+    fn pos(line: i32, column: usize) -> Pos {
+        Pos::new(Line(line), Column(column))
+    }
+
+    fn row(text: &str) -> Row<Square> {
+        let mut row = Row::<Square>::new(text.chars().count());
+        for (column, c) in text.chars().enumerate() {
+            row[Column(column)].c = c;
+        }
+        row
+    }
+
+    fn link(rows: &mut [Row<Square>], start: Pos, end: Pos, hyperlink: &Hyperlink) {
+        for line in start.row.0..=end.row.0 {
+            let row = &mut rows[line as usize];
+            let first = if line == start.row.0 { start.col.0 } else { 0 };
+            let last = if line == end.row.0 {
+                end.col.0
+            } else {
+                row.len() - 1
+            };
+            for column in first..=last {
+                row[Column(column)].set_hyperlink(Some(hyperlink.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn hyperlink_under_point() {
+        let mut rows = vec![row("go rio now")];
+        let rio = Hyperlink::new(None, "https://rio.dev");
+        link(&mut rows, pos(0, 3), pos(0, 5), &rio);
+
+        let uri: Option<Arc<str>> = Some(Arc::from("https://rio.dev"));
+        assert_eq!(hyperlink_at(&rows, pos(0, 4)), uri);
+        assert_eq!(hyperlink_at(&rows, pos(0, 3)), uri);
+        assert_eq!(hyperlink_at(&rows, pos(0, 5)), uri);
+
+        assert_eq!(hyperlink_at(&rows, pos(0, 2)), None);
+        assert_eq!(hyperlink_at(&rows, pos(0, 6)), None);
+        assert_eq!(hyperlink_at(&rows, pos(0, 20)), None);
+        assert_eq!(hyperlink_at(&rows, pos(1, 0)), None);
+        assert_eq!(hyperlink_at(&rows, pos(-1, 0)), None);
+    }
+
+    #[test]
+    fn hyperlink_range_covers_the_whole_link() {
+        let mut rows = vec![row("go rio now")];
+        let rio = Hyperlink::new(None, "https://rio.dev");
+        let now = Hyperlink::new(None, "https://rio.dev/now");
+        link(&mut rows, pos(0, 3), pos(0, 5), &rio);
+        link(&mut rows, pos(0, 6), pos(0, 9), &now);
+
+        for column in 3..=5 {
+            assert_eq!(
+                hyperlink_range(&rows, pos(0, column)),
+                Some((pos(0, 3), pos(0, 5)))
+            );
+        }
+        // The adjacent link is a different one.
+        assert_eq!(
+            hyperlink_range(&rows, pos(0, 6)),
+            Some((pos(0, 6), pos(0, 9)))
+        );
+        assert_eq!(hyperlink_range(&rows, pos(0, 2)), None);
+    }
+
+    #[test]
+    fn hyperlink_range_follows_wrapped_rows() {
+        let mut rows = vec![row("a ri"), row("o b "), row("rio ")];
+        let rio = Hyperlink::new(None, "https://rio.dev");
+        link(&mut rows, pos(0, 2), pos(1, 0), &rio);
+        link(&mut rows, pos(2, 0), pos(2, 2), &rio);
+        rows[0][Column(3)].flags.insert(Flags::WRAPLINE);
+
+        let wrapped = Some((pos(0, 2), pos(1, 0)));
+        assert_eq!(hyperlink_range(&rows, pos(0, 3)), wrapped);
+        assert_eq!(hyperlink_range(&rows, pos(1, 0)), wrapped);
+
+        // The same link after a hard line break is a separate run.
+        assert_eq!(
+            hyperlink_range(&rows, pos(2, 1)),
+            Some((pos(2, 0), pos(2, 2)))
+        );
+    }
+}
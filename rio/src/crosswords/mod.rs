@@ -16,40 +16,48 @@
 
 pub mod attr;
 pub mod grid;
+pub mod html;
+pub mod hyperlink;
 pub mod pos;
+pub mod search;
 pub mod square;
+pub mod text;
 pub mod vi_mode;
+pub mod width;
 
 use crate::ansi::{
-    mode::Mode as AnsiMode, ClearMode, CursorShape, KeyboardModes,
-    KeyboardModesApplyBehavior, LineClearMode, TabulationClearMode,
+    mode::Mode as AnsiMode, ClearMode, CursorShape, CursorStyle, KeyboardModes,
+    KeyboardModesApplyBehavior, LineClearMode, Rectangle, TabulationClearMode,
 };
 use crate::clipboard::ClipboardType;
-use crate::crosswords::grid::{BidirectionalIterator, Dimensions, Grid, Scroll};
+use crate::crosswords::grid::{
+    BidirectionalIterator, Dimensions, Grid, GridIterator, Rect, Scroll,
+};
 use crate::event::{EventListener, RioEvent};
 use crate::performer::handler::Handler;
-use crate::selection::{Selection, SelectionRange, SelectionType};
+use crate::selection::{Selection, SelectionRange};
 use attr::*;
 use base64::{engine::general_purpose, Engine as _};
 use bitflags::bitflags;
-use grid::row::Row;
+use grid::row::{LineAttr, Row};
 use log::{debug, info, warn};
 use pos::{
-    Boundary, CharsetIndex, Column, Cursor, CursorState, Direction, Line, Pos, Side,
-};
-use rio_config::colors::{
-    self,
-    term::{List, TermColors},
-    AnsiColor, ColorRgb,
+    Boundary, CharsetIndex, Column, Cursor, CursorState, Direction, Line, Pos,
+    SavedCursor, Side,
 };
+use rio_config::colors::{self, term::TermColors, ColorRgb};
+use rio_config::ResetMode;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use square::{Hyperlink, LineLength, Square};
 use std::mem;
-use std::ops::{Index, IndexMut, Range};
+use std::ops::{Index, IndexMut, Range, RangeInclusive};
 use std::option::Option;
 use std::ptr;
 use std::sync::Arc;
-use unicode_width::UnicodeWidthChar;
+use unicode_segmentation::UnicodeSegmentation;
 use vi_mode::{ViModeCursor, ViMotion};
+use width::WidthCache;
 use winit::window::WindowId;
 
 pub type NamedColor = colors::NamedColor;
@@ -59,7 +67,7 @@ pub const MIN_LINES: usize = 1;
 const BRACKET_PAIRS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
 
 bitflags! {
-    #[derive(Debug, Copy, Clone)]
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
      pub struct Mode: u32 {
         const NONE                             = 0;
         const SHOW_CURSOR                      = 0b0000_0000_0000_0000_0000_0001;
@@ -86,6 +94,8 @@ bitflags! {
         const KEYBOARD_REPORT_ALTERNATE_KEYS   = 0b0001_0000_0000_0000_0000_0000;
         const KEYBOARD_REPORT_ALL_KEYS_AS_ESC  = 0b0010_0000_0000_0000_0000_0000;
         const KEYBOARD_REPORT_ASSOCIATED_TEXT  = 0b0100_0000_0000_0000_0000_0000;
+        const GRAPHEME_CLUSTERING              = 0b1000_0000_0000_0000_0000_0000;
+        const LEFT_RIGHT_MARGIN                = 0b1_0000_0000_0000_0000_0000_0000;
         const KEYBOARD_PROTOCOL = Self::KEYBOARD_DISAMBIGUATE_ESC_CODES.bits()
                                 | Self::KEYBOARD_REPORT_EVENT_TYPES.bits()
                                 | Self::KEYBOARD_REPORT_ALTERNATE_KEYS.bits()
@@ -283,6 +293,26 @@ impl TabStops {
         }
     }
 
+    /// First tabstop after `column`, or the last column when there is none.
+    #[inline]
+    fn next_stop(&self, column: Column) -> Column {
+        (column.0 + 1..self.tabs.len())
+            .map(Column)
+            .find(|&column| self[column])
+            .unwrap_or(Column(self.tabs.len() - 1))
+            .max(column)
+    }
+
+    /// Last tabstop before `column`, or `column` itself when there is none.
+    #[inline]
+    fn prev_stop(&self, column: Column) -> Column {
+        (0..column.0)
+            .rev()
+            .map(Column)
+            .find(|&column| self[column])
+            .unwrap_or(column)
+    }
+
     /// Increase tabstop capacity.
     #[inline]
     fn resize(&mut self, columns: usize) {
@@ -336,6 +366,149 @@ const TITLE_STACK_MAX_DEPTH: usize = 4096;
 // Max size of the keyboard modes.
 const KEYBOARD_MODE_STACK_MAX_DEPTH: usize = 16384;
 
+/// Memory accounting for the squares stored in the grid, including scrollback.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GridMemoryStats {
+    /// Number of squares in the grid.
+    pub total_squares: usize,
+    /// Number of squares holding an `Arc<CellExtra>`.
+    pub squares_with_extra: usize,
+    /// Total zerowidth characters stored across all squares.
+    pub zerowidth_chars: usize,
+    /// Squares whose extra storage is shared with another owner.
+    pub shared_extras: usize,
+    /// Squares whose extra storage is uniquely owned.
+    pub unique_extras: usize,
+    /// Number of rows allocated in storage, including rows cached for reuse.
+    pub allocated_rows: usize,
+}
+
+/// Run a grid operation which resets cells using the erase template of the current reset mode
+/// in place of the cursor template.
+#[inline]
+fn with_erase_template<R>(
+    grid: &mut Grid<Square>,
+    mode: ResetMode,
+    f: impl FnOnce(&mut Grid<Square>) -> R,
+) -> R {
+    let erase = grid.cursor.template.erase_template(mode);
+    let template = mem::replace(&mut grid.cursor.template, erase);
+    let result = f(grid);
+    grid.cursor.template = template;
+    result
+}
+
+/// Widen the erased columns `left..right` of `row` so that no wide char keeps only one half.
+fn erase_bounds(row: &Row<Square>, left: Column, right: Column) -> (Column, Column) {
+    let left = if left > 0 && row[left].flags.contains(square::Flags::WIDE_CHAR_SPACER) {
+        left - 1
+    } else {
+        left
+    };
+
+    let right = if right > left
+        && right.0 < row.len()
+        && row[right - 1].flags.contains(square::Flags::WIDE_CHAR)
+    {
+        right + 1
+    } else {
+        right
+    };
+
+    (left, right)
+}
+
+/// Semantic prompt zones reported by the shell through OSC 133.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PromptKind {
+    /// `A`: start of the prompt.
+    PromptStart,
+    /// `B`: start of the command typed by the user.
+    CommandStart,
+    /// `C`: start of the command output.
+    OutputStart,
+    /// `D`: the command finished.
+    CommandEnd,
+}
+
+impl PromptKind {
+    const ALL: [PromptKind; 4] = [
+        PromptKind::PromptStart,
+        PromptKind::CommandStart,
+        PromptKind::OutputStart,
+        PromptKind::CommandEnd,
+    ];
+}
+
+/// Shell integration marks stored on a line.
+///
+/// A line usually holds several kinds of marks, like the prompt and the command typed
+/// after it, so every kind seen on the line is kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PromptMarks {
+    /// Bitset of the kinds seen on the line, indexed by their discriminant.
+    kinds: u8,
+    /// Exit code reported by the last `D` mark on the line.
+    pub exit_code: Option<i32>,
+}
+
+impl PromptMarks {
+    #[inline]
+    pub fn insert(&mut self, kind: PromptKind) {
+        self.kinds |= 1 << kind as u8;
+    }
+
+    #[inline]
+    pub fn contains(&self, kind: PromptKind) -> bool {
+        self.kinds & (1 << kind as u8) != 0
+    }
+
+    /// Kinds seen on the line, in the order a shell emits them.
+    pub fn kinds(self) -> impl Iterator<Item = PromptKind> {
+        PromptKind::ALL
+            .into_iter()
+            .filter(move |kind| self.contains(*kind))
+    }
+}
+
+/// Contents of the active grid, including scrollback, used to persist and restore a session.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GridSnapshot {
+    pub columns: usize,
+    pub screen_lines: usize,
+    /// Rows from the oldest history line to the bottom of the screen.
+    pub rows: Vec<Row<Square>>,
+    pub cursor: Pos,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The snapshot dimensions are below the minimum grid size.
+    InvalidDimensions { columns: usize, screen_lines: usize },
+    /// A row does not match the snapshot column count.
+    InvalidRow { index: usize, columns: usize },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::InvalidDimensions {
+                columns,
+                screen_lines,
+            } => write!(
+                f,
+                "invalid snapshot dimensions: {columns} columns and {screen_lines} lines"
+            ),
+            SnapshotError::InvalidRow { index, columns } => {
+                write!(f, "snapshot row {index} has {columns} columns")
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Crosswords<U>
 where
@@ -348,17 +521,31 @@ where
     pub grid: Grid<Square>,
     inactive_grid: Grid<Square>,
     scroll_region: Range<Line>,
+    /// Left and right margins, only honored while DECLRMM is enabled.
+    margins: Range<Column>,
     tabs: TabStops,
     event_proxy: U,
     pub selection: Option<Selection>,
     #[allow(dead_code)]
-    colors: List,
+    colors: TermColors,
     pub title: String,
     damage: TermDamageState,
+    /// Configured cursor shape, used unless the application asks for another one.
     pub cursor_shape: CursorShape,
+    /// Configured cursor blinking, used unless the application asks otherwise.
     pub blinking_cursor: bool,
+    /// Cursor style requested through DECSCUSR, overriding the configured one.
+    cursor_style: Option<CursorStyle>,
+    pub reset_mode: ResetMode,
+    /// Copy tabs as the spaces they cover instead of a literal `\t`.
+    pub expand_tabs_on_copy: bool,
+    /// Largest base64 payload accepted by an OSC 52 clipboard write.
+    pub osc52_max_size: usize,
+    /// Answer OSC 52 clipboard reads with the clipboard content.
+    pub osc52_read: bool,
     window_id: WindowId,
     title_stack: Vec<String>,
+    width_cache: WidthCache,
 
     // The stack for the keyboard modes.
     keyboard_mode_stack: Vec<KeyboardModes>,
@@ -378,9 +565,8 @@ impl<U: EventListener> Crosswords<U> {
         let alt = Grid::new(rows, cols, 0);
 
         let scroll_region = Line(0)..Line(rows as i32);
-        let semantic_escape_chars = String::from(",│`|:\"' ()[]{}<>\t");
-        let term_colors = TermColors::default();
-        let colors = List::from(&term_colors);
+        let semantic_escape_chars = rio_config::defaults::default_word_separators();
+        let colors = TermColors::default();
 
         Crosswords {
             vi_mode_cursor: ViModeCursor::new(grid.cursor.pos),
@@ -390,6 +576,7 @@ impl<U: EventListener> Crosswords<U> {
             inactive_grid: alt,
             active_charset: CharsetIndex::default(),
             scroll_region,
+            margins: Column(0)..Column(cols),
             event_proxy,
             colors,
             title: String::from(""),
@@ -401,8 +588,14 @@ impl<U: EventListener> Crosswords<U> {
             damage: TermDamageState::new(cols, rows),
             cursor_shape: CursorShape::Block,
             blinking_cursor: false,
+            cursor_style: None,
+            reset_mode: ResetMode::default(),
+            expand_tabs_on_copy: false,
+            osc52_max_size: 4 * 1024 * 1024,
+            osc52_read: false,
             window_id,
             title_stack: Default::default(),
+            width_cache: WidthCache::default(),
             keyboard_mode_stack: Default::default(),
             inactive_keyboard_mode_stack: Default::default(),
         }
@@ -412,11 +605,166 @@ impl<U: EventListener> Crosswords<U> {
         self.damage.is_fully_damaged = true;
     }
 
+    /// Square at `pos` for mutation, damaging it.
+    #[inline]
+    fn cell_mut(&mut self, pos: Pos) -> &mut Square {
+        // Squares in the history have no damage, they are redrawn once scrolled into view.
+        if pos.row >= 0 {
+            self.damage.damage_point(pos);
+        }
+        &mut self.grid[pos.row][pos.col]
+    }
+
+    /// Flags of the square at `pos` for mutation, damaging it.
+    #[inline]
+    fn flags_mut(&mut self, pos: Pos) -> &mut square::Flags {
+        &mut self.cell_mut(pos).flags
+    }
+
+    /// Columns between the left and right margins, the whole line unless DECLRMM is set.
+    fn horizontal_margins(&self) -> Range<Column> {
+        if self.mode.contains(Mode::LEFT_RIGHT_MARGIN) {
+            self.margins.clone()
+        } else {
+            Column(0)..Column(self.grid.columns())
+        }
+    }
+
+    /// Whether scrolling has to be restricted to the columns between the margins.
+    fn has_horizontal_margins(&self) -> bool {
+        self.horizontal_margins() != (Column(0)..Column(self.grid.columns()))
+    }
+
+    /// Columns shown on `line`, only half of them on double-width lines.
+    pub fn line_columns(&self, line: Line) -> usize {
+        let columns = self.grid.columns();
+        if self.grid[line].line_attr().is_double_width() {
+            std::cmp::max(columns / 2, 1)
+        } else {
+            columns
+        }
+    }
+
+    /// Column where writing and shifting squares stops for the cursor: the right margin,
+    /// unless the cursor is already past it.
+    fn line_end_at_cursor(&self) -> usize {
+        let right = self.horizontal_margins().end;
+        let end = if self.grid.cursor.pos.col < right {
+            right.0
+        } else {
+            self.grid.columns()
+        };
+
+        std::cmp::min(end, self.line_columns(self.grid.cursor.pos.row))
+    }
+
+    /// Scroll the squares of `lines` which are between the left and right margins.
+    fn scroll_within_margins(&mut self, lines: Range<Line>, positions: i32) {
+        let rect = Rect {
+            lines,
+            columns: self.horizontal_margins(),
+        };
+
+        // A selection cannot follow a partial scroll of its lines.
+        self.selection = None;
+
+        with_erase_template(&mut self.grid, self.reset_mode, |grid| {
+            grid.scroll_region(&rect, positions)
+        });
+        self.mark_fully_damaged();
+    }
+
+    /// Erase the unprotected squares of `line` within `columns`.
+    fn selective_erase(&mut self, line: Line, columns: Range<Column>) {
+        if columns.start < columns.end {
+            self.damage
+                .damage_line(line.0 as usize, columns.start.0, columns.end.0 - 1);
+        }
+
+        let erase = self.grid.cursor.template.erase_template(self.reset_mode);
+        for square in &mut self.grid[line][columns] {
+            square.reset_keep_protected(&erase);
+        }
+    }
+
+    /// Lines and columns positions are relative to: the scrolling region and the margins in
+    /// origin mode, the whole screen otherwise.
+    fn origin_bounds(&self) -> (Range<Line>, Range<Column>) {
+        if self.mode.contains(Mode::ORIGIN) {
+            (self.scroll_region.clone(), self.horizontal_margins())
+        } else {
+            (
+                Line(0)..Line(self.grid.screen_lines() as i32),
+                Column(0)..Column(self.grid.columns()),
+            )
+        }
+    }
+
+    /// Resolve the area of a rectangular operation, clipped to the screen or, in origin mode,
+    /// to the scrolling region and the margins.
+    fn rectangle_area(&self, rect: Rectangle) -> Option<Rect> {
+        let (lines, columns) = self.origin_bounds();
+
+        let top = lines.start + (rect.top.unwrap_or(1) - 1) as i32;
+        let bottom = rect.bottom.map_or(lines.end, |bottom| {
+            std::cmp::min(lines.start + bottom as i32, lines.end)
+        });
+        let left = columns.start + (rect.left.unwrap_or(1) - 1);
+        let right = rect.right.map_or(columns.end, |right| {
+            std::cmp::min(columns.start + right, columns.end)
+        });
+
+        (top < bottom && left < right).then_some(Rect {
+            lines: top..bottom,
+            columns: left..right,
+        })
+    }
+
+    /// Drop the selection and redraw after a rectangular operation on `rect`.
+    fn rectangle_changed(&mut self, rect: &Rect) {
+        let range = rect.lines.clone();
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+        self.mark_fully_damaged();
+    }
+
     #[allow(dead_code)]
     pub fn reset_damage(&mut self) {
         self.damage.reset(self.grid.columns());
     }
 
+    /// Viewport lines changed since the last call along with their damaged columns,
+    /// clearing the damage afterwards.
+    pub fn take_damage(&mut self) -> impl Iterator<Item = LineDamageBounds> {
+        let columns = self.grid.columns();
+        let screen_lines = self.grid.screen_lines();
+        let display_offset = self.grid.display_offset();
+        let damage: Vec<LineDamageBounds> = if self.damage.is_fully_damaged {
+            (0..screen_lines)
+                .map(|line| LineDamageBounds {
+                    line,
+                    left: 0,
+                    right: columns - 1,
+                })
+                .collect()
+        } else {
+            // Damage is tracked for the lines of the grid, which move down the viewport
+            // while the display is scrolled into the history.
+            self.damage
+                .lines
+                .iter()
+                .filter(|bounds| bounds.is_damaged())
+                .map(|bounds| LineDamageBounds {
+                    line: bounds.line + display_offset,
+                    ..*bounds
+                })
+                .filter(|bounds| bounds.line < screen_lines)
+                .collect()
+        };
+
+        self.reset_damage();
+        damage.into_iter()
+    }
+
     pub fn display_offset(&mut self) -> usize {
         self.grid.display_offset()
     }
@@ -448,12 +796,18 @@ impl<U: EventListener> Crosswords<U> {
         }
     }
 
+    /// Number of history lines above the viewport, used to draw a scrollbar.
+    #[allow(dead_code)]
+    pub fn lines_above_viewport(&self) -> usize {
+        self.history_size() - self.grid.display_offset()
+    }
+
     pub fn bottommost_line(&self) -> Line {
         self.grid.bottommost_line()
     }
 
-    pub fn colors(&self) -> List {
-        self.colors
+    pub fn colors(&self) -> &TermColors {
+        &self.colors
     }
 
     #[inline]
@@ -494,7 +848,7 @@ impl<U: EventListener> Crosswords<U> {
         } else if let Some(selection) = self.selection.take() {
             let max_lines = std::cmp::max(num_lines, old_lines) as i32;
             let range = Line(0)..Line(max_lines);
-            self.selection = selection.rotate(&self.grid, &range, -delta);
+            self.selection = selection.rotate(&range, -delta);
         }
 
         // Clamp vi cursor to viewport.
@@ -506,8 +860,9 @@ impl<U: EventListener> Crosswords<U> {
         self.vi_mode_cursor.pos.col =
             std::cmp::min(vi_pos.pos.col, self.grid.last_column());
 
-        // Reset scrolling region.
+        // Reset scrolling region and margins.
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.margins = Column(0)..Column(self.grid.columns());
 
         // Resize damage information.
         self.damage.resize(num_cols, num_lines);
@@ -567,6 +922,50 @@ impl<U: EventListener> Crosswords<U> {
         // Move cursor.
         self.vi_mode_cursor = self.vi_mode_cursor.motion(self, motion);
         self.vi_mode_recompute_selection();
+
+        // Follow the cursor when it leaves the viewport.
+        self.scroll_to_pos(self.vi_mode_cursor.pos);
+    }
+
+    /// Move the vi mode cursor to `pos`, scrolling the display to it.
+    #[inline]
+    pub fn vi_goto_pos(&mut self, pos: Pos)
+    where
+        U: EventListener,
+    {
+        self.scroll_to_pos(pos);
+        self.vi_mode_cursor.pos = pos;
+        self.vi_mode_recompute_selection();
+    }
+
+    /// Move the vi mode cursor to the next `c` in `direction` within its line.
+    ///
+    /// With `stop_short` the cursor stops on the square before the match, like vi's `t`.
+    pub fn vi_inline_search(&mut self, c: char, direction: Direction, stop_short: bool)
+    where
+        U: EventListener,
+    {
+        let mut buf = [0; 4];
+        let needles = c.encode_utf8(&mut buf);
+
+        let origin = self.vi_mode_cursor.pos;
+        let found = match direction {
+            Direction::Right => self.inline_search_right(origin, needles),
+            Direction::Left => self.inline_search_left(origin, needles),
+        };
+
+        if let Ok(mut pos) = found {
+            if stop_short {
+                let mut iter = self.grid.iter_from(pos);
+                let before = match direction {
+                    Direction::Right => iter.prev(),
+                    Direction::Left => iter.next(),
+                };
+                pos = before.map_or(pos, |square| square.pos);
+            }
+
+            self.vi_goto_pos(pos);
+        }
     }
 
     /// Scroll display to point if it is outside of viewport.
@@ -629,15 +1028,28 @@ impl<U: EventListener> Crosswords<U> {
         &self.semantic_escape_chars
     }
 
+    /// Characters ending a word in semantic selections and vi mode word motions.
+    #[inline]
+    pub fn set_semantic_escape_chars(&mut self, chars: &str) {
+        self.semantic_escape_chars = chars.to_owned();
+    }
+
+    /// Enable or disable autowrap (DECAWM).
+    ///
+    /// With autowrap, a char written while the cursor is pending on the last column first
+    /// moves it to the next line. Without it, the last column is overwritten in place.
+    #[inline]
+    pub fn set_autowrap(&mut self, enabled: bool) {
+        self.mode.set(Mode::LINE_WRAP, enabled);
+    }
+
     #[inline]
     pub fn wrapline(&mut self) {
         if !self.mode.contains(Mode::LINE_WRAP) {
             return;
         }
 
-        self.grid
-            .cursor_cell()
-            .flags
+        self.flags_mut(self.grid.cursor.pos)
             .insert(square::Flags::WRAPLINE);
 
         if self.grid.cursor.pos.row + 1 >= self.scroll_region.end {
@@ -647,11 +1059,20 @@ impl<U: EventListener> Crosswords<U> {
             self.grid.cursor.pos.row += 1;
         }
 
-        self.grid.cursor.pos.col = Column(0);
+        self.grid.cursor.pos.col = self.horizontal_margins().start;
         self.grid.cursor.should_wrap = false;
         self.damage_cursor();
     }
 
+    /// Update the scrollback limit of the primary screen, the alternate screen has none.
+    pub fn update_history(&mut self, history_size: usize) {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            self.inactive_grid.update_history(history_size);
+        } else {
+            self.grid.update_history(history_size);
+        }
+    }
+
     pub fn history_size(&self) -> usize {
         self.grid
             .total_lines()
@@ -680,11 +1101,16 @@ impl<U: EventListener> Crosswords<U> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_within_margins(region, -(lines as i32));
+            return;
+        }
+
         // Scroll selection.
         self.selection = self
             .selection
             .take()
-            .and_then(|s| s.rotate(&self.grid, &region, -(lines as i32)));
+            .and_then(|s| s.rotate(&region, -(lines as i32)));
 
         // Scroll vi mode cursor.
         let line = &mut self.vi_mode_cursor.pos.row;
@@ -693,12 +1119,26 @@ impl<U: EventListener> Crosswords<U> {
         }
 
         // Scroll between origin and bottom
-        self.grid.scroll_down(&region, lines);
+        with_erase_template(&mut self.grid, self.reset_mode, |grid| {
+            grid.scroll_down(&region, lines)
+        });
         self.mark_fully_damaged();
     }
 
     #[inline]
-    pub fn scroll_up_relative(&mut self, origin: Line, mut lines: usize) {
+    pub fn scroll_up_relative(&mut self, origin: Line, lines: usize) {
+        self.scroll_up_within_region(origin, lines, true);
+    }
+
+    /// Scroll the lines between `origin` and the bottom of the scrolling region up.
+    ///
+    /// Lines leaving the top of the screen only go to the history with `save_history`.
+    fn scroll_up_within_region(
+        &mut self,
+        origin: Line,
+        mut lines: usize,
+        save_history: bool,
+    ) {
         debug!("Scrolling up: origin={origin}, lines={lines}");
 
         lines = std::cmp::min(
@@ -708,13 +1148,31 @@ impl<U: EventListener> Crosswords<U> {
 
         let region = origin..self.scroll_region.end;
 
+        if self.has_horizontal_margins() {
+            self.scroll_within_margins(region, lines as i32);
+            return;
+        }
+
         // Scroll selection.
         self.selection = self
             .selection
             .take()
-            .and_then(|s| s.rotate(&self.grid, &region, lines as i32));
+            .and_then(|s| s.rotate(&region, lines as i32));
+
+        with_erase_template(&mut self.grid, self.reset_mode, |grid| {
+            if save_history {
+                grid.scroll_up(&region, lines)
+            } else {
+                grid.rotate_region_up(&region, lines)
+            }
+        });
 
-        self.grid.scroll_up(&region, lines);
+        // Drop the selection once it scrolls past the end of the history.
+        let topmost_line = self.grid.topmost_line();
+        self.selection = self
+            .selection
+            .take()
+            .filter(|s| !s.intersects_range(..topmost_line));
 
         // Scroll vi mode cursor.
         let viewport_top = Line(-(self.grid.display_offset() as i32));
@@ -782,12 +1240,12 @@ impl<U: EventListener> Crosswords<U> {
         let mut iter = self.grid.iter_from(point);
         let last_column = self.grid.columns() - 1;
 
-        let wide = square::Flags::WIDE_CHAR
-            | square::Flags::WIDE_CHAR_SPACER
-            | square::Flags::LEADING_WIDE_CHAR_SPACER;
+        // Spacers belong to their wide char, which decides where the word ends.
+        let spacers =
+            square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER;
         while let Some(cell) = iter.prev() {
-            if !cell.flags.intersects(wide) && self.semantic_escape_chars.contains(cell.c)
-            {
+            let is_spacer = cell.flags.intersects(spacers);
+            if !is_spacer && self.semantic_escape_chars.contains(cell.c) {
                 break;
             }
 
@@ -797,7 +1255,9 @@ impl<U: EventListener> Crosswords<U> {
                 break; // cut off if on new line or hit escape char
             }
 
-            point = cell.pos;
+            if !is_spacer {
+                point = cell.pos;
+            }
         }
 
         point
@@ -808,13 +1268,13 @@ impl<U: EventListener> Crosswords<U> {
         // Limit the starting point to the last line in the history
         point.row = std::cmp::max(point.row, self.grid.topmost_line());
 
-        let wide = square::Flags::WIDE_CHAR
-            | square::Flags::WIDE_CHAR_SPACER
-            | square::Flags::LEADING_WIDE_CHAR_SPACER;
+        let spacers =
+            square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER;
         let last_column = self.grid.columns() - 1;
 
         for cell in self.grid.iter_from(point) {
-            if !cell.flags.intersects(wide) && self.semantic_escape_chars.contains(cell.c)
+            if !cell.flags.intersects(spacers)
+                && self.semantic_escape_chars.contains(cell.c)
             {
                 break;
             }
@@ -829,6 +1289,95 @@ impl<U: EventListener> Crosswords<U> {
         point
     }
 
+    /// Append `c` to the grapheme cluster of the previous cell if it is a continuation
+    /// of it, promoting the cell to a wide char when the cluster becomes wide.
+    #[inline]
+    fn extend_grapheme_cluster(&mut self, c: char) -> bool {
+        let row = self.grid.cursor.pos.row;
+        let mut column = self.grid.cursor.pos.col;
+        if !self.grid.cursor.should_wrap {
+            if column == 0 {
+                return false;
+            }
+            column -= 1;
+        }
+
+        if self.grid[row][column]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER)
+        {
+            column -= 1;
+        }
+
+        // The first and last chars of the cluster are enough context to find a boundary
+        // before `c`, so they are encoded on the stack instead of collecting the cluster.
+        let square = &self.grid[row][column];
+        let mut buffer = [0; 12];
+        let mut len = square.c.encode_utf8(&mut buffer).len();
+        if let Some(last) = square.zerowidth().and_then(|chars| chars.last()) {
+            len += last.encode_utf8(&mut buffer[len..]).len();
+        }
+        len += c.encode_utf8(&mut buffer[len..]).len();
+        match std::str::from_utf8(&buffer[..len]) {
+            Ok(cluster) if cluster.graphemes(true).count() == 1 => {}
+            _ => return false,
+        }
+
+        // A cluster at the last column has no room for a spacer.
+        if column >= self.grid.last_column() {
+            self.cell_mut(Pos::new(row, column)).push_zerowidth(c);
+            return true;
+        }
+
+        if self
+            .cell_mut(Pos::new(row, column))
+            .push_zerowidth_and_promote(c)
+        {
+            self.write_at_cursor(' ');
+            self.grid.cursor_cell().into_spacer();
+
+            if self.grid.cursor.pos.col + 1 < self.grid.columns() {
+                self.grid.cursor.pos.col += 1;
+            } else {
+                self.grid.cursor.should_wrap = true;
+            }
+        }
+
+        true
+    }
+
+    /// Whether a mode is currently set, used to answer DECRQM.
+    fn is_mode_set(&self, mode: &AnsiMode) -> bool {
+        match mode {
+            AnsiMode::CursorKeys => self.mode.contains(Mode::APP_CURSOR),
+            AnsiMode::Column => false,
+            AnsiMode::Insert => self.mode.contains(Mode::INSERT),
+            AnsiMode::Origin => self.mode.contains(Mode::ORIGIN),
+            AnsiMode::LineWrap => self.mode.contains(Mode::LINE_WRAP),
+            AnsiMode::BlinkingCursor => self.cursor_style().blinking,
+            AnsiMode::LineFeedNewLine => self.mode.contains(Mode::LINE_FEED_NEW_LINE),
+            AnsiMode::ShowCursor => self.mode.contains(Mode::SHOW_CURSOR),
+            AnsiMode::ReportMouseClicks => self.mode.contains(Mode::MOUSE_REPORT_CLICK),
+            AnsiMode::ReportSquareMouseMotion => self.mode.contains(Mode::MOUSE_DRAG),
+            AnsiMode::ReportAllMouseMotion => self.mode.contains(Mode::MOUSE_MOTION),
+            AnsiMode::ReportFocusInOut => self.mode.contains(Mode::FOCUS_IN_OUT),
+            AnsiMode::Utf8Mouse => self.mode.contains(Mode::UTF8_MOUSE),
+            AnsiMode::SgrMouse => self.mode.contains(Mode::SGR_MOUSE),
+            AnsiMode::AlternateScroll => self.mode.contains(Mode::ALTERNATE_SCROLL),
+            AnsiMode::UrgencyHints => self.mode.contains(Mode::URGENCY_HINTS),
+            AnsiMode::SwapScreen
+            | AnsiMode::SwapScreenAndClear
+            | AnsiMode::SwapScreenAndSetRestoreCursor => {
+                self.mode.contains(Mode::ALT_SCREEN)
+            }
+            // Saving the cursor has no persistent state.
+            AnsiMode::SaveCursor => false,
+            AnsiMode::LeftRightMargin => self.mode.contains(Mode::LEFT_RIGHT_MARGIN),
+            AnsiMode::BracketedPaste => self.mode.contains(Mode::BRACKETED_PASTE),
+            AnsiMode::GraphemeClustering => self.mode.contains(Mode::GRAPHEME_CLUSTERING),
+        }
+    }
+
     #[inline(always)]
     pub fn write_at_cursor(&mut self, c: char) {
         let c = self.grid.cursor.charsets[self.active_charset].map(c);
@@ -837,38 +1386,157 @@ impl<U: EventListener> Crosswords<U> {
         let flags = self.grid.cursor.template.flags;
         let extra = self.grid.cursor.template.extra.clone();
 
+        let point = self.grid.cursor.pos;
         let mut cursor_square = self.grid.cursor_square();
-        if cursor_square
-            .flags
-            .intersects(square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER)
-        {
+        let wide_cleared = cursor_square.is_wide() || cursor_square.is_spacer();
+        if wide_cleared {
             // Remove wide char and spacer.
-            let wide = cursor_square.flags.contains(square::Flags::WIDE_CHAR);
-            let point = self.grid.cursor.pos;
+            let wide = cursor_square.is_wide();
             if wide && point.col < self.grid.last_column() {
-                self.grid[point.row][point.col + 1]
-                    .flags
+                self.flags_mut(Pos::new(point.row, point.col + 1))
                     .remove(square::Flags::WIDE_CHAR_SPACER);
             } else if point.col > 0 {
-                self.grid[point.row][point.col - 1].clear_wide();
+                self.cell_mut(Pos::new(point.row, point.col - 1))
+                    .clear_wide();
             }
 
             // Remove leading spacers.
             if point.col <= 1 && point.row != self.grid.topmost_line() {
                 let column = self.grid.last_column();
-                self.grid[point.row - 1i32][column]
-                    .flags
+                self.flags_mut(Pos::new(point.row - 1i32, column))
                     .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
             }
 
             cursor_square = self.grid.cursor_cell();
         }
 
+        // Writing the exact same content should not damage the line.
+        let changed = cursor_square.c != c
+            || cursor_square.fg != fg
+            || cursor_square.bg != bg
+            || cursor_square.flags != flags
+            || cursor_square.extra != extra;
+
         cursor_square.c = c;
         cursor_square.fg = fg;
         cursor_square.bg = bg;
         cursor_square.flags = flags;
-        cursor_square.extra = extra;
+        square::recycle_extra(mem::replace(&mut cursor_square.extra, extra));
+
+        if wide_cleared || changed {
+            self.damage.damage_point(point);
+        }
+    }
+
+    /// Walk every row, scrollback included, and account for extra square storage.
+    pub fn memory_stats(&self) -> GridMemoryStats {
+        let mut stats = GridMemoryStats {
+            allocated_rows: self.grid.allocated_rows(),
+            ..GridMemoryStats::default()
+        };
+
+        let topmost_line = self.grid.topmost_line().0;
+        let bottommost_line = self.grid.bottommost_line().0;
+        for line in topmost_line..=bottommost_line {
+            for square in &self.grid[Line(line)][..] {
+                stats.total_squares += 1;
+
+                if let Some(extra) = square.extra.as_ref() {
+                    stats.squares_with_extra += 1;
+                    stats.zerowidth_chars += square.zerowidth().map_or(0, |z| z.len());
+
+                    if Arc::strong_count(extra) > 1 {
+                        stats.shared_extras += 1;
+                    } else {
+                        stats.unique_extras += 1;
+                    }
+                }
+            }
+        }
+
+        stats
+    }
+
+    /// Capture the active grid, including scrollback.
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> GridSnapshot {
+        let topmost_line = self.grid.topmost_line().0;
+        let bottommost_line = self.grid.bottommost_line().0;
+
+        GridSnapshot {
+            columns: self.grid.columns(),
+            screen_lines: self.grid.screen_lines(),
+            rows: (topmost_line..=bottommost_line)
+                .map(|line| self.grid[Line(line)].clone())
+                .collect(),
+            cursor: self.grid.cursor.pos,
+        }
+    }
+
+    /// Replace the active grid with a snapshot, rewrapping it to the current dimensions.
+    #[allow(dead_code)]
+    pub fn restore(&mut self, snapshot: GridSnapshot) -> Result<(), SnapshotError> {
+        let GridSnapshot {
+            columns,
+            screen_lines,
+            rows,
+            cursor,
+        } = snapshot;
+
+        if columns < MIN_COLUMNS || screen_lines < MIN_LINES || rows.len() < screen_lines
+        {
+            return Err(SnapshotError::InvalidDimensions {
+                columns,
+                screen_lines,
+            });
+        }
+
+        if let Some((index, row)) = rows
+            .iter()
+            .enumerate()
+            .find(|(_, row)| row.len() != columns)
+        {
+            return Err(SnapshotError::InvalidRow {
+                index,
+                columns: row.len(),
+            });
+        }
+
+        let mut grid =
+            Grid::from_rows(rows, screen_lines, columns, self.grid.max_scroll_limit());
+        grid.cursor.pos = Pos::new(
+            Line(cursor.row.0.clamp(0, screen_lines as i32 - 1)),
+            Column(std::cmp::min(cursor.col.0, columns - 1)),
+        );
+        grid.resize(true, self.grid.screen_lines(), self.grid.columns());
+
+        self.grid = grid;
+        self.selection = None;
+        self.mark_fully_damaged();
+
+        Ok(())
+    }
+
+    /// Shell integration marks from the oldest history line to the bottom of the screen.
+    ///
+    /// The exit code is only reported along with the `D` marks.
+    #[allow(dead_code)]
+    pub fn prompt_marks(
+        &self,
+    ) -> impl Iterator<Item = (Line, PromptKind, Option<i32>)> + '_ {
+        let topmost_line = self.grid.topmost_line().0;
+        let bottommost_line = self.grid.bottommost_line().0;
+        (topmost_line..=bottommost_line).flat_map(|line| {
+            let line = Line(line);
+            let marks = self.grid[line].prompt_marks();
+            marks.kinds().map(move |kind| {
+                let exit_code = match kind {
+                    PromptKind::CommandEnd => marks.exit_code,
+                    _ => None,
+                };
+                (line, kind, exit_code)
+            })
+        })
     }
 
     #[inline]
@@ -890,6 +1558,59 @@ impl<U: EventListener> Crosswords<U> {
         visible_rows
     }
 
+    /// Runs of visible squares sharing the hyperlink of the square at `pos`.
+    ///
+    /// OSC 8 ids tie the fragments of a link together, so runs on every visible line are
+    /// returned. The lines of the runs are relative to the top of the viewport.
+    #[allow(dead_code)]
+    pub fn hyperlink_range_at(&self, pos: Pos) -> Option<Vec<RangeInclusive<Pos>>> {
+        if pos.row < self.grid.topmost_line() || pos.row > self.grid.bottommost_line() {
+            return None;
+        }
+        let hyperlink = self.grid[pos.row].inner.get(pos.col.0)?.hyperlink()?;
+
+        let display_offset = self.grid.display_offset() as i32;
+        let mut runs = Vec::new();
+        for line in 0..self.grid.screen_lines() as i32 {
+            let row = &self.grid[Line(line - display_offset)];
+            let mut start = None;
+            for column in 0..=row.len() {
+                let linked = column < row.len()
+                    && row[Column(column)].hyperlink().as_ref() == Some(&hyperlink);
+                match (start, linked) {
+                    (None, true) => start = Some(column),
+                    (Some(first), false) => {
+                        runs.push(
+                            Pos::new(Line(line), Column(first))
+                                ..=Pos::new(Line(line), Column(column - 1)),
+                        );
+                        start = None;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Some(runs)
+    }
+
+    /// Iterate over the squares of the viewport in row-major order.
+    ///
+    /// Positions follow the display offset, so history rows have negative lines while
+    /// scrolled back and can be used to index the grid directly.
+    #[inline]
+    #[allow(dead_code)]
+    pub fn display_iter(&self) -> GridIterator<'_, Square> {
+        self.grid.display_iter()
+    }
+
+    /// Iterate from the square after `pos` through the history and the screen.
+    #[inline]
+    #[allow(dead_code)]
+    pub fn iter_from(&self, pos: Pos) -> GridIterator<'_, Square> {
+        self.grid.iter_from(pos)
+    }
+
     fn deccolm(&mut self)
     where
         U: EventListener,
@@ -899,7 +1620,9 @@ impl<U: EventListener> Crosswords<U> {
         self.set_scrolling_region(1, None);
 
         // Clear grid.
-        self.grid.reset_region(..);
+        with_erase_template(&mut self.grid, self.reset_mode, |grid| {
+            grid.reset_region(..)
+        });
         self.mark_fully_damaged();
     }
 
@@ -907,9 +1630,18 @@ impl<U: EventListener> Crosswords<U> {
         self.mode
     }
 
+    /// Cursor style requested by the application, or else the configured one.
+    #[inline]
+    pub fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style.unwrap_or(CursorStyle {
+            shape: self.cursor_shape,
+            blinking: self.blinking_cursor,
+        })
+    }
+
     #[inline]
     pub fn cursor(&mut self) -> CursorState {
-        let mut content = self.cursor_shape;
+        let mut content = self.cursor_style().shape;
         let vi_mode = self.mode.contains(Mode::VI);
         let mut pos = if vi_mode {
             self.vi_mode_cursor.pos
@@ -934,18 +1666,51 @@ impl<U: EventListener> Crosswords<U> {
         CursorState { pos, content }
     }
 
-    pub fn swap_alt(&mut self) {
-        if !self.mode.contains(Mode::ALT_SCREEN) {
-            // Set alt screen cursor to the current primary screen cursor.
-            self.inactive_grid.cursor = self.grid.cursor.clone();
+    /// Switch from the primary to the alternate screen.
+    ///
+    /// With `save_and_clear` (mode 1049) the primary cursor is saved and the alternate screen
+    /// is cleared with the current background, modes 47 and 1047 keep its contents.
+    pub fn enter_alt_screen(&mut self, save_and_clear: bool) {
+        if self.mode.contains(Mode::ALT_SCREEN) {
+            return;
+        }
+
+        // The primary screen is shown at the bottom again once the application is done.
+        self.scroll_display(Scroll::Bottom);
+
+        // Set alt screen cursor to the current primary screen cursor.
+        self.inactive_grid.cursor = self.grid.cursor.clone();
 
+        if save_and_clear {
             // Drop information about the primary screens saved cursor.
-            self.grid.saved_cursor = self.grid.cursor.clone();
+            self.save_cursor_position();
 
             // Reset alternate screen contents.
-            self.inactive_grid.reset_region(..);
+            with_erase_template(&mut self.inactive_grid, self.reset_mode, |grid| {
+                grid.reset_region(..)
+            });
         }
 
+        self.swap_screens();
+    }
+
+    /// Switch from the alternate back to the primary screen.
+    ///
+    /// With `restore_cursor` (mode 1049) the cursor saved when entering the alternate screen
+    /// is restored, together with origin mode and the active charset.
+    pub fn leave_alt_screen(&mut self, restore_cursor: bool) {
+        if !self.mode.contains(Mode::ALT_SCREEN) {
+            return;
+        }
+
+        self.swap_screens();
+
+        if restore_cursor {
+            self.restore_cursor_position();
+        }
+    }
+
+    fn swap_screens(&mut self) {
         mem::swap(
             &mut self.keyboard_mode_stack,
             &mut self.inactive_keyboard_mode_stack,
@@ -966,39 +1731,7 @@ impl<U: EventListener> Crosswords<U> {
     }
 
     pub fn selection_to_string(&self) -> Option<String> {
-        let selection_range = self.selection.as_ref().and_then(|s| s.to_range(self))?;
-        let SelectionRange { start, end, .. } = selection_range;
-
-        let mut res = String::new();
-
-        match self.selection.as_ref() {
-            Some(Selection {
-                ty: SelectionType::Block,
-                ..
-            }) => {
-                for line in (start.row.0..end.row.0).map(Line::from) {
-                    res += self
-                        .line_to_string(line, start.col..end.col, start.col.0 != 0)
-                        .trim_end();
-                    res += "\n";
-                }
-
-                res += self
-                    .line_to_string(end.row, start.col..end.col, true)
-                    .trim_end();
-            }
-            Some(Selection {
-                ty: SelectionType::Lines,
-                ..
-            }) => {
-                res = self.bounds_to_string(start, end) + "\n";
-            }
-            _ => {
-                res = self.bounds_to_string(start, end);
-            }
-        }
-
-        Some(res)
+        self.selection.as_ref()?.to_string(self)
     }
 
     pub fn bounds_to_string(&self, start: Pos, end: Pos) -> String {
@@ -1022,8 +1755,30 @@ impl<U: EventListener> Crosswords<U> {
         res.strip_suffix('\n').map(str::to_owned).unwrap_or(res)
     }
 
+    /// Text of the lines in `range`, or of the whole history and screen when it is `None`.
+    ///
+    /// Wrapped lines are joined and trailing blanks, including blank lines at the end,
+    /// are dropped.
+    pub fn to_text(&self, range: Option<Range<Line>>) -> String {
+        let range = range.unwrap_or_else(|| {
+            self.grid.topmost_line()..Line(self.grid.screen_lines() as i32)
+        });
+        let rows = (range.start.0..range.end.0).map(|line| &self.grid[Line(line)]);
+
+        let mut content = text::to_plain_text(rows);
+        content.truncate(content.trim_end_matches('\n').len());
+        content
+    }
+
+    /// Text of the lines currently displayed, following the display offset.
+    #[allow(dead_code)]
+    pub fn visible_to_string(&self) -> String {
+        let top = Line(-(self.grid.display_offset() as i32));
+        self.to_text(Some(top..top + self.grid.screen_lines()))
+    }
+
     /// Convert a single line in the grid to a String.
-    fn line_to_string(
+    pub(crate) fn line_to_string(
         &self,
         line: Line,
         mut cols: Range<Column>,
@@ -1057,19 +1812,17 @@ impl<U: EventListener> Crosswords<U> {
 
             if cell.c == '\t' {
                 tab_mode = true;
-            }
 
-            if !cell.flags.intersects(
-                square::Flags::WIDE_CHAR_SPACER | square::Flags::LEADING_WIDE_CHAR_SPACER,
-            ) {
-                // Push cells primary character.
-                text.push(cell.c);
-
-                // Push zero-width characters.
-                for c in cell.zerowidth().into_iter().flatten() {
-                    text.push(*c);
+                // Expand the tab to the squares it covers, up to the end of the selection.
+                if self.expand_tabs_on_copy {
+                    let stop = std::cmp::min(self.tabs.next_stop(column), cols.end + 1);
+                    let width = std::cmp::max(stop.0.saturating_sub(column.0), 1);
+                    text.extend(std::iter::repeat(' ').take(width));
+                    continue;
                 }
             }
+
+            cell.write_text_to(&mut text);
         }
 
         if cols.end >= self.grid.columns() - 1
@@ -1089,7 +1842,7 @@ impl<U: EventListener> Crosswords<U> {
                 .contains(square::Flags::LEADING_WIDE_CHAR_SPACER)
             && include_wrapped_wide
         {
-            text.push(self.grid[line - 1i32][Column(0)].c);
+            self.grid[line - 1i32][Column(0)].write_text_to(&mut text);
         }
 
         text
@@ -1145,11 +1898,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
     fn set_mode(&mut self, mode: AnsiMode) {
         match mode {
             AnsiMode::UrgencyHints => self.mode.insert(Mode::URGENCY_HINTS),
-            AnsiMode::SwapScreenAndSetRestoreCursor => {
-                if !self.mode.contains(Mode::ALT_SCREEN) {
-                    self.swap_alt();
-                }
+            AnsiMode::SwapScreen | AnsiMode::SwapScreenAndClear => {
+                self.enter_alt_screen(false)
             }
+            AnsiMode::SaveCursor => self.save_cursor_position(),
+            AnsiMode::SwapScreenAndSetRestoreCursor => self.enter_alt_screen(true),
             AnsiMode::ShowCursor => self.mode.insert(Mode::SHOW_CURSOR),
             AnsiMode::CursorKeys => self.mode.insert(Mode::APP_CURSOR),
             // Mouse protocols are mutually exclusive.
@@ -1183,13 +1936,20 @@ impl<U: EventListener> Handler for Crosswords<U> {
                 self.mode.insert(Mode::UTF8_MOUSE);
             }
             AnsiMode::AlternateScroll => self.mode.insert(Mode::ALTERNATE_SCROLL),
-            AnsiMode::LineWrap => self.mode.insert(Mode::LINE_WRAP),
+            AnsiMode::LineWrap => self.set_autowrap(true),
             AnsiMode::LineFeedNewLine => self.mode.insert(Mode::LINE_FEED_NEW_LINE),
-            AnsiMode::Origin => self.mode.insert(Mode::ORIGIN),
+            AnsiMode::Origin => {
+                self.mode.insert(Mode::ORIGIN);
+                self.goto(Line(0), Column(0));
+            }
             AnsiMode::Column => self.deccolm(),
             AnsiMode::Insert => self.mode.insert(Mode::INSERT),
+            AnsiMode::GraphemeClustering => self.mode.insert(Mode::GRAPHEME_CLUSTERING),
+            AnsiMode::LeftRightMargin => self.mode.insert(Mode::LEFT_RIGHT_MARGIN),
             AnsiMode::BlinkingCursor => {
-                self.blinking_cursor = true;
+                let mut style = self.cursor_style();
+                style.blinking = true;
+                self.cursor_style = Some(style);
                 self.event_proxy
                     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
             }
@@ -1204,29 +1964,52 @@ impl<U: EventListener> Handler for Crosswords<U> {
         );
 
         let terminator = terminator.to_owned();
-        self.event_proxy.send_event(
-            RioEvent::ColorRequest(
-                index,
-                Arc::new(move |color| {
-                    format!(
-                        "\x1b]{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
-                        prefix, color.r, color.g, color.b, terminator
-                    )
-                }),
-            ),
-            self.window_id,
-        );
+        let format = move |color: ColorRgb| {
+            format!(
+                "\x1b]{};rgb:{1:02x}{1:02x}/{2:02x}{2:02x}/{3:02x}{3:02x}{4}",
+                prefix, color.r, color.g, color.b, terminator
+            )
+        };
+
+        // Colors changed by the terminal are answered right away, the others
+        // come from the configuration owned by the screen.
+        let event = match self.colors[index] {
+            Some(color) => RioEvent::PtyWrite(format(color)),
+            None => RioEvent::ColorRequest(index, Arc::new(format)),
+        };
+        self.event_proxy.send_event(event, self.window_id);
+    }
+
+    #[inline]
+    fn report_mode(&mut self, intermediate: Option<&u8>, mode: u16) {
+        // 0: not recognized, 1: set, 2: reset.
+        let state = match AnsiMode::from_primitive(intermediate, mode) {
+            Some(ansi_mode) if self.is_mode_set(&ansi_mode) => 1,
+            Some(_) => 2,
+            None => 0,
+        };
+
+        let private = if intermediate == Some(&b'?') { "?" } else { "" };
+        let text = format!("\x1b[{private}{mode};{state}$y");
+        self.event_proxy
+            .send_event(RioEvent::PtyWrite(text), self.window_id);
     }
 
     #[inline]
     fn unset_mode(&mut self, mode: AnsiMode) {
         match mode {
             AnsiMode::UrgencyHints => self.mode.remove(Mode::URGENCY_HINTS),
-            AnsiMode::SwapScreenAndSetRestoreCursor => {
+            AnsiMode::SwapScreen => self.leave_alt_screen(false),
+            AnsiMode::SwapScreenAndClear => {
                 if self.mode.contains(Mode::ALT_SCREEN) {
-                    self.swap_alt();
+                    with_erase_template(&mut self.grid, self.reset_mode, |grid| {
+                        grid.reset_region(..)
+                    });
+                    self.leave_alt_screen(false);
                 }
             }
+            AnsiMode::SaveCursor => self.restore_cursor_position(),
+            AnsiMode::SwapScreenAndSetRestoreCursor => self.leave_alt_screen(true),
             AnsiMode::ShowCursor => self.mode.remove(Mode::SHOW_CURSOR),
             AnsiMode::CursorKeys => self.mode.remove(Mode::APP_CURSOR),
             AnsiMode::ReportMouseClicks => {
@@ -1249,35 +2032,57 @@ impl<U: EventListener> Handler for Crosswords<U> {
             AnsiMode::SgrMouse => self.mode.remove(Mode::SGR_MOUSE),
             AnsiMode::Utf8Mouse => self.mode.remove(Mode::UTF8_MOUSE),
             AnsiMode::AlternateScroll => self.mode.remove(Mode::ALTERNATE_SCROLL),
-            AnsiMode::LineWrap => self.mode.remove(Mode::LINE_WRAP),
+            AnsiMode::LineWrap => self.set_autowrap(false),
             AnsiMode::LineFeedNewLine => self.mode.remove(Mode::LINE_FEED_NEW_LINE),
-            AnsiMode::Origin => self.mode.remove(Mode::ORIGIN),
+            AnsiMode::Origin => {
+                self.mode.remove(Mode::ORIGIN);
+                self.goto(Line(0), Column(0));
+            }
             AnsiMode::Column => self.deccolm(),
             AnsiMode::Insert => {
                 self.mode.remove(Mode::INSERT);
                 self.mark_fully_damaged();
             }
+            AnsiMode::GraphemeClustering => self.mode.remove(Mode::GRAPHEME_CLUSTERING),
+            AnsiMode::LeftRightMargin => {
+                self.mode.remove(Mode::LEFT_RIGHT_MARGIN);
+                self.margins = Column(0)..Column(self.grid.columns());
+            }
             AnsiMode::BlinkingCursor => {
-                // TODO: Update it
-                // self.blinking_cursor = false;
-                // self.event_proxy
-                //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+                let mut style = self.cursor_style();
+                style.blinking = false;
+                self.cursor_style = Some(style);
+                self.event_proxy
+                    .send_event(RioEvent::CursorBlinkingChange, self.window_id);
             }
         }
     }
 
     #[inline]
     fn goto(&mut self, line: Line, col: Column) {
-        let (y_offset, max_y) = if self.mode.contains(Mode::ORIGIN) {
-            (self.scroll_region.start, self.scroll_region.end - 1)
+        let (y_offset, max_y, x_offset, max_x) = if self.mode.contains(Mode::ORIGIN) {
+            let margins = self.horizontal_margins();
+            (
+                self.scroll_region.start,
+                self.scroll_region.end - 1,
+                margins.start,
+                margins.end - 1,
+            )
         } else {
-            (Line(0), self.grid.bottommost_line())
+            (
+                Line(0),
+                self.grid.bottommost_line(),
+                Column(0),
+                self.grid.last_column(),
+            )
         };
 
         self.damage_cursor();
         self.grid.cursor.pos.row =
             std::cmp::max(std::cmp::min(line + y_offset, max_y), Line(0));
-        self.grid.cursor.pos.col = std::cmp::min(col, self.grid.last_column());
+        let line_end = Column(self.line_columns(self.grid.cursor.pos.row) - 1);
+        self.grid.cursor.pos.col =
+            std::cmp::min(std::cmp::min(col + x_offset, max_x), line_end);
         self.damage_cursor();
         self.grid.cursor.should_wrap = false;
     }
@@ -1289,8 +2094,16 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn move_forward(&mut self, cols: Column) {
-        let last_column =
-            std::cmp::min(self.grid.cursor.pos.col + cols, self.grid.last_column());
+        // The right margin stops the cursor unless it is already past it.
+        let right = self.horizontal_margins().end;
+        let limit = if self.grid.cursor.pos.col < right {
+            right - 1
+        } else {
+            self.grid.last_column()
+        };
+        let line_end = Column(self.line_columns(self.grid.cursor.pos.row) - 1);
+        let limit = std::cmp::min(limit, line_end);
+        let last_column = std::cmp::min(self.grid.cursor.pos.col + cols, limit);
 
         let cursor_line = self.grid.cursor.pos.row.0 as usize;
         self.damage
@@ -1302,7 +2115,15 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn move_backward(&mut self, cols: Column) {
-        let column = self.grid.cursor.pos.col.saturating_sub(cols.0);
+        // The left margin stops the cursor unless it is already before it.
+        let left = self.horizontal_margins().start;
+        let limit = if self.grid.cursor.pos.col >= left {
+            left.0
+        } else {
+            0
+        };
+        let column =
+            std::cmp::max(self.grid.cursor.pos.col.saturating_sub(cols.0), limit);
 
         let cursor_line = self.grid.cursor.pos.row.0 as usize;
         self.damage
@@ -1318,14 +2139,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
         let old_col = self.grid.cursor.pos.col.0;
         for _ in 0..count {
-            let mut col = self.grid.cursor.pos.col;
-            for i in (0..(col.0)).rev() {
-                if self.tabs[Column(i)] {
-                    col = Column(i);
-                    break;
-                }
-            }
-            self.grid.cursor.pos.col = col;
+            self.grid.cursor.pos.col = self.tabs.prev_stop(self.grid.cursor.pos.col);
         }
 
         let line = self.grid.cursor.pos.row.0 as usize;
@@ -1345,17 +2159,33 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn decaln(&mut self) {
+        let template = Square {
+            c: 'E',
+            ..Square::default()
+        };
+        let columns = Column(0)..Column(self.grid.columns());
         for line in (0..self.grid.screen_lines()).map(Line::from) {
-            for column in 0..self.grid.columns() {
-                let cell = &mut self.grid[line][Column(column)];
-                *cell = Square::default();
-                cell.c = 'E';
-            }
+            square::fill_row(&mut self.grid[line], columns.clone(), &template);
         }
 
         self.mark_fully_damaged();
     }
 
+    #[inline]
+    fn set_line_attr(&mut self, attr: LineAttr) {
+        let line = self.grid.cursor.pos.row;
+        self.grid[line].set_line_attr(attr);
+
+        // The cursor cannot stay in the hidden half of a double-width line.
+        let line_end = Column(self.line_columns(line) - 1);
+        if self.grid.cursor.pos.col > line_end {
+            self.grid.cursor.pos.col = line_end;
+            self.grid.cursor.should_wrap = false;
+        }
+        self.damage
+            .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
+    }
+
     #[inline]
     fn move_up(&mut self, rows: usize) {
         self.goto(self.grid.cursor.pos.row - rows, self.grid.cursor.pos.col)
@@ -1385,10 +2215,18 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
     fn delete_lines(&mut self, lines: usize) {
         let origin = self.grid.cursor.pos.row;
-        let lines = std::cmp::min(self.grid.screen_lines() - origin.0 as usize, lines);
+        let lines =
+            std::cmp::min((self.scroll_region.end - origin).0.max(0) as usize, lines);
 
-        if lines > 0 && self.scroll_region.contains(&origin) {
-            self.scroll_up_relative(origin, lines);
+        let column = self.grid.cursor.pos.col;
+        if lines > 0
+            && self.scroll_region.contains(&origin)
+            && self.horizontal_margins().contains(&column)
+        {
+            // Deleted lines are gone, even at the top of the screen.
+            self.scroll_up_within_region(origin, lines, false);
+            self.grid.cursor.pos.col = self.horizontal_margins().start;
+            self.grid.cursor.should_wrap = false;
         }
     }
 
@@ -1424,44 +2262,34 @@ impl<U: EventListener> Handler for Crosswords<U> {
         let start = cursor.pos.col;
         let end = std::cmp::min(start + count, Column(self.grid.columns()));
 
-        // Cleared cells have current background color set.
-        let bg = self.grid.cursor.template.bg;
+        // Cleared cells inherit attributes according to the reset mode.
+        let erase = cursor.template.erase_template(self.reset_mode);
         let line = cursor.pos.row;
         self.damage.damage_line(line.0 as usize, start.0, end.0);
         let row = &mut self.grid[line];
         for cell in &mut row[start..end] {
-            *cell = bg.into();
+            *cell = erase.clone();
         }
     }
 
     #[inline]
     fn delete_chars(&mut self, count: usize) {
-        let columns = self.grid.columns();
-        let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
-
-        // Ensure deleting within terminal bounds.
-        let count = std::cmp::min(count, columns);
+        // Squares outside of the margins are never shifted.
+        if !self
+            .horizontal_margins()
+            .contains(&self.grid.cursor.pos.col)
+        {
+            return;
+        }
 
-        let start = cursor.pos.col.0;
-        let end = std::cmp::min(start + count, columns - 1);
-        let num_cells = columns - end;
+        let columns = Column(self.line_end_at_cursor());
+        let cursor = &self.grid.cursor;
+        let erase = cursor.template.erase_template(self.reset_mode);
+        let Pos { row: line, col } = cursor.pos;
 
-        let line = cursor.pos.row;
         self.damage
             .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
-        let row = &mut self.grid[line][..];
-
-        for offset in 0..num_cells {
-            row.swap(start + offset, end + offset);
-        }
-
-        // Clear last `count` cells in the row. If deleting 1 char, need to delete
-        // 1 cell.
-        let end = columns - count;
-        for cell in &mut row[end..] {
-            *cell = bg.into();
-        }
+        square::delete_chars(&mut self.grid[line], col..columns, count, &erase);
     }
 
     #[inline]
@@ -1473,38 +2301,34 @@ impl<U: EventListener> Handler for Crosswords<U> {
     #[inline]
     fn insert_blank_lines(&mut self, lines: usize) {
         let origin = self.grid.cursor.pos.row;
-        if self.scroll_region.contains(&origin) {
+        let column = self.grid.cursor.pos.col;
+        if self.scroll_region.contains(&origin)
+            && self.horizontal_margins().contains(&column)
+        {
             self.scroll_down_relative(origin, lines);
+            self.grid.cursor.pos.col = self.horizontal_margins().start;
+            self.grid.cursor.should_wrap = false;
         }
     }
 
     #[inline]
     fn insert_blank(&mut self, count: usize) {
-        let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
-
-        // Ensure inserting within terminal bounds
-        let count = std::cmp::min(count, self.grid.columns() - cursor.pos.col.0);
+        // Squares outside of the margins are never shifted.
+        if !self
+            .horizontal_margins()
+            .contains(&self.grid.cursor.pos.col)
+        {
+            return;
+        }
 
-        let source = cursor.pos.col;
-        let destination = cursor.pos.col.0 + count;
-        let num_cells = self.grid.columns() - destination;
+        let columns = Column(self.line_end_at_cursor());
+        let cursor = &self.grid.cursor;
+        let erase = cursor.template.erase_template(self.reset_mode);
+        let Pos { row: line, col } = cursor.pos;
 
-        let line = cursor.pos.row;
         self.damage
             .damage_line(line.0 as usize, 0, self.grid.columns() - 1);
-
-        let row = &mut self.grid[line][..];
-
-        for offset in (0..num_cells).rev() {
-            row.swap(destination + offset, source.0 + offset);
-        }
-
-        // Squares were just moved out toward the end of the line;
-        // fill in between source and dest with blanks.
-        for cell in &mut row[source.0..destination] {
-            *cell = bg.into();
-        }
+        square::insert_chars(&mut self.grid[line], col..columns, count, &erase);
     }
 
     #[inline]
@@ -1526,14 +2350,16 @@ impl<U: EventListener> Handler for Crosswords<U> {
             std::mem::swap(&mut self.grid, &mut self.inactive_grid);
         }
         self.active_charset = Default::default();
-        // self.cursor_style = None;
+        self.cursor_style = None;
         self.grid.reset();
         self.inactive_grid.reset();
         self.scroll_region = Line(0)..Line(self.grid.screen_lines() as i32);
+        self.margins = Column(0)..Column(self.grid.columns());
         self.tabs = TabStops::new(self.grid.columns());
         self.title_stack = Vec::new();
         self.keyboard_mode_stack = Vec::new();
         self.title = String::from("");
+        self.colors = TermColors::default();
         self.selection = None;
         self.vi_mode_cursor = Default::default();
         self.keyboard_mode_stack = Default::default();
@@ -1550,68 +2376,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn terminal_attribute(&mut self, attr: Attr) {
-        let cursor = &mut self.grid.cursor;
-        match attr {
-            Attr::Foreground(color) => cursor.template.fg = color,
-            Attr::Background(color) => cursor.template.bg = color,
-            Attr::UnderlineColor(color) => cursor.template.set_underline_color(color),
-            Attr::Reset => {
-                cursor.template.fg = AnsiColor::Named(NamedColor::Foreground);
-                cursor.template.bg = AnsiColor::Named(NamedColor::Background);
-                cursor.template.flags = square::Flags::empty();
-                cursor.template.set_underline_color(None);
-            }
-            Attr::Reverse => cursor.template.flags.insert(square::Flags::INVERSE),
-            Attr::CancelReverse => cursor.template.flags.remove(square::Flags::INVERSE),
-            Attr::Bold => cursor.template.flags.insert(square::Flags::BOLD),
-            Attr::CancelBold => cursor.template.flags.remove(square::Flags::BOLD),
-            Attr::Dim => cursor.template.flags.insert(square::Flags::DIM),
-            Attr::CancelBoldDim => cursor
-                .template
-                .flags
-                .remove(square::Flags::BOLD | square::Flags::DIM),
-            Attr::Italic => cursor.template.flags.insert(square::Flags::ITALIC),
-            Attr::CancelItalic => cursor.template.flags.remove(square::Flags::ITALIC),
-            Attr::Underline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERLINE);
-            }
-            Attr::DoubleUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOUBLE_UNDERLINE);
-            }
-            Attr::Undercurl => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor.template.flags.insert(square::Flags::UNDERCURL);
-            }
-            Attr::DottedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DOTTED_UNDERLINE);
-            }
-            Attr::DashedUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES);
-                cursor
-                    .template
-                    .flags
-                    .insert(square::Flags::DASHED_UNDERLINE);
-            }
-            Attr::CancelUnderline => {
-                cursor.template.flags.remove(square::Flags::ALL_UNDERLINES)
-            }
-            Attr::Hidden => cursor.template.flags.insert(square::Flags::HIDDEN),
-            Attr::CancelHidden => cursor.template.flags.remove(square::Flags::HIDDEN),
-            Attr::Strike => cursor.template.flags.insert(square::Flags::STRIKEOUT),
-            Attr::CancelStrike => cursor.template.flags.remove(square::Flags::STRIKEOUT),
-            _ => {
-                warn!("Term got unhandled attr: {:?}", attr);
-            }
-        }
+        self.grid.cursor.template.apply(attr);
     }
 
     fn set_title(&mut self, title: Option<String>) {
@@ -1623,21 +2388,17 @@ impl<U: EventListener> Handler for Crosswords<U> {
     }
 
     #[inline]
-    fn set_cursor_style(&mut self, style: Option<CursorShape>, _blinking: bool) {
-        if let Some(cursor_shape) = style {
-            self.cursor_shape = cursor_shape;
-        } else {
-            self.cursor_shape = CursorShape::default();
-        }
-
-        // self.blinking_cursor = blinking;
-        // self.event_proxy
-        //     .send_event(RioEvent::CursorBlinkingChange, self.window_id);
+    fn set_cursor_style(&mut self, style: Option<CursorShape>, blinking: bool) {
+        // Without a shape the configured style is restored.
+        self.cursor_style = style.map(|shape| CursorStyle { shape, blinking });
+        self.event_proxy
+            .send_event(RioEvent::CursorBlinkingChange, self.window_id);
     }
 
     #[inline]
     fn set_cursor_shape(&mut self, shape: CursorShape) {
-        self.cursor_shape = shape;
+        let blinking = self.cursor_style().blinking;
+        self.cursor_style = Some(CursorStyle { shape, blinking });
     }
 
     #[inline]
@@ -1661,6 +2422,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
             _ => return,
         };
 
+        if base64.len() > self.osc52_max_size {
+            log::debug!("Ignoring OSC 52 payload of {} bytes", base64.len());
+            return;
+        }
+
         if let Ok(bytes) = general_purpose::STANDARD.decode(base64) {
             if let Ok(text) = String::from_utf8(bytes) {
                 self.event_proxy.send_event(
@@ -1683,11 +2449,18 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline(never)]
     fn input(&mut self, c: char) {
-        let width = match c.width() {
-            Some(width) => width,
+        let width = match self.width_cache.char_width(c) {
+            Some(width) => usize::from(width),
             None => return,
         };
 
+        // Keep grapheme clusters in a single cell when mode 2027 is enabled.
+        if self.mode.contains(Mode::GRAPHEME_CLUSTERING)
+            && self.extend_grapheme_cluster(c)
+        {
+            return;
+        }
+
         // Handle zero-width characters.
         if width == 0 {
             // // Get previous column.
@@ -1705,7 +2478,7 @@ impl<U: EventListener> Handler for Crosswords<U> {
                 column.0 = column.saturating_sub(1);
             }
 
-            self.grid[row][column].push_zerowidth(c);
+            self.cell_mut(Pos::new(row, column)).push_zerowidth(c);
             return;
         }
 
@@ -1713,11 +2486,12 @@ impl<U: EventListener> Handler for Crosswords<U> {
             self.wrapline();
         }
 
-        let columns = self.grid.columns();
+        let columns = self.line_end_at_cursor();
         if self.mode.contains(Mode::INSERT) && self.grid.cursor.pos.col + width < columns
         {
             let line = self.grid.cursor.pos.row;
             let col = self.grid.cursor.pos.col;
+            self.damage.damage_line(line.0 as usize, col.0, columns - 1);
             let row = &mut self.grid[line][..];
 
             for col in (col.0..(columns - width)).rev() {
@@ -1743,8 +2517,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
                         .flags
                         .remove(square::Flags::LEADING_WIDE_CHAR_SPACER);
                     self.wrapline();
+                } else if columns >= self.horizontal_margins().start.0 + 2 {
+                    // Without autowrap the glyph takes the last two columns of the line.
+                    self.grid.cursor.pos.col = Column(columns - 2);
                 } else {
-                    // Prevent out of bounds crash when linewrapping is disabled.
+                    // Prevent out of bounds crash when the line has no room for the glyph.
                     self.grid.cursor.should_wrap = true;
                     return;
                 }
@@ -1764,17 +2541,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
             // Write spacer to cell following the wide glyph.
             self.grid.cursor.pos.col += 1;
-            self.grid
-                .cursor
-                .template
-                .flags
-                .insert(square::Flags::WIDE_CHAR_SPACER);
             self.write_at_cursor(' ');
-            self.grid
-                .cursor
-                .template
-                .flags
-                .remove(square::Flags::WIDE_CHAR_SPACER);
+            self.grid.cursor_cell().into_spacer();
         }
 
         if self.grid.cursor.pos.col + 1 < columns {
@@ -1789,7 +2557,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
         match intermediate {
             None => {
                 log::trace!("Reporting primary device attributes");
-                let text = String::from("\x1b[?6c");
+                // VT220 with ANSI color and rectangular editing.
+                let text = String::from("\x1b[?62;22;28c");
                 self.event_proxy
                     .send_event(RioEvent::PtyWrite(text), self.window_id);
             }
@@ -1893,7 +2662,8 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn clear_screen(&mut self, mode: ClearMode) {
-        let bg = self.grid.cursor.template.bg;
+        let reset_mode = self.reset_mode;
+        let erase = self.grid.cursor.template.erase_template(reset_mode);
 
         let screen_lines = self.grid.screen_lines();
 
@@ -1901,17 +2671,17 @@ impl<U: EventListener> Handler for Crosswords<U> {
             ClearMode::Above => {
                 let cursor = self.grid.cursor.pos;
 
-                // If clearing more than one line.
-                if cursor.row > 1 {
-                    // Fully clear all lines before the current line.
-                    self.grid.reset_region(..cursor.row);
+                // Fully clear all lines before the current line.
+                if cursor.row > 0 {
+                    with_erase_template(&mut self.grid, reset_mode, |grid| {
+                        grid.reset_region(..cursor.row)
+                    });
                 }
 
                 // Clear up to the current column in the current line.
                 let end = std::cmp::min(cursor.col + 1, Column(self.grid.columns()));
-                for cell in &mut self.grid[cursor.row][..end] {
-                    *cell = bg.into();
-                }
+                let (_, end) = erase_bounds(&self.grid[cursor.row], Column(0), end);
+                square::fill_row(&mut self.grid[cursor.row], Column(0)..end, &erase);
 
                 let range = Line(0)..=cursor.row;
                 self.selection =
@@ -1919,12 +2689,15 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             ClearMode::Below => {
                 let cursor = self.grid.cursor.pos;
-                for cell in &mut self.grid[cursor.row][cursor.col..] {
-                    *cell = bg.into();
-                }
+                let columns = Column(self.grid.columns());
+                let (start, _) =
+                    erase_bounds(&self.grid[cursor.row], cursor.col, columns);
+                square::fill_row(&mut self.grid[cursor.row], start..columns, &erase);
 
                 if (cursor.row.0 as usize) < screen_lines - 1 {
-                    self.grid.reset_region((cursor.row + 1)..);
+                    with_erase_template(&mut self.grid, reset_mode, |grid| {
+                        grid.reset_region((cursor.row + 1)..)
+                    });
                 }
 
                 let range = cursor.row..Line(screen_lines as i32);
@@ -1933,11 +2706,15 @@ impl<U: EventListener> Handler for Crosswords<U> {
             }
             ClearMode::All => {
                 if self.mode.contains(Mode::ALT_SCREEN) {
-                    self.grid.reset_region(..);
+                    with_erase_template(&mut self.grid, reset_mode, |grid| {
+                        grid.reset_region(..)
+                    });
                 } else {
                     let old_offset = self.grid.display_offset();
 
-                    self.grid.clear_viewport();
+                    with_erase_template(&mut self.grid, reset_mode, |grid| {
+                        grid.clear_viewport()
+                    });
 
                     // Compute number of lines scrolled by clearing the viewport.
                     let lines = self.grid.display_offset().saturating_sub(old_offset);
@@ -2000,29 +2777,41 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
-        println!("{:?}", hyperlink);
         self.grid.cursor.template.set_hyperlink(hyperlink);
     }
 
+    #[inline]
+    fn set_prompt_mark(&mut self, kind: PromptKind, exit_code: Option<i32>) {
+        let line = self.grid.cursor.pos.row;
+        let marks = self.grid[line].prompt_marks_mut();
+
+        // Shells usually emit `D` right before the next `A` on the same line, so every
+        // kind is kept instead of the last one.
+        marks.insert(kind);
+        if kind == PromptKind::CommandEnd {
+            marks.exit_code = exit_code;
+        }
+    }
+
     /// Set the indexed color value.
     #[inline]
-    fn set_color(&mut self, _index: usize, _color: ColorRgb) {
+    fn set_color(&mut self, index: usize, color: ColorRgb) {
         // Damage terminal if the color changed and it's not the cursor.
-        // if index != NamedColor::Cursor as usize && self.colors[index] != Some(color) {
-        // self.mark_fully_damaged();
-        // }
+        if index != NamedColor::Cursor as usize && self.colors[index] != Some(color) {
+            self.mark_fully_damaged();
+        }
 
-        // self.colors[index] = Some(color);
+        self.colors[index] = Some(color);
     }
 
     #[inline]
-    fn reset_color(&mut self, _index: usize) {
+    fn reset_color(&mut self, index: usize) {
         // Damage terminal if the color changed and it's not the cursor.
-        // if index != NamedColor::Cursor as usize && self.colors[index].is_some() {
-        // self.mark_fully_damaged();
-        // }
+        if index != NamedColor::Cursor as usize && self.colors[index].is_some() {
+            self.mark_fully_damaged();
+        }
 
-        // self.colors[index] = None;
+        self.colors[index] = None;
     }
 
     #[inline]
@@ -2037,6 +2826,11 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn clipboard_load(&mut self, clipboard: u8, terminator: &str) {
+        if !self.osc52_read {
+            log::debug!("Ignoring OSC 52 clipboard read");
+            return;
+        }
+
         let clipboard_type = match clipboard {
             b'c' => ClipboardType::Clipboard,
             b'p' | b's' => ClipboardType::Selection,
@@ -2074,23 +2868,20 @@ impl<U: EventListener> Handler for Crosswords<U> {
                 cell.c = c;
             }
 
-            loop {
-                if (self.grid.cursor.pos.col + 1) == self.grid.columns() {
-                    break;
-                }
-
-                self.grid.cursor.pos.col += 1;
-
-                if self.tabs[self.grid.cursor.pos.col] {
-                    break;
-                }
-            }
+            let line_end = Column(self.line_columns(self.grid.cursor.pos.row) - 1);
+            let stop = self.tabs.next_stop(self.grid.cursor.pos.col);
+            self.grid.cursor.pos.col = std::cmp::min(stop, line_end);
         }
     }
 
     #[inline]
     fn carriage_return(&mut self) {
-        let new_col = 0;
+        let left = self.horizontal_margins().start;
+        let new_col = if self.grid.cursor.pos.col >= left {
+            left.0
+        } else {
+            0
+        };
         let row = self.grid.cursor.pos.row.0 as usize;
         self.damage
             .damage_line(row, new_col, self.grid.cursor.pos.col.0);
@@ -2100,25 +2891,44 @@ impl<U: EventListener> Handler for Crosswords<U> {
 
     #[inline]
     fn move_forward_tabs(&mut self, count: u16) {
-        log::trace!("[unimplemented] Moving forward {} tabs", count);
+        self.damage_cursor();
+
+        let old_col = self.grid.cursor.pos.col.0;
+        let line_end = Column(self.line_columns(self.grid.cursor.pos.row) - 1);
+        for _ in 0..count {
+            let stop = self.tabs.next_stop(self.grid.cursor.pos.col);
+            self.grid.cursor.pos.col = std::cmp::min(stop, line_end);
+        }
+        self.grid.cursor.should_wrap = false;
+
+        let line = self.grid.cursor.pos.row.0 as usize;
+        self.damage
+            .damage_line(line, old_col, self.grid.cursor.pos.col.0);
     }
 
     #[inline]
     fn save_cursor_position(&mut self) {
-        self.grid.saved_cursor = self.grid.cursor.clone();
+        self.grid.saved_cursor = SavedCursor {
+            cursor: self.grid.cursor.clone(),
+            origin: self.mode.contains(Mode::ORIGIN),
+            active_charset: self.active_charset,
+        };
     }
 
     #[inline]
     fn restore_cursor_position(&mut self) {
         self.damage_cursor();
-        self.grid.cursor = self.grid.saved_cursor.clone();
+        let saved = self.grid.saved_cursor.clone();
+        self.grid.cursor = saved.cursor;
+        self.mode.set(Mode::ORIGIN, saved.origin);
+        self.active_charset = saved.active_charset;
         self.damage_cursor();
     }
 
     #[inline]
     fn clear_line(&mut self, mode: LineClearMode) {
         let cursor = &self.grid.cursor;
-        let bg = cursor.template.bg;
+        let erase = cursor.template.erase_template(self.reset_mode);
         let point = cursor.pos;
 
         let (left, right) = match mode {
@@ -2128,18 +2938,184 @@ impl<U: EventListener> Handler for Crosswords<U> {
             LineClearMode::All => (Column(0), Column(self.grid.columns())),
         };
 
+        let (left, right) = erase_bounds(&self.grid[point.row], left, right);
         self.damage
             .damage_line(point.row.0 as usize, left.0, right.0 - 1);
 
         let row = &mut self.grid[point.row];
-        for cell in &mut row[left..right] {
-            *cell = bg.into();
+        if right.0 == row.len() {
+            // Clearing up to the end of the line can skip the unoccupied squares.
+            row.reset_from(left, &erase);
+        } else {
+            square::fill_row(row, left..right, &erase);
         }
 
         let range = self.grid.cursor.pos.row..=self.grid.cursor.pos.row;
         self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
     }
 
+    #[inline]
+    fn set_protected(&mut self, protected: bool) {
+        self.grid
+            .cursor
+            .template
+            .flags
+            .set(square::Flags::PROTECTED, protected);
+    }
+
+    #[inline]
+    fn selective_clear_line(&mut self, mode: LineClearMode) {
+        let cursor = &self.grid.cursor;
+        let point = cursor.pos;
+
+        let (left, right) = match mode {
+            LineClearMode::Right if cursor.should_wrap => return,
+            LineClearMode::Right => (point.col, Column(self.grid.columns())),
+            LineClearMode::Left => (Column(0), point.col + 1),
+            LineClearMode::All => (Column(0), Column(self.grid.columns())),
+        };
+
+        self.damage
+            .damage_line(point.row.0 as usize, left.0, right.0 - 1);
+
+        self.selective_erase(point.row, left..right);
+
+        let range = point.row..=point.row;
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+    }
+
+    #[inline]
+    fn selective_clear_screen(&mut self, mode: ClearMode) {
+        let cursor = self.grid.cursor.pos;
+        let columns = Column(self.grid.columns());
+        let screen_lines = self.grid.screen_lines() as i32;
+
+        let (lines, range) = match mode {
+            ClearMode::Above => {
+                let end = std::cmp::min(cursor.col + 1, columns);
+                self.selective_erase(cursor.row, Column(0)..end);
+                (0..cursor.row.0, Line(0)..cursor.row + 1)
+            }
+            ClearMode::Below => {
+                self.selective_erase(cursor.row, cursor.col..columns);
+                (
+                    cursor.row.0 + 1..screen_lines,
+                    cursor.row..Line(screen_lines),
+                )
+            }
+            ClearMode::All => (0..screen_lines, Line(0)..Line(screen_lines)),
+            // Selective erase does not apply to the scrollback.
+            ClearMode::Saved => return,
+        };
+
+        for line in lines {
+            self.selective_erase(Line(line), Column(0)..columns);
+        }
+
+        self.selection = self.selection.take().filter(|s| !s.intersects_range(range));
+        self.mark_fully_damaged();
+    }
+
+    #[inline]
+    fn fill_rect(&mut self, c: char, rect: Rectangle) {
+        let rect = match self.rectangle_area(rect) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        // The area is filled with the current attributes.
+        let template = Square {
+            c,
+            ..self.grid.cursor.template.clone()
+        };
+        for line in rect.lines.start.0..rect.lines.end.0 {
+            square::fill_row(&mut self.grid[Line(line)], rect.columns.clone(), &template);
+        }
+
+        self.rectangle_changed(&rect);
+    }
+
+    #[inline]
+    fn erase_rect(&mut self, rect: Rectangle) {
+        let rect = match self.rectangle_area(rect) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        let erase = self.grid.cursor.template.erase_template(self.reset_mode);
+        for line in (rect.lines.start.0..rect.lines.end.0).map(Line) {
+            let (left, right) =
+                erase_bounds(&self.grid[line], rect.columns.start, rect.columns.end);
+            square::fill_row(&mut self.grid[line], left..right, &erase);
+        }
+
+        self.rectangle_changed(&rect);
+    }
+
+    #[inline]
+    fn selective_erase_rect(&mut self, rect: Rectangle) {
+        let rect = match self.rectangle_area(rect) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        for line in (rect.lines.start.0..rect.lines.end.0).map(Line) {
+            let (left, right) =
+                erase_bounds(&self.grid[line], rect.columns.start, rect.columns.end);
+            self.selective_erase(line, left..right);
+        }
+
+        self.rectangle_changed(&rect);
+    }
+
+    #[inline]
+    fn copy_rect(&mut self, rect: Rectangle, line: Line, col: Column) {
+        let source = match self.rectangle_area(rect) {
+            Some(source) => source,
+            None => return,
+        };
+
+        // Clip the destination the same way as the source.
+        let (lines, columns) = self.origin_bounds();
+        let top = lines.start + line.0;
+        let left = columns.start + col.0;
+        let height = (source.lines.end - source.lines.start).0;
+        let width = source.columns.end.0 - source.columns.start.0;
+        let bottom = std::cmp::min(top + height, lines.end);
+        let right = std::cmp::min(left + width, columns.end);
+        if top >= bottom || left >= right {
+            return;
+        }
+
+        // Copy through a buffer, since both areas may overlap.
+        let start = source.columns.start;
+        let squares: Vec<Vec<Square>> = (0..(bottom - top).0)
+            .map(|offset| {
+                let row = &self.grid[source.lines.start + offset];
+                row[start..start + (right - left).0].to_vec()
+            })
+            .collect();
+
+        for (line, squares) in (top.0..bottom.0).map(Line).zip(squares) {
+            let row = &mut self.grid[line];
+            square::clear_split_wide_char(row, left);
+            square::clear_split_wide_char(row, right);
+
+            for (column, square) in (left.0..right.0).map(Column).zip(squares) {
+                square::recycle_extra(mem::replace(&mut row[column], square).extra);
+            }
+
+            // Halves of wide chars copied without their partner.
+            square::clear_split_wide_char(row, left);
+            square::clear_split_wide_char(row, right);
+        }
+
+        self.rectangle_changed(&Rect {
+            lines: top..bottom,
+            columns: left..right,
+        });
+    }
+
     #[inline]
     fn set_scrolling_region(&mut self, top: usize, bottom: Option<usize>) {
         // Fallback to the last line as default.
@@ -2165,6 +3141,28 @@ impl<U: EventListener> Handler for Crosswords<U> {
         self.goto(Line(0), Column(0));
     }
 
+    #[inline]
+    fn set_left_right_margins(&mut self, left: usize, right: Option<usize>) {
+        if !self.mode.contains(Mode::LEFT_RIGHT_MARGIN) {
+            self.save_cursor_position();
+            return;
+        }
+
+        // Fallback to the last column as default.
+        let columns = self.grid.columns();
+        let right = std::cmp::min(right.unwrap_or(columns), columns);
+
+        if left >= right {
+            warn!("Invalid left and right margins: ({};{})", left, right);
+            return;
+        }
+
+        debug!("Setting left and right margins: ({};{})", left, right);
+
+        self.margins = Column(left - 1)..Column(right);
+        self.goto(Line(0), Column(0));
+    }
+
     #[inline]
     fn text_area_size_pixels(&mut self) {
         debug!("text_area_size_pixels");
@@ -2225,9 +3223,14 @@ pub mod test {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::crosswords::grid::GridSquare;
     use crate::crosswords::pos::{Column, Line, Pos, Side};
+    use crate::crosswords::square::UnderlineStyle;
     use crate::crosswords::test::CrosswordsSize;
     use crate::event::VoidListener;
+    use crate::performer::handler::ParserProcessor;
+    use crate::selection::SelectionType;
+    use rio_config::colors::AnsiColor;
     use winit::window::WindowId;
 
     #[test]
@@ -2315,6 +3318,310 @@ mod tests {
         assert_eq!(cw.grid[Line(0)][Column(4)].c, ' ');
     }
 
+    #[test]
+    fn test_underline_color_sgr() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(5, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in
+            b"\x1b[58:2::1:2:3ma\x1b[58;2;4;5;6mb\x1b[58:5:9mc\x1b[58;5;10md\x1b[59me"
+        {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let rgb = |r, g, b| Some(AnsiColor::Spec(ColorRgb { r, g, b }));
+        assert_eq!(cw.grid[Line(0)][Column(0)].underline_color(), rgb(1, 2, 3));
+        assert_eq!(cw.grid[Line(0)][Column(1)].underline_color(), rgb(4, 5, 6));
+        assert_eq!(
+            cw.grid[Line(0)][Column(2)].underline_color(),
+            Some(AnsiColor::Indexed(9))
+        );
+        assert_eq!(
+            cw.grid[Line(0)][Column(3)].underline_color(),
+            Some(AnsiColor::Indexed(10))
+        );
+        assert_eq!(cw.grid[Line(0)][Column(4)].underline_color(), None);
+        assert!(cw.grid[Line(0)][Column(4)].extra.is_none());
+    }
+
+    #[test]
+    fn test_underlined_padding_is_not_trimmed() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"ab\x1b[4:3m  \x1b[4:2m \x1b[0m" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert!(!cw.grid[Line(0)][Column(4)].is_empty());
+        assert_eq!(cw.grid[Line(0)].line_length(), Column(5));
+    }
+
+    #[test]
+    fn test_grapheme_clustering_mode() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "\x1b[?2027h👩\u{200D}💻\r\n🇧🇷".as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert!(cw.is_mode_set(&AnsiMode::GraphemeClustering));
+
+        let square = &cw.grid[Line(0)][Column(0)];
+        assert_eq!(square.c, '👩');
+        assert_eq!(square.zerowidth(), Some(&['\u{200D}', '💻'][..]));
+        assert!(square.flags.contains(square::Flags::WIDE_CHAR));
+        assert!(cw.grid[Line(0)][Column(1)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(cw.grid[Line(0)][Column(2)].c, ' ');
+
+        let square = &cw.grid[Line(1)][Column(0)];
+        assert_eq!(square.c, '🇧');
+        assert_eq!(square.zerowidth(), Some(&['🇷'][..]));
+        assert!(square.flags.contains(square::Flags::WIDE_CHAR));
+        assert_eq!(cw.grid.cursor.pos.col, Column(2));
+
+        assert_eq!(
+            cw.line_to_string(Line(0), Column(0)..Column(4), false),
+            "👩\u{200D}💻\n"
+        );
+        assert_eq!(
+            cw.line_to_string(Line(1), Column(0)..Column(4), false),
+            "🇧🇷\n"
+        );
+    }
+
+    #[test]
+    fn test_grapheme_clustering_long_sequences() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(6, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let family = "👨\u{1F3FD}\u{200D}👩\u{200D}👧";
+        let text = format!("\x1b[?2027h{family}\r\n🇧🇷🇧🇷");
+        for byte in text.as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
+
+        // The whole family is one cluster, even though each join only looks at the
+        // first and last chars of the cluster.
+        assert_eq!(
+            cw.line_to_string(Line(0), Column(0)..Column(5), false),
+            format!("{family}\n")
+        );
+        assert_eq!(cw.grid[Line(0)][Column(2)].c, ' ');
+
+        // A third regional indicator starts a new flag.
+        assert_eq!(cw.grid[Line(1)][Column(0)].zerowidth(), Some(&['🇷'][..]));
+        assert_eq!(cw.grid[Line(1)][Column(2)].c, '🇧');
+        assert_eq!(cw.grid[Line(1)][Column(2)].zerowidth(), Some(&['🇷'][..]));
+    }
+
+    #[test]
+    fn test_grapheme_clustering_mode_disabled() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(5, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "🇧🇷".as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert_eq!(cw.grid[Line(0)][Column(0)].c, '🇧');
+        assert_eq!(cw.grid[Line(0)][Column(0)].zerowidth(), None);
+    }
+
+    #[test]
+    fn test_memory_stats() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(4, 2, VoidListener {}, WindowId::from(0));
+        assert_eq!(
+            cw.memory_stats(),
+            GridMemoryStats {
+                total_squares: 8,
+                allocated_rows: 2,
+                ..GridMemoryStats::default()
+            }
+        );
+
+        let mut parser = ParserProcessor::new();
+        for byte in "a\u{301}\u{302}e\u{301}".as_bytes() {
+            parser.advance(&mut cw, *byte);
+        }
+
+        // Underline color is shared between the cursor template and the written squares.
+        for byte in b"\x1b[58;5;1mxy\x1b[59m" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert_eq!(
+            cw.memory_stats(),
+            GridMemoryStats {
+                total_squares: 8,
+                squares_with_extra: 4,
+                zerowidth_chars: 3,
+                shared_extras: 2,
+                unique_extras: 2,
+                allocated_rows: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn clear_history_releases_memory() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(4, 2, VoidListener {}, WindowId::from(0));
+        cw.update_history(10_000);
+        let mut parser = ParserProcessor::new();
+        // The first newline only moves the cursor down to the last screen line.
+        for _ in 0..2_001 {
+            parser.advance(&mut cw, b'\n');
+        }
+        cw.scroll_display(Scroll::Delta(5));
+        cw.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(-5), Column(0)),
+            Side::Left,
+        ));
+        assert_eq!(cw.history_size(), 2_000);
+        assert!(cw.memory_stats().allocated_rows > 2_000);
+
+        for byte in b"\x1b[3J" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        assert_eq!(cw.history_size(), 0);
+        assert_eq!(cw.grid.display_offset(), 0);
+        assert!(cw.selection.is_none());
+        let stats = cw.memory_stats();
+        assert_eq!(stats.allocated_rows, 2);
+        assert_eq!(stats.total_squares, 8);
+    }
+
+    #[test]
+    fn test_blink_sgr() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(5, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[5ma\x1b[6mb\x1b[25mc" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let flags = |column| cw.grid[Line(0)][Column(column)].flags;
+        assert_eq!(
+            flags(0) & square::Flags::ALL_BLINKS,
+            square::Flags::SLOW_BLINK
+        );
+        assert_eq!(
+            flags(1) & square::Flags::ALL_BLINKS,
+            square::Flags::ALL_BLINKS
+        );
+        assert!(!flags(2).intersects(square::Flags::ALL_BLINKS));
+    }
+
+    #[test]
+    fn test_hyperlink_osc8() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b]8;;https://rust-lang.org\x1b\\click\x1b]8;;\x1b\\ me" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let hyperlink = cw.grid[Line(0)][Column(0)].hyperlink().unwrap();
+        assert_eq!(hyperlink.uri(), "https://rust-lang.org");
+        for column in 1..5 {
+            assert_eq!(
+                cw.grid[Line(0)][Column(column)].hyperlink(),
+                Some(hyperlink.clone())
+            );
+        }
+        for column in 5..8 {
+            assert_eq!(cw.grid[Line(0)][Column(column)].hyperlink(), None);
+        }
+    }
+
+    #[test]
+    fn test_hyperlink_osc8_with_id() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b]8;id=x;https://example.com\x1b\\text\x1b]8;;\x1b\\" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        for column in 0..4 {
+            let hyperlink = cw.grid[Line(0)][Column(column)].hyperlink().unwrap();
+            assert_eq!(hyperlink.id(), "x");
+            assert_eq!(hyperlink.uri(), "https://example.com");
+        }
+        assert_eq!(cw.grid[Line(0)][Column(4)].hyperlink(), None);
+    }
+
+    #[test]
+    fn hyperlink_range_with_id_across_lines() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = b"a \x1b]8;id=x;https://rio.dev\x1b\\one\x1b]8;;\x1b\\ b\r\n\
+            \x1b]8;id=x;https://rio.dev\x1b\\two\x1b]8;;\x1b\\";
+        for byte in input {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let fragments = vec![
+            Pos::new(Line(0), Column(2))..=Pos::new(Line(0), Column(4)),
+            Pos::new(Line(1), Column(0))..=Pos::new(Line(1), Column(2)),
+        ];
+        let hovered = Pos::new(Line(1), Column(1));
+        assert_eq!(cw.hyperlink_range_at(hovered), Some(fragments));
+        assert_eq!(cw.hyperlink_range_at(Pos::new(Line(0), Column(0))), None);
+
+        // Scrolled back, the lines follow the viewport.
+        cw.update_history(10);
+        for byte in b"\r\n\r\n" {
+            parser.advance(&mut cw, *byte);
+        }
+        cw.scroll_display(Scroll::Delta(1));
+        let fragments = vec![
+            Pos::new(Line(0), Column(2))..=Pos::new(Line(0), Column(4)),
+            Pos::new(Line(1), Column(0))..=Pos::new(Line(1), Column(2)),
+        ];
+        let hovered = Pos::new(Line(-1), Column(2));
+        assert_eq!(cw.hyperlink_range_at(hovered), Some(fragments));
+    }
+
+    #[test]
+    fn hyperlink_range_of_adjacent_links() {
+        let mut cw: Crosswords<VoidListener> =
+            Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = b"\x1b]8;;https://rio.dev\x1b\\ab\x1b]8;;https://rio.dev\x1b\\cd\
+            \x1b]8;id=y;https://rio.dev\x1b\\ef\x1b]8;;\x1b\\";
+        for byte in input {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let run = |start, end| {
+            Some(vec![
+                Pos::new(Line(0), Column(start))..=Pos::new(Line(0), Column(end)),
+            ])
+        };
+        assert_eq!(
+            cw.hyperlink_range_at(Pos::new(Line(0), Column(0))),
+            run(0, 1)
+        );
+        assert_eq!(
+            cw.hyperlink_range_at(Pos::new(Line(0), Column(3))),
+            run(2, 3)
+        );
+        assert_eq!(
+            cw.hyperlink_range_at(Pos::new(Line(0), Column(4))),
+            run(4, 5)
+        );
+        assert_eq!(cw.hyperlink_range_at(Pos::new(Line(0), Column(6))), None);
+    }
+
     #[test]
     fn simple_selection_works() {
         let size = CrosswordsSize::new(5, 5);
@@ -2421,88 +3728,2279 @@ mod tests {
         assert_eq!(term.selection_to_string(), Some(String::from("\"aa\"a\n")));
     }
 
-    #[test]
-    fn block_selection_works() {
-        let size = CrosswordsSize::new(5, 5);
-        let mut term = Crosswords::new(
-            size.columns,
-            size.screen_lines,
-            VoidListener {},
-            WindowId::from(0),
-        );
-        let grid = &mut term.grid;
-        for i in 1..4 {
-            grid[Line(i)][Column(0)].c = '"';
-
-            for j in 1..4 {
-                grid[Line(i)][Column(j)].c = 'a';
-            }
-
-            grid[Line(i)][Column(4)].c = '"';
+    fn selected_line_text(text: &str) -> Option<String> {
+        let mut term = Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in text.as_bytes() {
+            parser.advance(&mut term, *byte);
         }
-        grid[Line(2)][Column(2)].c = ' ';
-        grid[Line(2)][Column(4)]
-            .flags
-            .insert(square::Flags::WRAPLINE);
-        grid[Line(3)][Column(4)].c = ' ';
 
         term.selection = Some(Selection::new(
-            SelectionType::Block,
+            SelectionType::Lines,
             Pos {
                 row: Line(0),
-                col: Column(3),
+                col: Column(0),
             },
             Side::Left,
         ));
+        term.selection_to_string()
+    }
 
-        // The same column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(3),
-                },
-                Side::Right,
-            );
+    #[test]
+    fn selection_keeps_combining_accents() {
+        assert_eq!(
+            selected_line_text("e\u{301}a\u{308}"),
+            Some(String::from("e\u{301}a\u{308}\n"))
+        );
+    }
+
+    #[test]
+    fn selection_keeps_variation_selectors() {
+        assert_eq!(
+            selected_line_text("\u{2764}\u{FE0F}x"),
+            Some(String::from("\u{2764}\u{FE0F}x\n"))
+        );
+    }
+
+    #[test]
+    fn selection_keeps_skin_tone_modifiers() {
+        assert_eq!(
+            selected_line_text("\u{1F44D}\u{1F3FD}"),
+            Some(String::from("\u{1F44D}\u{1F3FD}\n"))
+        );
+    }
+
+    fn snapshot_term() -> Crosswords<VoidListener> {
+        let mut term = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = "\x1b[1;4mab\x1b[0m\u{4f60}e\u{301}\r\nhistory\r\n\r\n\r\nabcdefgh";
+        for byte in input.as_bytes() {
+            parser.advance(&mut term, *byte);
         }
-        assert_eq!(term.selection_to_string(), Some(String::from("\na\na\na")));
+        term
+    }
 
-        // The first column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(0),
-                },
-                Side::Left,
-            );
+    fn grid_text(term: &Crosswords<VoidListener>) -> String {
+        let mut text = String::new();
+        for line in term.grid.topmost_line().0..=term.grid.bottommost_line().0 {
+            for square in &term.grid[Line(line)][..] {
+                square.write_text_to(&mut text);
+            }
+        }
+        text
+    }
+
+    #[test]
+    fn test_snapshot_restore_round_trip() {
+        let term = snapshot_term();
+        let snapshot = term.snapshot();
+        assert_eq!(snapshot.rows.len(), 5);
+
+        let mut restored = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        restored.restore(snapshot.clone()).unwrap();
+        assert_eq!(restored.snapshot(), snapshot);
+
+        let first = &restored.grid[Line(-2)];
+        assert!(first[Column(0)]
+            .flags
+            .contains(square::Flags::BOLD | square::Flags::UNDERLINE));
+        assert!(first[Column(2)].flags.contains(square::Flags::WIDE_CHAR));
+        assert!(first[Column(3)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(first[Column(4)].zerowidth(), Some(&['\u{301}'][..]));
+        assert_eq!(restored.grid.cursor.pos, term.grid.cursor.pos);
+    }
+
+    #[test]
+    fn test_snapshot_restore_rewraps_columns() {
+        let term = snapshot_term();
+        let snapshot = term.snapshot();
+
+        let mut restored = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        restored.restore(snapshot).unwrap();
+
+        assert_eq!(restored.grid.columns(), 5);
+        assert_eq!(restored.grid.screen_lines(), 3);
+        assert!(grid_text(&restored).contains("abcdefgh"));
+        assert!(grid_text(&restored).contains("ab\u{4f60}e\u{301}"));
+    }
+
+    #[test]
+    fn test_snapshot_restore_rejects_invalid_rows() {
+        let mut snapshot = snapshot_term().snapshot();
+        snapshot.rows[1].shrink(4);
+
+        let mut term = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        assert_eq!(
+            term.restore(snapshot),
+            Err(SnapshotError::InvalidRow {
+                index: 1,
+                columns: 4
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_snapshot_serde_round_trip() {
+        let snapshot = snapshot_term().snapshot();
+
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: GridSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+    }
+
+    #[test]
+    fn test_prompt_marks_osc133() {
+        let mut term = Crosswords::new(10, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = "\x1b]133;A\x07$ \x1b]133;B\x07ls\r\n\x1b]133;C\x07out\r\n\x1b]133;D;2\x07\x1b]133;A\x07$ ";
+        for byte in input.as_bytes() {
+            parser.advance(&mut term, *byte);
         }
+
         assert_eq!(
-            term.selection_to_string(),
-            Some(String::from("\n\"aa\n\"a\n\"aa"))
+            term.prompt_marks().collect::<Vec<_>>(),
+            vec![
+                (Line(0), PromptKind::PromptStart, None),
+                (Line(0), PromptKind::CommandStart, None),
+                (Line(1), PromptKind::OutputStart, None),
+                (Line(2), PromptKind::PromptStart, None),
+                (Line(2), PromptKind::CommandEnd, Some(2)),
+            ]
         );
+    }
 
-        // The last column.
-        if let Some(s) = term.selection.as_mut() {
-            s.update(
-                Pos {
-                    row: Line(3),
-                    col: Column(4),
-                },
-                Side::Right,
-            );
+    #[test]
+    fn test_prompt_marks_follow_scrollback() {
+        let mut term = Crosswords::new(10, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "\x1b]133;A\x07$\r\n\r\n\r\n".as_bytes() {
+            parser.advance(&mut term, *byte);
         }
+
         assert_eq!(
-            term.selection_to_string(),
-            Some(String::from("\na\"\na\"\na"))
+            term.prompt_marks().collect::<Vec<_>>(),
+            vec![(Line(-2), PromptKind::PromptStart, None)]
         );
+
+        term.clear_saved_history();
+        assert_eq!(term.prompt_marks().count(), 0);
     }
 
     #[test]
-    fn parse_cargo_version() {
-        assert_eq!(version_number("0.0.1-canary"), 1);
-        assert_eq!(version_number("0.1.2-canary"), 1_02);
-        assert_eq!(version_number("1.2.3-canary"), 1_02_03);
-        assert_eq!(version_number("999.99.99"), 9_99_99_99);
+    fn test_overwrite_wide_char_with_narrow_char() {
+        let mut term = Crosswords::new(4, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "\u{4f60}\ra".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+
+        let row = &term.grid[Line(0)];
+        assert_eq!(row[Column(0)].c, 'a');
+        assert!(!row[Column(0)].is_wide());
+        assert!(!row[Column(1)].is_spacer());
+    }
+
+    #[test]
+    fn test_overwrite_spacer_with_narrow_char() {
+        let mut term = Crosswords::new(4, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "\u{4f60}\x1b[2Gb".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+
+        let row = &term.grid[Line(0)];
+        assert_eq!(row[Column(0)].c, ' ');
+        assert!(!row[Column(0)].is_wide());
+        assert_eq!(row[Column(1)].c, 'b');
+        assert!(!row[Column(1)].is_spacer());
+    }
+
+    fn damaged_lines<U: EventListener>(term: &mut Crosswords<U>) -> Vec<usize> {
+        term.take_damage().map(|bounds| bounds.line).collect()
+    }
+
+    #[test]
+    fn damage_single_cell_write() {
+        let mut term = Crosswords::new(10, 5, VoidListener {}, WindowId::from(0));
+        let _ = term.take_damage();
+
+        term.grid.cursor.pos = Pos::new(Line(2), Column(3));
+        term.write_at_cursor('a');
+
+        assert_eq!(
+            term.take_damage().collect::<Vec<_>>(),
+            vec![LineDamageBounds {
+                line: 2,
+                left: 3,
+                right: 3
+            }]
+        );
+        assert_eq!(term.take_damage().count(), 0);
+    }
+
+    #[test]
+    fn damage_skips_noop_write() {
+        let mut term = Crosswords::new(10, 5, VoidListener {}, WindowId::from(0));
+        term.grid.cursor.pos = Pos::new(Line(1), Column(0));
+        term.write_at_cursor('a');
+        let _ = term.take_damage();
+
+        term.write_at_cursor('a');
+        assert_eq!(term.take_damage().count(), 0);
+
+        term.grid.cursor.template.fg = AnsiColor::Named(NamedColor::Red);
+        term.write_at_cursor('a');
+        assert_eq!(damaged_lines(&mut term), vec![1]);
+    }
+
+    #[test]
+    fn damage_scroll_marks_all_lines() {
+        let mut term = Crosswords::new(10, 5, VoidListener {}, WindowId::from(0));
+        let _ = term.take_damage();
+
+        term.scroll_up_relative(Line(0), 1);
+
+        assert_eq!(damaged_lines(&mut term), (0..5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn damage_resize_marks_all_lines() {
+        let mut term = Crosswords::new(10, 5, VoidListener {}, WindowId::from(0));
+        let _ = term.take_damage();
+
+        term.resize::<CrosswordsSize>(8, 3);
+
+        assert_eq!(
+            term.take_damage().collect::<Vec<_>>(),
+            (0..3)
+                .map(|line| LineDamageBounds {
+                    line,
+                    left: 0,
+                    right: 7
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn damage_squares_changed_in_place() {
+        let mut term = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        feed(&mut term, b"ab");
+        let _ = term.take_damage();
+
+        // A combining char is added to the previous square.
+        feed(&mut term, "\u{301}".as_bytes());
+        assert_eq!(
+            term.take_damage().collect::<Vec<_>>(),
+            vec![LineDamageBounds {
+                line: 0,
+                left: 1,
+                right: 1
+            }]
+        );
+
+        // Inserting shifts every square up to the end of the line.
+        feed(&mut term, b"\x1b[4h\rc");
+        assert_eq!(
+            term.take_damage().collect::<Vec<_>>(),
+            vec![LineDamageBounds {
+                line: 0,
+                left: 0,
+                right: 9
+            }]
+        );
+    }
+
+    #[test]
+    fn damage_follows_display_offset() {
+        let mut term = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        feed(&mut term, b"\r\n\r\n\r\n\r\n");
+        term.scroll_display(Scroll::Delta(1));
+        let _ = term.take_damage();
+
+        // The second line of the grid is shown on the last line of the viewport.
+        term.grid.cursor.pos = Pos::new(Line(1), Column(0));
+        term.write_at_cursor('a');
+        assert_eq!(damaged_lines(&mut term), vec![2]);
+
+        // The last line of the grid is out of view.
+        term.grid.cursor.pos = Pos::new(Line(2), Column(0));
+        term.write_at_cursor('a');
+        assert_eq!(damaged_lines(&mut term), Vec::<usize>::new());
+    }
+
+    fn erased_square(mode: ResetMode, input: &[u8]) -> Square {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        term.reset_mode = mode;
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc\x1b[31;44;7m" {
+            parser.advance(&mut term, *byte);
+        }
+        for byte in input {
+            parser.advance(&mut term, *byte);
+        }
+
+        term.grid[Line(0)][Column(0)].clone()
+    }
+
+    #[test]
+    fn reset_mode_no_bce() {
+        let square = erased_square(ResetMode::NoBce, b"\x1b[2K");
+        assert_eq!(square, Square::default());
+    }
+
+    #[test]
+    fn reset_mode_bce_preserve_bg() {
+        for input in [&b"\x1b[2K"[..], b"\x1b[2J", b"\x1b[H\x1b[3X"] {
+            let square = erased_square(ResetMode::BcePreserveBg, input);
+            assert_eq!(square.c, ' ');
+            assert_eq!(square.bg, AnsiColor::Named(NamedColor::Blue));
+            assert_eq!(square.fg, AnsiColor::Named(NamedColor::Foreground));
+            assert!(!square.flags.contains(square::Flags::INVERSE));
+        }
+    }
+
+    #[test]
+    fn reset_mode_bce_full() {
+        let square = erased_square(ResetMode::BceFull, b"\x1b[2J");
+        assert_eq!(square.c, ' ');
+        assert_eq!(square.bg, AnsiColor::Named(NamedColor::Blue));
+        assert_eq!(square.fg, AnsiColor::Named(NamedColor::Red));
+        assert!(square.flags.contains(square::Flags::INVERSE));
+    }
+
+    #[test]
+    fn erase_after_hyperlink_leaves_no_residue() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        term.reset_mode = ResetMode::BcePreserveBg;
+        let mut parser = ParserProcessor::new();
+        let has_link = |term: &Crosswords<VoidListener>, line: i32| {
+            term.grid[Line(line)][..]
+                .iter()
+                .any(|square| square.hyperlink().is_some())
+        };
+
+        // The erased squares keep the link of the pen, then lose it once the link ends.
+        for byte in b"\x1b]8;;https://example.com\x1b\\ab\x1b[2J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!((0..3).all(|line| has_link(&term, line)));
+        for byte in b"\x1b]8;;\x1b\\\x1b[2J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!((0..3).all(|line| !has_link(&term, line)));
+
+        for byte in b"\x1b]8;;https://example.com\x1b\\\x1b[2K" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(has_link(&term, 0));
+        for byte in b"\x1b]8;;\x1b\\\x1b[2K" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(!has_link(&term, 0));
+    }
+
+    #[test]
+    fn history_is_bounded_by_history_size() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        term.update_history(4);
+        let mut parser = ParserProcessor::new();
+        for i in 1..=20 {
+            for byte in format!("{i}\r\n").bytes() {
+                parser.advance(&mut term, byte);
+            }
+        }
+
+        assert_eq!(term.history_size(), 4);
+
+        // Scrolling all the way back shows the oldest line kept.
+        term.scroll_display(Scroll::Top);
+        assert_eq!(term.grid.display_offset(), 4);
+        assert_eq!(row_text(&term, -4).trim_end(), "15");
+
+        // Shrinking the history clamps the display offset.
+        term.update_history(2);
+        assert_eq!(term.history_size(), 2);
+        assert_eq!(term.grid.display_offset(), 2);
+    }
+
+    #[test]
+    fn scrolled_back_viewport_follows_evicted_history() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        term.update_history(4);
+        let mut parser = ParserProcessor::new();
+        let mut write_lines = |term: &mut Crosswords<VoidListener>, lines| {
+            for i in lines {
+                for byte in format!("\r\n{i}").bytes() {
+                    parser.advance(term, byte);
+                }
+            }
+        };
+        write_lines(&mut term, 1..=4);
+        assert_eq!(term.history_size(), 2);
+
+        // While scrolled back, new lines keep the viewport on the same content.
+        term.scroll_display(Scroll::Delta(1));
+        let top = |term: &Crosswords<VoidListener>| {
+            let line = Line(-(term.grid.display_offset() as i32));
+            term.grid[line][Column(0)].c
+        };
+        assert_eq!(top(&term), '1');
+        write_lines(&mut term, 5..=6);
+        assert_eq!(term.history_size(), 4);
+        assert_eq!(term.grid.display_offset(), 3);
+        assert_eq!(top(&term), '1');
+
+        // Once the oldest lines are evicted, the viewport stops at the oldest kept one.
+        write_lines(&mut term, 7..=9);
+        assert_eq!(term.history_size(), 4);
+        assert_eq!(term.grid.display_offset(), 4);
+        assert_eq!(top(&term), '3');
+        assert_eq!(term.grid[Line(-4)][Column(0)].c, '3');
+        assert_eq!(term.grid[Line(2)][Column(0)].c, '9');
+    }
+
+    #[test]
+    fn display_iter_covers_the_viewport() {
+        let mut term = Crosswords::new(4, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for i in 1..=6 {
+            for byte in format!("{i}\r\n").bytes() {
+                parser.advance(&mut term, byte);
+            }
+        }
+        term.scroll_display(Scroll::Delta(2));
+
+        let squares: Vec<_> = term.display_iter().collect();
+        assert_eq!(squares.len(), 3 * 4);
+        assert_eq!(squares[0].pos, Pos::new(Line(-2), Column(0)));
+        assert_eq!(squares[0].c, '3');
+        for indexed in &squares {
+            assert!(ptr::eq(indexed.square, &term.grid[indexed.pos]));
+        }
+
+        // Iterating from history continues through the screen.
+        let mut iter = term.iter_from(Pos::new(Line(-3), Column(3)));
+        let first = iter.next().unwrap();
+        assert_eq!(first.pos, Pos::new(Line(-2), Column(0)));
+        assert_eq!(iter.last().unwrap().pos, Pos::new(Line(2), Column(3)));
+    }
+
+    #[test]
+    fn to_text_of_blank_grid_is_empty() {
+        let term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        assert_eq!(term.to_text(None), "");
+        assert_eq!(term.visible_to_string(), "");
+    }
+
+    #[test]
+    fn to_text_joins_line_wrapped_at_last_column() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "abcdefghij\r\nk\tl\r\n\u{4f60}".bytes() {
+            parser.advance(&mut term, byte);
+        }
+
+        assert_eq!(row_text(&term, 1), "fghij");
+        assert_eq!(term.to_text(None), "abcdefghij\nk   l\n\u{4f60}");
+        assert_eq!(term.to_text(Some(Line(1)..Line(2))), "fghij");
+    }
+
+    #[test]
+    fn visible_to_string_follows_display_offset() {
+        let mut term = Crosswords::new(4, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"1\r\n2\r\n3\r\n4" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.visible_to_string(), "3\n4");
+        term.scroll_display(Scroll::Delta(1));
+        assert_eq!(term.visible_to_string(), "2\n3");
+        assert_eq!(term.to_text(None), "1\n2\n3\n4");
+    }
+
+    #[test]
+    fn alt_screen_has_no_history() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[?1049h" {
+            parser.advance(&mut term, *byte);
+        }
+        term.update_history(100);
+        for _ in 0..10 {
+            parser.advance(&mut term, b'\n');
+        }
+        assert_eq!(term.history_size(), 0);
+
+        for byte in b"\x1b[?1049l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.max_scroll_limit(), 100);
+    }
+
+    #[test]
+    fn scrolling_region_does_not_create_history() {
+        let mut term = Crosswords::new(3, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\x1b[1;3r" {
+            parser.advance(&mut term, *byte);
+        }
+        // Setting the region homes the cursor.
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+
+        for byte in b"\x1b[3;1H\n\n" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.history_size(), 0);
+        assert_eq!(row_text(&term, 0), "c  ");
+        assert_eq!(row_text(&term, 1), "   ");
+        assert_eq!(row_text(&term, 2), "   ");
+        assert_eq!(row_text(&term, 3), "d  ");
+    }
+
+    #[test]
+    fn scroll_up_mid_screen_region() {
+        let mut term = Crosswords::new(3, 6, VoidListener {}, WindowId::from(0));
+        term.reset_mode = ResetMode::BcePreserveBg;
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\r\ne\r\nf\x1b[2;5r\x1b[44m\x1b[2S" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let texts: Vec<_> = (0..6).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["a  ", "d  ", "e  ", "   ", "   ", "f  "]);
+        assert_eq!(term.history_size(), 0);
+        for line in [3, 4] {
+            let square = &term.grid[Line(line)][Column(0)];
+            assert_eq!(square.bg, AnsiColor::Named(NamedColor::Blue));
+        }
+        assert_eq!(
+            term.grid[Line(5)][Column(0)].bg,
+            AnsiColor::Named(NamedColor::Background)
+        );
+
+        // IND at the bottom and RI at the top of the region scroll it as well.
+        for byte in b"\x1b[5;1H\x1bD\x1b[2;1H\x1bM" {
+            parser.advance(&mut term, *byte);
+        }
+        let texts: Vec<_> = (0..6).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["a  ", "   ", "e  ", "   ", "   ", "f  "]);
+        assert_eq!(term.history_size(), 0);
+    }
+
+    #[test]
+    fn origin_mode_addresses_scrolling_region() {
+        let mut term = Crosswords::new(3, 5, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[2;4r\x1b[?6h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+
+        // Cursor addressing is relative to and clamped by the region.
+        for byte in b"\x1b[2;2H" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(2), Column(1)));
+
+        for byte in b"\x1b[9;1H" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(3), Column(0)));
+    }
+
+    #[test]
+    fn alt_screen_restores_primary_screen_and_cursor() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc\r\nde\x1b[44m\x1b(0" {
+            parser.advance(&mut term, *byte);
+        }
+        let cursor = term.grid.cursor.pos;
+        let template = term.grid.cursor.template.clone();
+
+        for byte in b"\x1b[?1049h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.mode().contains(Mode::ALT_SCREEN));
+        assert_eq!(term.grid.cursor.pos, cursor);
+        // The alternate screen is cleared to the current background.
+        assert_eq!(row_text(&term, 0), "     ");
+        assert_eq!(term.grid[Line(2)][Column(4)].bg, template.bg);
+
+        for byte in b"\x1b[m\x1b(B\x1b[?6h\x1b[Hxyz\r\nw" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "xyz  ");
+
+        for byte in b"\x1b[?1049l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(!term.mode().contains(Mode::ALT_SCREEN));
+        assert!(!term.mode().contains(Mode::ORIGIN));
+        assert_eq!(row_text(&term, 0), "abc  ");
+        assert_eq!(row_text(&term, 1), "de   ");
+        assert_eq!(row_text(&term, 2), "     ");
+        assert_eq!(term.grid.cursor.pos, cursor);
+        assert_eq!(term.grid.cursor.template.bg, template.bg);
+        assert_eq!(term.history_size(), 0);
+
+        // The charset active before entering is back in place.
+        parser.advance(&mut term, b'q');
+        assert_eq!(row_text(&term, 1), "de─  ");
+    }
+
+    #[test]
+    fn scroll_display_clamps_to_history() {
+        let mut term = Crosswords::new(3, 4, VoidListener {}, WindowId::from(0));
+        term.update_history(100);
+        let mut parser = ParserProcessor::new();
+        for _ in 0..13 {
+            parser.advance(&mut term, b'\n');
+        }
+        assert_eq!(term.history_size(), 10);
+        assert_eq!(term.lines_above_viewport(), 10);
+
+        term.reset_damage();
+        term.scroll_display(Scroll::Delta(3));
+        assert_eq!(term.grid.display_offset(), 3);
+        assert_eq!(term.lines_above_viewport(), 7);
+        assert!(term.damage.is_fully_damaged);
+
+        // Scrolling past the top stops at the oldest history line.
+        term.scroll_display(Scroll::Delta(50));
+        assert_eq!(term.grid.display_offset(), 10);
+        term.scroll_display(Scroll::PageUp);
+        assert_eq!(term.grid.display_offset(), 10);
+        assert_eq!(term.lines_above_viewport(), 0);
+
+        // An unchanged offset leaves the damage alone.
+        term.reset_damage();
+        term.scroll_display(Scroll::Top);
+        assert!(!term.damage.is_fully_damaged);
+
+        term.scroll_display(Scroll::PageDown);
+        assert_eq!(term.grid.display_offset(), 6);
+        term.scroll_display(Scroll::Delta(-50));
+        assert_eq!(term.grid.display_offset(), 0);
+
+        term.scroll_display(Scroll::Delta(5));
+        term.scroll_display(Scroll::Bottom);
+        assert_eq!(term.grid.display_offset(), 0);
+    }
+
+    #[test]
+    fn alt_screen_snaps_primary_screen_to_bottom() {
+        let mut term = Crosswords::new(3, 4, VoidListener {}, WindowId::from(0));
+        term.update_history(100);
+        let mut parser = ParserProcessor::new();
+        for _ in 0..13 {
+            parser.advance(&mut term, b'\n');
+        }
+        term.scroll_display(Scroll::Delta(5));
+
+        for byte in b"\x1b[?1049h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.display_offset(), 0);
+        for byte in b"\x1b[?1049l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.display_offset(), 0);
+        assert_eq!(term.history_size(), 10);
+    }
+
+    #[test]
+    fn alt_screen_enter_and_leave_are_idempotent() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc" {
+            parser.advance(&mut term, *byte);
+        }
+        term.leave_alt_screen(true);
+        assert_eq!(row_text(&term, 0), "abc  ");
+
+        term.enter_alt_screen(true);
+        for byte in b"z" {
+            parser.advance(&mut term, *byte);
+        }
+        term.enter_alt_screen(true);
+        assert_eq!(row_text(&term, 0), "   z ");
+
+        term.leave_alt_screen(true);
+        term.leave_alt_screen(true);
+        assert!(!term.mode().contains(Mode::ALT_SCREEN));
+        assert_eq!(row_text(&term, 0), "abc  ");
+    }
+
+    #[test]
+    fn swap_screen_keeps_alternate_contents() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc\x1b[?47h\x1b[Hz" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "z    ");
+
+        for byte in b"\x1b[?47l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abc  ");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(3)));
+
+        for byte in b"\x1b[?47h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "z    ");
+    }
+
+    #[test]
+    fn swap_screen_and_clear_clears_on_exit() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc\x1b[?1047h\x1b[Hz\x1b[?1047l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abc  ");
+        assert!(!term.mode().contains(Mode::ALT_SCREEN));
+
+        for byte in b"\x1b[?1047h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "     ");
+    }
+
+    #[test]
+    fn save_cursor_mode_restores_cursor() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[2;3H\x1b[?1048h\x1b[H" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(0)));
+
+        for byte in b"\x1b[?1048l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+    }
+
+    #[test]
+    fn save_cursor_restores_attributes_and_charsets() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[31;1;4:3m\x1b[58;5;2m\x1b(0\x1b[2;4r\x1b[?6h\x1b[2;3H\x1b7" {
+            parser.advance(&mut term, *byte);
+        }
+        let template = term.grid.cursor.template.clone();
+        let pos = term.grid.cursor.pos;
+
+        for byte in b"\x1b[0;44m\x1b(B\x0e\x1b[?6l\x1b[H" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_ne!(term.grid.cursor.template, template);
+
+        for byte in b"\x1b8" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.template, template);
+        assert_eq!(term.grid.cursor.pos, pos);
+        assert_eq!(
+            term.grid.cursor.charsets[CharsetIndex::G0],
+            pos::StandardCharset::SpecialCharacterAndLineDrawing
+        );
+        assert_eq!(term.active_charset, CharsetIndex::G0);
+        assert!(term.mode().contains(Mode::ORIGIN));
+
+        // CSI ? 1048 l restores the same state.
+        for byte in b"\x1b[0m\x0e\x1b[?6l\x1b[?1048l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.template, template);
+        assert_eq!(term.active_charset, CharsetIndex::G0);
+        assert!(term.mode().contains(Mode::ORIGIN));
+    }
+
+    #[test]
+    fn save_cursor_is_kept_per_screen() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[2;3H\x1b7\x1b[?47h\x1b[1;5H\x1b[32m\x1b7\x1b[H\x1b8" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+
+        for byte in b"\x1b[?47l\x1b8" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+        assert_eq!(
+            term.grid.cursor.template.fg,
+            AnsiColor::Named(NamedColor::Foreground)
+        );
+    }
+
+    #[test]
+    fn swap_screen_clears_selection() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        term.selection = Some(Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(0)),
+            Side::Left,
+        ));
+
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[?1049h" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.selection.is_none());
+    }
+
+    fn select(term: &mut Crosswords<VoidListener>, start: Pos, end: Pos) {
+        let mut selection = Selection::new(SelectionType::Simple, start, Side::Left);
+        selection.update(end, Side::Right);
+        term.selection = Some(selection);
+    }
+
+    #[test]
+    fn selection_follows_text_into_history() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"ab\r\ncd\r\nef" {
+            parser.advance(&mut term, *byte);
+        }
+        select(
+            &mut term,
+            Pos::new(Line(1), Column(0)),
+            Pos::new(Line(1), Column(1)),
+        );
+
+        for byte in b"\r\nx\r\ny\r\nz" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("cd")));
+    }
+
+    #[test]
+    fn selection_cleared_past_end_of_history() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        term.update_history(1);
+        let mut parser = ParserProcessor::new();
+        for byte in b"ab\r\ncd" {
+            parser.advance(&mut term, *byte);
+        }
+        select(
+            &mut term,
+            Pos::new(Line(0), Column(0)),
+            Pos::new(Line(0), Column(1)),
+        );
+
+        for byte in b"\r\nx" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("ab")));
+
+        for byte in b"\r\ny" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.selection.is_none());
+    }
+
+    #[test]
+    fn selection_follows_text_in_scrolling_region() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\x1b[2;4r" {
+            parser.advance(&mut term, *byte);
+        }
+
+        // Lines above the region are not moved.
+        select(
+            &mut term,
+            Pos::new(Line(0), Column(0)),
+            Pos::new(Line(0), Column(0)),
+        );
+        for byte in b"\x1b[4;1H\n" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("a")));
+
+        select(
+            &mut term,
+            Pos::new(Line(2), Column(0)),
+            Pos::new(Line(2), Column(0)),
+        );
+        for byte in b"\n" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("d")));
+
+        // Scrolling the text out of the top of the region drops the selection.
+        for byte in b"\n\n" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(term.selection.is_none());
+    }
+
+    #[test]
+    fn vi_inline_search_within_line() {
+        let mut term = Crosswords::new(10, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a,b,c\r\nd,e" {
+            parser.advance(&mut term, *byte);
+        }
+        term.toggle_vi_mode();
+        term.vi_mode_cursor.pos = Pos::new(Line(0), Column(0));
+
+        term.vi_inline_search(',', Direction::Right, false);
+        assert_eq!(term.vi_mode_cursor.pos, Pos::new(Line(0), Column(1)));
+
+        term.vi_inline_search(',', Direction::Right, true);
+        assert_eq!(term.vi_mode_cursor.pos, Pos::new(Line(0), Column(2)));
+
+        // The search does not continue on the next line.
+        term.vi_mode_cursor.pos = Pos::new(Line(0), Column(4));
+        term.vi_inline_search(',', Direction::Right, false);
+        assert_eq!(term.vi_mode_cursor.pos, Pos::new(Line(0), Column(4)));
+
+        term.vi_inline_search('a', Direction::Left, true);
+        assert_eq!(term.vi_mode_cursor.pos, Pos::new(Line(0), Column(1)));
+    }
+
+    #[test]
+    fn vi_motion_scrolls_to_cursor() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd" {
+            parser.advance(&mut term, *byte);
+        }
+        term.toggle_vi_mode();
+
+        term.vi_motion(ViMotion::Up);
+        assert_eq!(term.grid.display_offset(), 0);
+
+        term.vi_motion(ViMotion::Up);
+        assert_eq!(term.vi_mode_cursor.pos.row, Line(-1));
+        assert_eq!(term.grid.display_offset(), 1);
+    }
+
+    fn cursor_column_after(term: &mut Crosswords<VoidListener>, bytes: &[u8]) -> usize {
+        let mut parser = ParserProcessor::new();
+        for byte in bytes {
+            parser.advance(term, *byte);
+        }
+        term.grid.cursor.pos.col.0
+    }
+
+    #[test]
+    fn tab_stops_set_and_clear() {
+        let mut term = Crosswords::new(20, 1, VoidListener {}, WindowId::from(0));
+
+        // Default stops every 8 columns, the last column ends the walk.
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 8);
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 16);
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 19);
+
+        // HTS at column 4, then TBC for the stop at column 8.
+        assert_eq!(cursor_column_after(&mut term, b"\r\x1b[5G\x1bH"), 4);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[9G\x1b[g\r"), 0);
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 4);
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 16);
+
+        // Clearing all stops sends tabs to the last column.
+        assert_eq!(cursor_column_after(&mut term, b"\r\x1b[3g\t"), 19);
+    }
+
+    #[test]
+    fn tab_stops_forward_and_backward() {
+        let mut term = Crosswords::new(30, 1, VoidListener {}, WindowId::from(0));
+
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[2I"), 16);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[I"), 24);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[5I"), 29);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[Z"), 24);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[2Z"), 8);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[9Z"), 0);
+
+        // CHT only moves the cursor.
+        assert_eq!(row_text(&term, 0).trim_end(), "");
+    }
+
+    #[test]
+    fn tab_stops_next_and_prev_stop() {
+        let mut tabs = TabStops::new(20);
+        assert_eq!(tabs.next_stop(Column(0)), Column(8));
+        assert_eq!(tabs.next_stop(Column(8)), Column(16));
+        assert_eq!(tabs.next_stop(Column(16)), Column(19));
+        assert_eq!(tabs.next_stop(Column(19)), Column(19));
+        assert_eq!(tabs.prev_stop(Column(19)), Column(16));
+        assert_eq!(tabs.prev_stop(Column(0)), Column(0));
+
+        tabs.clear_all();
+        tabs[Column(3)] = true;
+        tabs[Column(10)] = true;
+        assert_eq!(tabs.next_stop(Column(0)), Column(3));
+        assert_eq!(tabs.next_stop(Column(3)), Column(10));
+        assert_eq!(tabs.next_stop(Column(10)), Column(19));
+        assert_eq!(tabs.prev_stop(Column(10)), Column(3));
+        assert_eq!(tabs.prev_stop(Column(3)), Column(3));
+    }
+
+    #[test]
+    fn tabs_on_copy() {
+        let mut term = Crosswords::new(20, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\tb\tc" {
+            parser.advance(&mut term, *byte);
+        }
+
+        select(
+            &mut term,
+            Pos::new(Line(0), Column(0)),
+            Pos::new(Line(0), Column(12)),
+        );
+        assert_eq!(term.selection_to_string(), Some(String::from("a\tb\t")));
+
+        // Expanded tabs stop at the end of the selection.
+        term.expand_tabs_on_copy = true;
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("a       b    "))
+        );
+    }
+
+    #[test]
+    fn double_width_line_halves_columns() {
+        let mut term = Crosswords::new(10, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b#6abcdefgh" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.grid[Line(0)].line_attr(), LineAttr::DoubleWidth);
+        assert_eq!(term.line_columns(Line(0)), 5);
+        assert_eq!(term.line_columns(Line(1)), 10);
+        assert_eq!(row_text(&term, 0), "abcde     ");
+        assert_eq!(row_text(&term, 1), "fgh       ");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(3)));
+
+        // Cursor motions stop at the last shown column.
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[1;9H"), 4);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[1;1H\x1b[9C"), 4);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[1;1H\t"), 4);
+
+        // DECSWL restores the full width.
+        for byte in b"\x1b#5" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.line_columns(Line(0)), 10);
+        assert_eq!(cursor_column_after(&mut term, b"\x1b[1;9H"), 8);
+    }
+
+    #[test]
+    fn double_height_line_clamps_cursor() {
+        let mut term = Crosswords::new(10, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[1;8H\x1b#3\x1b[2;8H\x1b#4" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.grid[Line(0)].line_attr(), LineAttr::DoubleHeightTop);
+        assert_eq!(term.grid[Line(1)].line_attr(), LineAttr::DoubleHeightBottom);
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(4)));
+
+        // Clearing the screen resets the line attributes.
+        for byte in b"\x1b[2J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid[Line(0)].line_attr(), LineAttr::Normal);
+    }
+
+    fn numbered_lines(lines: usize) -> Vec<u8> {
+        let text: Vec<String> = (0..lines).map(|line| line.to_string()).collect();
+        text.join("\r\n").into_bytes()
+    }
+
+    #[test]
+    fn resize_shrink_and_grow_preserves_content() {
+        let mut term = Crosswords::new(3, 10, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in numbered_lines(10) {
+            parser.advance(&mut term, byte);
+        }
+        let original: Vec<_> = (0..10).map(|line| row_text(&term, line)).collect();
+        let cursor = term.grid.cursor.pos;
+
+        // Lines above the cursor go to the history.
+        term.resize::<CrosswordsSize>(3, 5);
+        assert_eq!(term.history_size(), 5);
+        assert_eq!(row_text(&term, 0), "5  ");
+        assert_eq!(term.grid.cursor.pos.row, Line(4));
+
+        term.resize::<CrosswordsSize>(3, 10);
+        let texts: Vec<_> = (0..10).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, original);
+        assert_eq!(term.grid.cursor.pos, cursor);
+        assert_eq!(term.history_size(), 0);
+    }
+
+    #[test]
+    fn resize_keeps_saved_cursor_on_its_line() {
+        let mut term = Crosswords::new(3, 10, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[9;2H\x1b7\x1b[10;1H" {
+            parser.advance(&mut term, *byte);
+        }
+
+        term.resize::<CrosswordsSize>(3, 5);
+        assert_eq!(term.grid.saved_cursor.cursor.pos.row, Line(3));
+
+        term.resize::<CrosswordsSize>(3, 10);
+        for byte in b"\x1b8" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(8), Column(1)));
+    }
+
+    #[test]
+    fn resize_alternate_screen_truncates() {
+        let mut term = Crosswords::new(3, 10, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in numbered_lines(10) {
+            parser.advance(&mut term, byte);
+        }
+        for byte in b"\x1b[?1049h" {
+            parser.advance(&mut term, *byte);
+        }
+        for byte in numbered_lines(10) {
+            parser.advance(&mut term, byte);
+        }
+
+        term.resize::<CrosswordsSize>(3, 5);
+        term.resize::<CrosswordsSize>(3, 10);
+        assert_eq!(term.history_size(), 0);
+        assert_eq!(row_text(&term, 0), "5  ");
+        assert_eq!(row_text(&term, 5), "   ");
+
+        // The primary screen kept everything through the history.
+        for byte in b"\x1b[?1049l" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "0  ");
+        assert_eq!(row_text(&term, 9), "9  ");
+    }
+
+    #[test]
+    fn tab_stops_extend_on_resize() {
+        let mut term = Crosswords::new(10, 1, VoidListener {}, WindowId::from(0));
+        cursor_column_after(&mut term, b"\x1b[3g\x1b[3G\x1bH\r");
+
+        term.resize::<CrosswordsSize>(20, 1);
+
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 2);
+        // Columns beyond the previous width get the default stops.
+        assert_eq!(cursor_column_after(&mut term, b"\t"), 16);
+    }
+
+    #[test]
+    fn kitty_unknown_underline_style_is_ignored() {
+        let mut term = Crosswords::new(4, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[4:3ma\x1b[4:9mb\x1b[4:1mc\x1b[4:0md" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let styles: Vec<_> = term.grid[Line(0)][..]
+            .iter()
+            .map(|square| square.underline_style())
+            .collect();
+        assert_eq!(
+            styles,
+            vec![
+                Some(UnderlineStyle::Curly),
+                Some(UnderlineStyle::Curly),
+                Some(UnderlineStyle::Single),
+                None,
+            ]
+        );
+    }
+
+    fn row_text(term: &Crosswords<VoidListener>, line: i32) -> String {
+        term.grid[Line(line)][..]
+            .iter()
+            .map(|square| square.c)
+            .collect()
+    }
+
+    #[test]
+    fn selective_erase_skips_protected_squares() {
+        let mut term = Crosswords::new(6, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"ab\x1b[1\"qcd\x1b[0\"qef\r\n\x1b[1\"qgh\x1b[2\"qij" {
+            parser.advance(&mut term, *byte);
+        }
+
+        for byte in b"\x1b[1;1H\x1b[?K" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "  cd  ");
+        assert!(term.grid[Line(0)][Column(2)].is_protected());
+
+        for byte in b"\x1b[?2J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "  cd  ");
+        assert_eq!(row_text(&term, 1), "gh    ");
+    }
+
+    #[test]
+    fn ordinary_erase_clears_protected_squares() {
+        let mut term = Crosswords::new(4, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[1\"qabcd\x1b[1;1H\x1b[2K" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(row_text(&term, 0), "    ");
+        assert!(!term.grid[Line(0)][Column(0)].is_protected());
+    }
+
+    #[test]
+    fn selective_erase_above_and_below_cursor() {
+        let mut term = Crosswords::new(3, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abc\r\nd\x1b[1\"qe\x1b[0\"qf\r\nghi" {
+            parser.advance(&mut term, *byte);
+        }
+
+        for byte in b"\x1b[2;2H\x1b[?1J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "   ");
+        assert_eq!(row_text(&term, 1), " ef");
+        assert_eq!(row_text(&term, 2), "ghi");
+
+        for byte in b"\x1b[?0J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), " e ");
+        assert_eq!(row_text(&term, 2), "   ");
+    }
+
+    #[test]
+    fn reset_mode_scroll_fills_new_lines() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        term.reset_mode = ResetMode::BceFull;
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[31;44;7m\x1b[S" {
+            parser.advance(&mut term, *byte);
+        }
+
+        let square = &term.grid[Line(2)][Column(0)];
+        assert_eq!(square.bg, AnsiColor::Named(NamedColor::Blue));
+        assert_eq!(square.fg, AnsiColor::Named(NamedColor::Red));
+        // The cursor template itself is left untouched.
+        assert_eq!(
+            term.grid.cursor.template.fg,
+            AnsiColor::Named(NamedColor::Red)
+        );
+    }
+
+    #[test]
+    fn scrolling_region_with_left_right_margins() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcde\r\nfghij\r\nklmno\r\npqrst" {
+            parser.advance(&mut term, *byte);
+        }
+
+        for byte in b"\x1b[?69h\x1b[2;4s\x1b[2;4r\x1b[4;3H\n" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(row_text(&term, 0), "abcde");
+        assert_eq!(row_text(&term, 1), "flmnj");
+        assert_eq!(row_text(&term, 2), "kqrso");
+        assert_eq!(row_text(&term, 3), "p   t");
+        assert_eq!(term.history_size(), 0);
+    }
+
+    #[test]
+    fn wrap_and_carriage_return_within_margins() {
+        let mut term = Crosswords::new(6, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[?69h\x1b[2;4s\x1b[1;2Habcde\rX" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(row_text(&term, 0), " abc  ");
+        assert_eq!(row_text(&term, 1), " Xe   ");
+    }
+
+    #[test]
+    fn insert_and_delete_chars_within_margins() {
+        let mut term = Crosswords::new(6, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcdef\x1b[?69h\x1b[2;5s\x1b[1;3H\x1b[@" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "ab cdf");
+
+        for byte in b"\x1b[2P" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abd  f");
+    }
+
+    #[test]
+    fn insert_and_delete_chars_without_margins() {
+        let mut term = Crosswords::new(6, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcdef\x1b[1;3H\x1b[2@" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "ab  cd");
+
+        for byte in b"\x1b[3P" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abd   ");
+    }
+
+    #[test]
+    fn insert_and_delete_chars_outside_margins() {
+        let mut term = Crosswords::new(6, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcdef\x1b[?69h\x1b[2;5s\x1b[1;1H\x1b[@\x1b[P\x1b[1;6H\x1b[@\x1b[P"
+        {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(row_text(&term, 0), "abcdef");
+    }
+
+    #[test]
+    fn insert_and_delete_lines_without_margins() {
+        let mut term = Crosswords::new(3, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"aaa\r\nbbb\r\nccc\r\nddd\x1b[2;3H\x1b[L" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "aaa");
+        assert_eq!(row_text(&term, 1), "   ");
+        assert_eq!(row_text(&term, 2), "bbb");
+        assert_eq!(row_text(&term, 3), "ccc");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(0)));
+
+        for byte in b"\x1b[2M" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "ccc");
+        assert_eq!(row_text(&term, 2), "   ");
+        assert_eq!(row_text(&term, 3), "   ");
+    }
+
+    #[test]
+    fn insert_lines_at_top_of_scroll_region() {
+        let mut term = Crosswords::new(1, 6, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\r\ne\r\nf\x1b[2;5r\x1b[2;1H\x1b[41m\x1b[2L" {
+            parser.advance(&mut term, *byte);
+        }
+        let texts: Vec<_> = (0..6).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["a", " ", " ", "b", "c", "f"]);
+
+        // Inserted lines use the background color.
+        let red = AnsiColor::Named(NamedColor::Red);
+        assert_eq!(term.grid[Line(1)][Column(0)].bg, red);
+        assert_eq!(term.grid[Line(2)][Column(0)].bg, red);
+        assert_ne!(term.grid[Line(3)][Column(0)].bg, red);
+    }
+
+    #[test]
+    fn delete_lines_in_middle_of_scroll_region() {
+        let mut term = Crosswords::new(1, 6, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\r\ne\r\nf\x1b[2;5r\x1b[3;1H\x1b[41m\x1b[M" {
+            parser.advance(&mut term, *byte);
+        }
+        let texts: Vec<_> = (0..6).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["a", "b", "d", "e", " ", "f"]);
+        let red = AnsiColor::Named(NamedColor::Red);
+        assert_eq!(term.grid[Line(4)][Column(0)].bg, red);
+
+        // Deleting more lines than are left stops at the bottom margin.
+        for byte in b"\x1b[9M" {
+            parser.advance(&mut term, *byte);
+        }
+        let texts: Vec<_> = (0..6).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["a", "b", " ", " ", " ", "f"]);
+    }
+
+    #[test]
+    fn delete_lines_at_top_does_not_create_history() {
+        let mut term = Crosswords::new(1, 3, VoidListener {}, WindowId::from(0));
+        term.update_history(100);
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\x1b[H\x1b[2M" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.history_size(), 0);
+        let texts: Vec<_> = (0..3).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["c", " ", " "]);
+    }
+
+    #[test]
+    fn insert_and_delete_lines_within_margins() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcde\r\nfghij\r\nklmno\r\npqrst" {
+            parser.advance(&mut term, *byte);
+        }
+
+        // Outside of the scrolling region or the margins nothing moves.
+        for byte in b"\x1b[?69h\x1b[2;4s\x1b[2;4r\x1b[1;3H\x1b[L\x1b[3;1H\x1b[M" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "fghij");
+        assert_eq!(row_text(&term, 2), "klmno");
+
+        for byte in b"\x1b[2;3H\x1b[L" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abcde");
+        assert_eq!(row_text(&term, 1), "f   j");
+        assert_eq!(row_text(&term, 2), "kghio");
+        assert_eq!(row_text(&term, 3), "plmnt");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+
+        for byte in b"\x1b[M" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "fghij");
+        assert_eq!(row_text(&term, 2), "klmno");
+        assert_eq!(row_text(&term, 3), "p   t");
+    }
+
+    #[test]
+    fn erase_chars_does_not_shift() {
+        let mut term = Crosswords::new(6, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcdef\x1b[1;2H\x1b[3X" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "a   ef");
+        assert_eq!(term.grid.cursor.pos.col, Column(1));
+
+        // Like xterm, ECH is not bounded by the left and right margins.
+        for byte in b"\x1b[?69h\x1b[2;3s\x1b[1;5H\x1b[9X" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "a     ");
+    }
+
+    fn has_wide_flags(term: &Crosswords<VoidListener>, line: i32) -> bool {
+        term.grid[Line(line)][..].iter().any(|square| {
+            square
+                .flags
+                .intersects(square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER)
+        })
+    }
+
+    #[test]
+    fn autowrap_wraps_on_next_char() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"abcde" {
+            parser.advance(&mut term, *byte);
+        }
+        // The cursor stays on the last column until the next char.
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+        assert!(term.grid.cursor.should_wrap);
+        let last = |term: &Crosswords<VoidListener>| term.grid[Line(0)][Column(4)].flags;
+        assert!(!last(&term).contains(square::Flags::WRAPLINE));
+
+        parser.advance(&mut term, b'f');
+        assert!(last(&term).contains(square::Flags::WRAPLINE));
+        assert_eq!(row_text(&term, 0), "abcde");
+        assert_eq!(row_text(&term, 1), "f    ");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+    }
+
+    #[test]
+    fn autowrap_disabled_overwrites_last_column() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[?7labcdefg" {
+            parser.advance(&mut term, *byte);
+        }
+        assert!(!term.mode().contains(Mode::LINE_WRAP));
+        assert_eq!(row_text(&term, 0), "abcdg");
+        assert_eq!(row_text(&term, 1), "     ");
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(4)));
+        assert!(!term.grid[Line(0)][Column(4)]
+            .flags
+            .contains(square::Flags::WRAPLINE));
+
+        term.set_autowrap(true);
+        parser.advance(&mut term, b'h');
+        assert_eq!(row_text(&term, 1), "h    ");
+    }
+
+    #[test]
+    fn autowrap_wide_char_at_last_column() {
+        // With autowrap the wide char moves to the next line.
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "abcd字".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        let last = term.grid[Line(0)][Column(4)].flags;
+        assert!(last.contains(square::Flags::LEADING_WIDE_CHAR_SPACER));
+        assert!(last.contains(square::Flags::WRAPLINE));
+        assert_eq!(term.grid[Line(1)][Column(0)].c, '字');
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+
+        // Without it, the wide char takes the last two columns.
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        for byte in "\x1b[?7labcd字".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "abc字 ");
+        assert!(term.grid[Line(0)][Column(3)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR));
+        assert!(term.grid[Line(0)][Column(4)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(row_text(&term, 1), "     ");
+        assert!(term.grid.cursor.should_wrap);
+
+        // Writing over the spacer clears the wide char.
+        parser.advance(&mut term, b'x');
+        assert_eq!(row_text(&term, 0), "abc x");
+        assert!(!has_wide_flags(&term, 0));
+    }
+
+    fn erased_line(mode: &[u8]) -> Crosswords<VoidListener> {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = "abcdeno\x1b[1;3Hc\u{301}\x1b[1;3H\x1b[44m".bytes();
+        for byte in input.chain(mode.iter().copied()) {
+            parser.advance(&mut term, byte);
+        }
+        term
+    }
+
+    #[test]
+    fn erase_in_line_variants() {
+        let blue = AnsiColor::Named(NamedColor::Blue);
+        let wrapped = |term: &Crosswords<VoidListener>| {
+            term.grid[Line(0)][Column(4)]
+                .flags
+                .contains(square::Flags::WRAPLINE)
+        };
+        assert!(wrapped(&erased_line(b"")));
+
+        let term = erased_line(b"\x1b[K");
+        assert_eq!(row_text(&term, 0), "ab   ");
+        assert_eq!(row_text(&term, 1), "no   ");
+        assert!(term.grid[Line(0)][Column(2)].extra.is_none());
+        assert_eq!(term.grid[Line(0)][Column(4)].bg, blue);
+        assert!(!wrapped(&term));
+
+        let term = erased_line(b"\x1b[1K");
+        assert_eq!(row_text(&term, 0), "   de");
+        assert!(term.grid[Line(0)][Column(2)].extra.is_none());
+        assert_eq!(term.grid[Line(0)][Column(0)].bg, blue);
+        assert_ne!(term.grid[Line(0)][Column(3)].bg, blue);
+        // The end of the line is kept, and so is its wrap.
+        assert!(wrapped(&term));
+
+        let term = erased_line(b"\x1b[2K");
+        assert_eq!(row_text(&term, 0), "     ");
+        assert_eq!(row_text(&term, 1), "no   ");
+        assert_eq!(term.grid[Line(0)][Column(4)].bg, blue);
+        assert!(!wrapped(&term));
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(0), Column(2)));
+    }
+
+    #[test]
+    fn erase_in_line_splits_wide_chars() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        // Erasing right from the spacer takes the wide char too.
+        for byte in "a字b\r\na字b\x1b[1;3H\x1b[K".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "a    ");
+        assert!(!has_wide_flags(&term, 0));
+
+        // Erasing left up to the wide char takes its spacer too.
+        for byte in b"\x1b[2;2H\x1b[1K" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "   b ");
+        assert!(!has_wide_flags(&term, 1));
+    }
+
+    #[test]
+    fn erase_in_display_splits_wide_chars() {
+        let mut term = Crosswords::new(5, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "a字b\r\na字b\r\na字b\x1b[3;3H\x1b[J".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "a字 b ");
+        assert_eq!(row_text(&term, 2), "a    ");
+        assert!(!has_wide_flags(&term, 2));
+
+        // Every line above the cursor is cleared, including the first one.
+        for byte in b"\x1b[2;2H\x1b[1J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "     ");
+        assert_eq!(row_text(&term, 1), "   b ");
+        assert!(!has_wide_flags(&term, 1));
+    }
+
+    #[test]
+    fn fill_rectangle() {
+        let mut term = Crosswords::new(5, 4, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[88;2;2;3;4$x" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "     ");
+        assert_eq!(row_text(&term, 1), " XXX ");
+        assert_eq!(row_text(&term, 2), " XXX ");
+        assert_eq!(row_text(&term, 3), "     ");
+
+        // In origin mode the area is relative to the scrolling region and clipped by it.
+        for byte in b"\x1b[3;4r\x1b[?6h\x1b[69;2;1;9;2$x" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), " XXX ");
+        assert_eq!(row_text(&term, 2), " XXX ");
+        assert_eq!(row_text(&term, 3), "EE   ");
+    }
+
+    #[test]
+    fn erase_rectangle_splits_wide_chars() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "a字b\x1b[1;3;1;3$z".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "a  b ");
+        assert!(!has_wide_flags(&term, 0));
+    }
+
+    #[test]
+    fn selective_erase_rectangle() {
+        let mut term = Crosswords::new(4, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[1\"qab\x1b[0\"qcd\r\nefgh\x1b[1;2;2;4${" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "ab  ");
+        assert_eq!(row_text(&term, 1), "e   ");
+    }
+
+    #[test]
+    fn copy_rectangle() {
+        let mut term = Crosswords::new(6, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        // Overlapping areas are copied as they were before the copy.
+        for byte in b"abcdef\x1b[1;1;1;4;1;1;3$v" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 0), "ababcd");
+
+        // The destination is clipped by the screen.
+        for byte in b"\x1b[1;1;1;6;1;2;5$v" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(row_text(&term, 1), "    ab");
+    }
+
+    #[test]
+    fn copy_rectangle_splits_wide_chars() {
+        let mut term = Crosswords::new(6, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in "字bc字\x1b[1;2;1;3;1;1;4$v".as_bytes() {
+            parser.advance(&mut term, *byte);
+        }
+
+        // The spacer is copied without its wide char, and the copy cuts the last wide char.
+        assert_eq!(row_text(&term, 0), "字 b b ");
+        assert!(term.grid[Line(0)][Column(0)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR));
+        for column in 3..6 {
+            assert!(!term.grid[Line(0)][Column(column)]
+                .flags
+                .intersects(square::Flags::WIDE_CHAR | square::Flags::WIDE_CHAR_SPACER));
+        }
+    }
+
+    fn erased_display(mode: &[u8]) -> Crosswords<VoidListener> {
+        let mut term = Crosswords::new(3, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let input = "abc\r\nde\u{301}f\r\nghi\x1b[2;2H\x1b[44m".bytes();
+        for byte in input.chain(mode.iter().copied()) {
+            parser.advance(&mut term, byte);
+        }
+        term
+    }
+
+    #[test]
+    fn erase_in_display_variants() {
+        let blue = AnsiColor::Named(NamedColor::Blue);
+
+        let term = erased_display(b"\x1b[J");
+        let texts: Vec<_> = (0..3).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["abc", "d  ", "   "]);
+        assert!(term.grid[Line(1)][Column(1)].extra.is_none());
+        assert_eq!(term.grid[Line(1)][Column(1)].bg, blue);
+        assert_eq!(term.grid[Line(2)][Column(0)].bg, blue);
+
+        let term = erased_display(b"\x1b[1J");
+        let texts: Vec<_> = (0..3).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["   ", "  f", "ghi"]);
+        assert!(term.grid[Line(1)][Column(1)].extra.is_none());
+        assert_eq!(term.grid[Line(0)][Column(2)].bg, blue);
+
+        let term = erased_display(b"\x1b[2J");
+        let texts: Vec<_> = (0..3).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["   ", "   ", "   "]);
+        assert_eq!(term.grid[Line(2)][Column(2)].bg, blue);
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+
+        // Without history, clearing it leaves the screen alone.
+        let term = erased_display(b"\x1b[3J");
+        let texts: Vec<_> = (0..3).map(|line| row_text(&term, line)).collect();
+        assert_eq!(texts, ["abc", "def", "ghi"]);
+        assert_eq!(term.history_size(), 0);
+    }
+
+    #[test]
+    fn erase_display_keeps_cursor_and_clears_history() {
+        let mut term = Crosswords::new(3, 2, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"a\r\nb\r\nc\r\nd\x1b[2J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(1)));
+        assert_eq!(row_text(&term, 0), "   ");
+        assert_eq!(row_text(&term, 1), "   ");
+        assert!(term.history_size() > 0);
+
+        term.scroll_display(Scroll::Delta(1));
+        assert_eq!(term.grid.display_offset(), 1);
+
+        for byte in b"\x1b[3J" {
+            parser.advance(&mut term, *byte);
+        }
+        assert_eq!(term.history_size(), 0);
+        assert_eq!(term.grid.display_offset(), 0);
+    }
+
+    #[test]
+    fn save_cursor_without_left_right_margin_mode() {
+        let mut term = Crosswords::new(6, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[2;3H\x1b[s\x1b[1;1H\x1b[u" {
+            parser.advance(&mut term, *byte);
+        }
+
+        assert_eq!(term.grid.cursor.pos, Pos::new(Line(1), Column(2)));
+        assert!(!term.has_horizontal_margins());
+    }
+
+    #[test]
+    fn selection_keeps_zerowidth_chars() {
+        let mut term = Crosswords::new(5, 2, VoidListener {}, WindowId::from(0));
+        feed(&mut term, "e\u{301}你\r\n".as_bytes());
+
+        let mut selection = Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(0)),
+            Side::Left,
+        );
+        selection.update(Pos::new(Line(0), Column(2)), Side::Right);
+        term.selection = Some(selection);
+
+        assert_eq!(term.selection_to_string(), Some(String::from("e\u{301}你")));
+    }
+
+    #[test]
+    fn block_selection_works() {
+        let size = CrosswordsSize::new(5, 5);
+        let mut term = Crosswords::new(
+            size.columns,
+            size.screen_lines,
+            VoidListener {},
+            WindowId::from(0),
+        );
+        let grid = &mut term.grid;
+        for i in 1..4 {
+            grid[Line(i)][Column(0)].c = '"';
+
+            for j in 1..4 {
+                grid[Line(i)][Column(j)].c = 'a';
+            }
+
+            grid[Line(i)][Column(4)].c = '"';
+        }
+        grid[Line(2)][Column(2)].c = ' ';
+        grid[Line(2)][Column(4)]
+            .flags
+            .insert(square::Flags::WRAPLINE);
+        grid[Line(3)][Column(4)].c = ' ';
+
+        term.selection = Some(Selection::new(
+            SelectionType::Block,
+            Pos {
+                row: Line(0),
+                col: Column(3),
+            },
+            Side::Left,
+        ));
+
+        // The same column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(3),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(term.selection_to_string(), Some(String::from("\na\na\na")));
+
+        // The first column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(0),
+                },
+                Side::Left,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("\n\"aa\n\"a\n\"aa"))
+        );
+
+        // The last column.
+        if let Some(s) = term.selection.as_mut() {
+            s.update(
+                Pos {
+                    row: Line(3),
+                    col: Column(4),
+                },
+                Side::Right,
+            );
+        }
+        assert_eq!(
+            term.selection_to_string(),
+            Some(String::from("\na\"\na\"\na"))
+        );
+    }
+
+    #[test]
+    fn parse_cargo_version() {
+        assert_eq!(version_number("0.0.1-canary"), 1);
+        assert_eq!(version_number("0.1.2-canary"), 1_02);
+        assert_eq!(version_number("1.2.3-canary"), 1_02_03);
+        assert_eq!(version_number("999.99.99"), 9_99_99_99);
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingListener {
+        stores: Arc<std::sync::Mutex<Vec<(ClipboardType, String)>>>,
+        loads: Arc<std::sync::Mutex<Vec<ClipboardType>>>,
+        writes: Arc<std::sync::Mutex<Vec<String>>>,
+        color_requests: Arc<std::sync::Mutex<Vec<(usize, String)>>>,
+    }
+
+    impl EventListener for RecordingListener {
+        fn send_event(&self, event: RioEvent, _id: WindowId) {
+            match event {
+                RioEvent::ClipboardStore(ty, text) => {
+                    self.stores.lock().unwrap().push((ty, text))
+                }
+                RioEvent::ClipboardLoad(ty, _) => self.loads.lock().unwrap().push(ty),
+                RioEvent::PtyWrite(text) => self.writes.lock().unwrap().push(text),
+                // Answer configured colors with pure red, as the screen would.
+                RioEvent::ColorRequest(index, format) => {
+                    let red = ColorRgb { r: 255, g: 0, b: 0 };
+                    self.color_requests
+                        .lock()
+                        .unwrap()
+                        .push((index, format(red)))
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn recording_term() -> (Crosswords<RecordingListener>, RecordingListener) {
+        let listener = RecordingListener::default();
+        let term = Crosswords::new(10, 3, listener.clone(), WindowId::from(0));
+        (term, listener)
+    }
+
+    fn feed<U: EventListener>(term: &mut Crosswords<U>, bytes: &[u8]) {
+        let mut parser = ParserProcessor::new();
+        for byte in bytes {
+            parser.advance(term, *byte);
+        }
+    }
+
+    #[test]
+    fn osc52_stores_clipboard_and_selection() {
+        let (mut term, listener) = recording_term();
+
+        // "hello" and "world" in base64, terminated by ST and BEL.
+        feed(&mut term, b"\x1b]52;c;aGVsbG8=\x1b\\");
+        feed(&mut term, b"\x1b]52;p;d29ybGQ=\x07");
+
+        assert_eq!(
+            *listener.stores.lock().unwrap(),
+            vec![
+                (ClipboardType::Clipboard, String::from("hello")),
+                (ClipboardType::Selection, String::from("world")),
+            ]
+        );
+    }
+
+    #[test]
+    fn osc52_accepts_payload_split_across_chunks() {
+        let (mut term, listener) = recording_term();
+
+        let text = "rio ".repeat(1024);
+        let base64 = general_purpose::STANDARD.encode(&text);
+        let sequence = format!("\x1b]52;c;{base64}\x07");
+
+        // Bytes arrive in several reads from the pty, sharing one parser.
+        let mut parser = ParserProcessor::new();
+        for chunk in sequence.as_bytes().chunks(100) {
+            for byte in chunk {
+                parser.advance(&mut term, *byte);
+            }
+        }
+
+        assert_eq!(
+            *listener.stores.lock().unwrap(),
+            vec![(ClipboardType::Clipboard, text)]
+        );
+    }
+
+    #[test]
+    fn osc52_empty_payload_clears_clipboard() {
+        let (mut term, listener) = recording_term();
+
+        feed(&mut term, b"\x1b]52;c;\x07");
+
+        assert_eq!(
+            *listener.stores.lock().unwrap(),
+            vec![(ClipboardType::Clipboard, String::new())]
+        );
+    }
+
+    #[test]
+    fn osc52_ignores_invalid_and_oversized_payloads() {
+        let (mut term, listener) = recording_term();
+        term.osc52_max_size = 8;
+
+        // Not base64, not UTF-8 once decoded, and larger than the cap.
+        feed(&mut term, b"\x1b]52;c;!!!!\x07");
+        feed(&mut term, b"\x1b]52;c;/w==\x07");
+        feed(&mut term, b"\x1b]52;c;aGVsbG8gd29ybGQ=\x07");
+        // Unknown clipboard.
+        feed(&mut term, b"\x1b]52;x;aGk=\x07");
+        assert!(listener.stores.lock().unwrap().is_empty());
+
+        feed(&mut term, b"\x1b]52;c;aGk=\x07");
+        assert_eq!(
+            *listener.stores.lock().unwrap(),
+            vec![(ClipboardType::Clipboard, String::from("hi"))]
+        );
+    }
+
+    #[test]
+    fn osc52_read_is_gated_by_config() {
+        let (mut term, listener) = recording_term();
+
+        feed(&mut term, b"\x1b]52;c;?\x07");
+        assert!(listener.loads.lock().unwrap().is_empty());
+
+        term.osc52_read = true;
+        feed(&mut term, b"\x1b]52;c;?\x07");
+        assert_eq!(
+            *listener.loads.lock().unwrap(),
+            vec![ClipboardType::Clipboard]
+        );
+    }
+
+    fn rgb(r: u8, g: u8, b: u8) -> Option<ColorRgb> {
+        Some(ColorRgb { r, g, b })
+    }
+
+    #[test]
+    fn osc4_sets_palette_colors() {
+        let (mut term, _) = recording_term();
+        term.reset_damage();
+
+        feed(&mut term, b"\x1b]4;1;rgb:ff/80/00\x1b\\");
+        assert!(term.damage.is_fully_damaged);
+        feed(&mut term, b"\x1b]4;2;#00ff00;3;Slate Blue\x07");
+        feed(&mut term, b"\x1b]4;200;rgb:ffff/0/8080\x07");
+
+        assert_eq!(term.colors()[1], rgb(255, 128, 0));
+        assert_eq!(term.colors()[2], rgb(0, 255, 0));
+        assert_eq!(term.colors()[3], rgb(106, 90, 205));
+        assert_eq!(term.colors()[200], rgb(255, 0, 128));
+
+        // Invalid specs leave the color alone.
+        feed(&mut term, b"\x1b]4;1;rgb:ff/80\x07");
+        feed(&mut term, b"\x1b]4;1;#ff80\x07");
+        feed(&mut term, b"\x1b]4;1;notacolor\x07");
+        assert_eq!(term.colors()[1], rgb(255, 128, 0));
+    }
+
+    #[test]
+    fn osc104_resets_palette_colors() {
+        let (mut term, _) = recording_term();
+
+        feed(&mut term, b"\x1b]4;1;red;2;green;3;blue\x07");
+        feed(&mut term, b"\x1b]104;1;3\x07");
+        assert_eq!(term.colors()[1], None);
+        assert_eq!(term.colors()[2], rgb(0, 255, 0));
+        assert_eq!(term.colors()[3], None);
+
+        term.reset_damage();
+        feed(&mut term, b"\x1b]104\x07");
+        assert!(term.damage.is_fully_damaged);
+        assert_eq!(term.colors()[2], None);
+    }
+
+    #[test]
+    fn osc4_query_replies_with_16_bit_channels() {
+        let (mut term, listener) = recording_term();
+
+        // Changed colors are answered by the terminal itself.
+        feed(&mut term, b"\x1b]4;1;rgb:12/34/56\x07");
+        feed(&mut term, b"\x1b]4;1;?\x1b\\");
+        feed(&mut term, b"\x1b]4;1;?\x07");
+        assert_eq!(
+            *listener.writes.lock().unwrap(),
+            vec![
+                String::from("\x1b]4;1;rgb:1212/3434/5656\x1b\\"),
+                String::from("\x1b]4;1;rgb:1212/3434/5656\x07"),
+            ]
+        );
+
+        // Configured colors are asked to the screen.
+        feed(&mut term, b"\x1b]4;9;?\x07");
+        assert_eq!(
+            *listener.color_requests.lock().unwrap(),
+            vec![(9, String::from("\x1b]4;9;rgb:ffff/0000/0000\x07"))]
+        );
+    }
+
+    #[test]
+    fn osc_dynamic_colors_set_query_and_reset() {
+        let (mut term, listener) = recording_term();
+        let background = NamedColor::Background as usize;
+
+        term.reset_damage();
+        feed(&mut term, b"\x1b]11;rgb:10/20/30\x1b\\");
+        assert!(term.damage.is_fully_damaged);
+        assert_eq!(term.colors()[background], rgb(0x10, 0x20, 0x30));
+
+        feed(&mut term, b"\x1b]11;?\x1b\\");
+        assert_eq!(
+            *listener.writes.lock().unwrap(),
+            vec![String::from("\x1b]11;rgb:1010/2020/3030\x1b\\")]
+        );
+
+        // Resetting goes back to the theme, which the screen answers for.
+        feed(&mut term, b"\x1b]111\x07");
+        assert_eq!(term.colors()[background], None);
+
+        // Several queries in a row ask for the following dynamic colors.
+        feed(&mut term, b"\x1b]10;?;?;?\x07");
+        assert_eq!(
+            *listener.color_requests.lock().unwrap(),
+            vec![
+                (256, String::from("\x1b]10;rgb:ffff/0000/0000\x07")),
+                (257, String::from("\x1b]11;rgb:ffff/0000/0000\x07")),
+                (258, String::from("\x1b]12;rgb:ffff/0000/0000\x07")),
+            ]
+        );
+
+        feed(&mut term, b"\x1b]12;#00ff00\x07");
+        assert_eq!(term.colors()[NamedColor::Cursor], rgb(0, 255, 0));
+        feed(&mut term, b"\x1b]112\x07");
+        assert_eq!(term.colors()[NamedColor::Cursor], None);
+    }
+
+    #[test]
+    fn decscusr_walks_through_cursor_styles() {
+        let (mut term, listener) = recording_term();
+        term.cursor_shape = CursorShape::Underline;
+        term.blinking_cursor = false;
+        let configured = CursorStyle {
+            shape: CursorShape::Underline,
+            blinking: false,
+        };
+
+        let styles = [
+            (1, CursorShape::Block, true),
+            (2, CursorShape::Block, false),
+            (3, CursorShape::Underline, true),
+            (4, CursorShape::Underline, false),
+            (5, CursorShape::Beam, true),
+            (6, CursorShape::Beam, false),
+        ];
+        for (ps, shape, blinking) in styles {
+            feed(&mut term, format!("\x1b[{ps} q").as_bytes());
+            assert_eq!(term.cursor_style(), CursorStyle { shape, blinking });
+            assert_eq!(term.cursor().content, shape);
+        }
+
+        // Zero goes back to the configured style.
+        feed(&mut term, b"\x1b[0 q");
+        assert_eq!(term.cursor_style(), configured);
+
+        // Blinking from the escape wins over the configuration, also for DECRQM.
+        feed(&mut term, b"\x1b[5 q\x1b[?12$p");
+        assert_eq!(
+            listener.writes.lock().unwrap().last(),
+            Some(&String::from("\x1b[?12;1$y"))
+        );
+
+        // Hiding the cursor doesn't change the requested style.
+        feed(&mut term, b"\x1b[?25l");
+        assert_eq!(term.cursor().content, CursorShape::Hidden);
+        assert_eq!(term.cursor_style().shape, CursorShape::Beam);
+
+        // A full reset also restores the configured style.
+        feed(&mut term, b"\x1bc");
+        assert_eq!(term.cursor_style(), configured);
+    }
+
+    #[test]
+    fn blinking_cursor_mode_keeps_the_cursor_shape() {
+        let (mut term, _) = recording_term();
+
+        feed(&mut term, b"\x1b[4 q\x1b[?12h");
+        assert_eq!(
+            term.cursor_style(),
+            CursorStyle {
+                shape: CursorShape::Underline,
+                blinking: true,
+            }
+        );
+
+        feed(&mut term, b"\x1b[?12l");
+        assert!(!term.cursor_style().blinking);
+        assert_eq!(term.cursor_style().shape, CursorShape::Underline);
+    }
+
+    #[test]
+    fn bracketed_paste_mode_is_tracked() {
+        let (mut term, listener) = recording_term();
+        assert!(!term.mode().contains(Mode::BRACKETED_PASTE));
+
+        feed(&mut term, b"\x1b[?2004h");
+        assert!(term.mode().contains(Mode::BRACKETED_PASTE));
+
+        feed(&mut term, b"\x1b[?2004$p");
+        assert_eq!(
+            listener.writes.lock().unwrap().last(),
+            Some(&String::from("\x1b[?2004;1$y"))
+        );
+
+        feed(&mut term, b"\x1b[?2004l");
+        assert!(!term.mode().contains(Mode::BRACKETED_PASTE));
+    }
+
+    #[test]
+    fn mouse_modes_are_mutually_exclusive() {
+        let (mut term, _) = recording_term();
+
+        feed(&mut term, b"\x1b[?1000h");
+        assert_eq!(term.mode() & Mode::MOUSE_MODE, Mode::MOUSE_REPORT_CLICK);
+        feed(&mut term, b"\x1b[?1002h");
+        assert_eq!(term.mode() & Mode::MOUSE_MODE, Mode::MOUSE_DRAG);
+        feed(&mut term, b"\x1b[?1003h");
+        assert_eq!(term.mode() & Mode::MOUSE_MODE, Mode::MOUSE_MOTION);
+        feed(&mut term, b"\x1b[?1003l");
+        assert!(!term.mode().intersects(Mode::MOUSE_MODE));
+
+        feed(&mut term, b"\x1b[?1005h");
+        assert!(term.mode().contains(Mode::UTF8_MOUSE));
+        feed(&mut term, b"\x1b[?1006h");
+        assert!(term.mode().contains(Mode::SGR_MOUSE));
+        assert!(!term.mode().contains(Mode::UTF8_MOUSE));
+        feed(&mut term, b"\x1b[?1006l");
+        assert!(!term.mode().intersects(Mode::SGR_MOUSE | Mode::UTF8_MOUSE));
+    }
+
+    #[test]
+    fn variation_selectors_set_the_cluster_width() {
+        let (mut term, _) = recording_term();
+
+        feed(&mut term, "\x1b[?2027h\u{2600}\u{FE0F}x\r\n".as_bytes());
+        let row = &term.grid[Line(0)];
+        assert_eq!(row[Column(0)].c, '\u{2600}');
+        assert_eq!(row[Column(0)].zerowidth(), Some(&['\u{FE0F}'][..]));
+        assert!(row[Column(0)].flags.contains(square::Flags::WIDE_CHAR));
+        assert!(row[Column(1)]
+            .flags
+            .contains(square::Flags::WIDE_CHAR_SPACER));
+        assert_eq!(row[Column(2)].c, 'x');
+
+        feed(&mut term, "\u{2600}\u{FE0E}x".as_bytes());
+        let row = &term.grid[Line(1)];
+        assert_eq!(row[Column(0)].zerowidth(), Some(&['\u{FE0E}'][..]));
+        assert!(!row[Column(0)].flags.contains(square::Flags::WIDE_CHAR));
+        assert_eq!(row[Column(1)].c, 'x');
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench_combining_marks`.
+    #[test]
+    #[ignore]
+    fn bench_combining_marks() {
+        const CELLS: usize = 100_000;
+        let (columns, lines) = (80, 25);
+        let mut term =
+            Crosswords::new(columns, lines, VoidListener {}, WindowId::from(0));
+
+        // A full screen application redrawing over and over, every overwritten cell hands
+        // its extra back to the pool for the next one.
+        let frame = format!("\x1b[H{}", "\u{1EBD}\u{302}\u{303}".repeat(columns * lines));
+        let frames = CELLS / (columns * lines);
+
+        let start = std::time::Instant::now();
+        for _ in 0..frames {
+            feed(&mut term, frame.as_bytes());
+        }
+        let elapsed = start.elapsed();
+
+        let square = &term.grid[Line(0)][Column(0)];
+        assert_eq!(square.zerowidth(), Some(&['\u{302}', '\u{303}'][..]));
+        println!("{CELLS} cells with combining marks: {elapsed:?}");
     }
 }
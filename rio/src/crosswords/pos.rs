@@ -4,6 +4,8 @@
 
 use crate::ansi::CursorShape;
 use crate::crosswords::grid::Dimensions;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::cmp::{max, min, Ord, Ordering};
 use std::fmt;
 use std::ops::{Add, AddAssign, Deref, Index, IndexMut, Sub, SubAssign};
@@ -17,7 +19,6 @@ pub enum Direction {
 }
 
 impl Direction {
-    #[allow(unused)]
     pub fn opposite(self) -> Self {
         match self {
             Side::Right => Side::Left,
@@ -40,6 +41,19 @@ pub struct Cursor<T> {
     pub should_wrap: bool,
 }
 
+/// Cursor state saved by DECSC and restored by DECRC, each screen keeps its own.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SavedCursor<T> {
+    /// Position, template, charsets and pending wrap.
+    pub cursor: Cursor<T>,
+
+    /// Whether origin mode was set.
+    pub origin: bool,
+
+    /// Character set invoked into GL.
+    pub active_charset: CharsetIndex,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct CursorState {
     pub pos: Pos,
@@ -153,6 +167,7 @@ pub enum Boundary {
 }
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialOrd, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Pos<L = Line, C = Column> {
     pub row: L,
     pub col: C,
@@ -219,6 +234,7 @@ impl Pos {
 ///
 /// Newtype to avoid passing values incorrectly.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Line(pub i32);
 
 impl Line {
@@ -312,6 +328,7 @@ impl PartialEq<usize> for Line {
 ///
 /// Newtype to avoid passing values incorrectly.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Default, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Column(pub usize);
 
 impl fmt::Display for Column {
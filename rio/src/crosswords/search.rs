@@ -0,0 +1,671 @@
+// Regex search over the grid, from the screen up through the scrollback history.
+
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::grid::{BidirectionalIterator, Dimensions, Grid};
+use crate::crosswords::pos::{Boundary, Column, Direction, Line, Pos};
+use crate::crosswords::square::{Flags, Square};
+use crate::crosswords::text;
+use crate::crosswords::Crosswords;
+use crate::event::EventListener;
+use regex::{Regex, RegexBuilder};
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+
+/// Maximum number of rows joined into a single searched line.
+///
+/// Matches never span more rows than this, which keeps pathological patterns from scanning
+/// the whole scrollback as one huge wrapped line.
+pub const MAX_SEARCH_LINES: usize = 100;
+
+/// Upper bound for the size of a compiled search pattern.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Squares standing in for part of a wide char, which hold no text of their own.
+const SPACER_FLAGS: Flags =
+    Flags::WIDE_CHAR_SPACER.union(Flags::LEADING_WIDE_CHAR_SPACER);
+
+/// Plain text URLs recognized by [`Crosswords::url_at`].
+const URL_PATTERN: &str =
+    r#"(?:https?://|file://|ftp://|mailto:)[^\s<>"'`{}|\\^\x00-\x1f\x7f]+"#;
+
+/// First and last square of a match.
+pub type Match = RangeInclusive<Pos>;
+
+/// Compiled search pattern.
+#[derive(Debug, Clone)]
+pub struct RegexSearch {
+    regex: Regex,
+}
+
+impl RegexSearch {
+    /// Compile `pattern`, ignoring letter case when `case_insensitive` is set.
+    pub fn new(
+        pattern: &str,
+        case_insensitive: bool,
+    ) -> Result<RegexSearch, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .build()?;
+
+        Ok(RegexSearch { regex })
+    }
+}
+
+/// Text of consecutive wrapped rows along with the position of every square in it.
+struct SearchLine {
+    rows: RangeInclusive<Line>,
+    text: String,
+    /// Byte offset in `text` where each square starts, spacers are left out.
+    offsets: Vec<(usize, Pos)>,
+}
+
+impl SearchLine {
+    fn new(grid: &Grid<Square>, rows: RangeInclusive<Line>) -> SearchLine {
+        let lines = rows.start().0..=rows.end().0;
+        SearchLine::from_rows(*rows.start(), lines.map(|line| &grid[Line(line)]))
+    }
+
+    /// Join `rows`, the first one being at `first`.
+    fn from_rows<'a>(
+        first: Line,
+        rows: impl IntoIterator<Item = &'a Row<Square>>,
+    ) -> SearchLine {
+        let mut text = String::new();
+        let mut offsets = Vec::new();
+        let mut last = first;
+
+        for (line, row) in rows.into_iter().enumerate() {
+            last = first + line;
+            for (column, square) in row[..].iter().enumerate() {
+                if square.flags.intersects(SPACER_FLAGS) {
+                    continue;
+                }
+
+                offsets.push((text.len(), Pos::new(last, Column(column))));
+                square.write_text_to(&mut text);
+            }
+        }
+
+        SearchLine {
+            rows: first..=last,
+            text,
+            offsets,
+        }
+    }
+
+    /// Byte offset of the square at `pos`, the spacers of a wide char resolving to it.
+    fn offset(&self, pos: Pos) -> Option<usize> {
+        let index = self.offsets.partition_point(|(_, square)| *square <= pos);
+        let (offset, _) = self.offsets.get(index.checked_sub(1)?)?;
+        Some(*offset)
+    }
+
+    /// Square holding the byte at `offset`.
+    fn pos(&self, offset: usize) -> Pos {
+        let index = self.offsets.partition_point(|(start, _)| *start <= offset);
+        self.offsets[index - 1].1
+    }
+
+    /// All non-empty matches in the line, in order.
+    fn matches(&self, regex: &RegexSearch) -> Vec<Match> {
+        regex
+            .regex
+            .find_iter(&self.text)
+            .filter(|found| !found.as_str().is_empty())
+            .map(|found| self.pos(found.start())..=self.pos(found.end() - 1))
+            .collect()
+    }
+
+    fn contains(&self, line: Line) -> bool {
+        self.rows.contains(&line)
+    }
+}
+
+/// All matches of `regex` in `rows`, in order, the first row being at `Line(0)`.
+///
+/// Rows chained with `WRAPLINE` are searched as one line, so matches can span the wrap.
+#[allow(dead_code)]
+pub fn search(rows: &[Row<Square>], regex: &RegexSearch) -> Vec<Match> {
+    let mut matches = Vec::new();
+
+    let mut start = 0;
+    while start < rows.len() {
+        let mut end = start;
+        while end + 1 < rows.len()
+            && text::is_wrapped(&rows[end])
+            && end - start + 1 < MAX_SEARCH_LINES
+        {
+            end += 1;
+        }
+
+        let line = SearchLine::from_rows(Line(start as i32), &rows[start..=end]);
+        matches.extend(line.matches(regex));
+        start = end + 1;
+    }
+
+    matches
+}
+
+fn url_regex() -> &'static Regex {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    URL.get_or_init(|| Regex::new(URL_PATTERN).unwrap())
+}
+
+/// Drop the punctuation following a URL in prose.
+///
+/// Closing parentheses and brackets are only dropped when unbalanced, so links such as
+/// `https://en.wikipedia.org/wiki/Rust_(programming_language)` stay intact.
+fn trim_url(mut url: &str) -> &str {
+    while let Some(last) = url.chars().next_back() {
+        let trim = match last {
+            '.' | ',' | ':' | ';' | '!' | '?' | '\'' | '"' => true,
+            ')' => url.matches('(').count() < url.matches(')').count(),
+            ']' => url.matches('[').count() < url.matches(']').count(),
+            _ => false,
+        };
+        if !trim {
+            break;
+        }
+
+        url = &url[..url.len() - last.len_utf8()];
+    }
+
+    url
+}
+
+impl<T: EventListener> Crosswords<T> {
+    /// Plain text URL covering the square at `pos`, with the text of its squares.
+    ///
+    /// Wrapped rows are scanned as one line, so URLs continued on the next row are found
+    /// whole. Trailing punctuation is left out of the URL.
+    #[allow(dead_code)]
+    pub fn url_at(&self, pos: Pos) -> Option<(Match, String)> {
+        let pos = self.clamp_to_grid(pos);
+        let line = self.search_line_at(pos.row);
+        let offset = line.offset(pos)?;
+
+        url_regex().find_iter(&line.text).find_map(|found| {
+            let url = trim_url(found.as_str());
+            let end = found.start() + url.len();
+            if !url_regex().is_match(url) || !(found.start()..end).contains(&offset) {
+                return None;
+            }
+
+            let span = line.pos(found.start())..=line.pos(end - 1);
+            Some((span, url.to_owned()))
+        })
+    }
+
+    /// Find the next match of `regex` from `origin` in `direction`.
+    ///
+    /// Searching right returns the first match starting at or after `origin`, searching
+    /// left the last match ending at or before it. Wrapped rows are searched as one line
+    /// and the search wraps around the history and the screen.
+    pub fn search(
+        &self,
+        regex: &RegexSearch,
+        origin: Pos,
+        direction: Direction,
+    ) -> Option<Match> {
+        let origin = self.clamp_to_grid(origin);
+        let first = self.search_line_at(origin.row);
+
+        let candidate = match direction {
+            Direction::Right => first
+                .matches(regex)
+                .into_iter()
+                .find(|found| *found.start() >= origin),
+            Direction::Left => first
+                .matches(regex)
+                .into_iter()
+                .rev()
+                .find(|found| *found.end() <= origin),
+        };
+        if candidate.is_some() {
+            return candidate;
+        }
+
+        // Walk the other lines until coming back to the one holding the origin, which is
+        // then searched on its other side.
+        let mut line = first;
+        loop {
+            line = self.adjacent_search_line(&line, direction);
+
+            let mut matches = line.matches(regex).into_iter();
+            let found = match direction {
+                Direction::Right => matches.next(),
+                Direction::Left => matches.next_back(),
+            };
+
+            if found.is_some() || line.contains(origin.row) {
+                return found;
+            }
+        }
+    }
+
+    /// Find the match after `current` in `direction`, wrapping around.
+    #[allow(dead_code)]
+    pub fn search_next(
+        &self,
+        regex: &RegexSearch,
+        current: &Match,
+        direction: Direction,
+    ) -> Option<Match> {
+        let origin = match direction {
+            Direction::Right => current.start().add(&self.grid, Boundary::None, 1),
+            Direction::Left => current.start().sub(&self.grid, Boundary::None, 1),
+        };
+
+        self.search(regex, origin, direction)
+    }
+
+    /// Move the vi mode cursor to the first match of `regex` after `origin` in
+    /// `direction`, highlighting it instead of the previous match.
+    ///
+    /// A match starting at `origin` is skipped, so searching from the current match goes
+    /// to the next one like vi's `n`.
+    pub fn vi_search(
+        &mut self,
+        regex: &RegexSearch,
+        origin: Pos,
+        direction: Direction,
+    ) -> Option<Match> {
+        let origin = match direction {
+            Direction::Right => origin.add(&self.grid, Boundary::None, 1),
+            Direction::Left => origin.sub(&self.grid, Boundary::None, 1),
+        };
+
+        self.clear_search_matches();
+        let found = self.search(regex, origin, direction)?;
+        self.set_search_match(&found, true);
+        self.vi_goto_pos(*found.start());
+
+        Some(found)
+    }
+
+    /// Highlight the squares of `found` as a search match, or remove the highlight.
+    pub fn set_search_match(&mut self, found: &Match, highlight: bool) {
+        let (start, end) = (*found.start(), *found.end());
+        let last_column = self.grid.last_column();
+
+        for line in start.row.0..=end.row.0 {
+            let line = Line(line);
+            let first = if line == start.row {
+                start.col
+            } else {
+                Column(0)
+            };
+            let last = if line == end.row {
+                end.col
+            } else {
+                last_column
+            };
+
+            for square in &mut self.grid[line][first..last + 1] {
+                square.flags.set(Flags::SEARCH_MATCH, highlight);
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Remove the highlight of every search match, once the search is dismissed.
+    pub fn clear_search_matches(&mut self) {
+        let lines = self.grid.topmost_line().0..=self.grid.bottommost_line().0;
+        for line in lines {
+            for square in &mut self.grid[Line(line)][..] {
+                square.flags.remove(Flags::SEARCH_MATCH);
+            }
+        }
+
+        self.mark_fully_damaged();
+    }
+
+    /// Find the next square right of `pos` holding one of `needles`, within its line.
+    ///
+    /// Wrapped rows count as one line. When nothing is found the last searched position is
+    /// returned as the error.
+    pub fn inline_search_right(&self, mut pos: Pos, needles: &str) -> Result<Pos, Pos> {
+        // Limit the starting point to the last line in the history.
+        pos.row = std::cmp::max(pos.row, self.grid.topmost_line());
+
+        let last_column = self.grid.last_column();
+        if pos.col == last_column && !self.grid[pos].flags.contains(Flags::WRAPLINE) {
+            return Err(pos);
+        }
+
+        for square in self.grid.iter_from(pos) {
+            pos = square.pos;
+
+            if !square.flags.intersects(SPACER_FLAGS) && needles.contains(square.c) {
+                return Ok(pos);
+            }
+
+            if pos.col == last_column && !square.flags.contains(Flags::WRAPLINE) {
+                break;
+            }
+        }
+
+        Err(pos)
+    }
+
+    /// Find the next square left of `pos` holding one of `needles`, within its line.
+    pub fn inline_search_left(&self, mut pos: Pos, needles: &str) -> Result<Pos, Pos> {
+        // Limit the starting point to the last line in the history.
+        pos.row = std::cmp::max(pos.row, self.grid.topmost_line());
+
+        let last_column = self.grid.last_column();
+        let mut iter = self.grid.iter_from(pos);
+        while let Some(square) = iter.prev() {
+            if square.pos.col == last_column && !square.flags.contains(Flags::WRAPLINE) {
+                break;
+            }
+
+            pos = square.pos;
+
+            if !square.flags.intersects(SPACER_FLAGS) && needles.contains(square.c) {
+                return Ok(pos);
+            }
+        }
+
+        Err(pos)
+    }
+
+    /// Move a position past either end of the grid to the other end.
+    fn clamp_to_grid(&self, pos: Pos) -> Pos {
+        let topmost = self.grid.topmost_line();
+        let bottommost = self.grid.bottommost_line();
+
+        if pos.row > bottommost {
+            Pos::new(topmost, Column(0))
+        } else if pos.row < topmost {
+            Pos::new(bottommost, self.grid.last_column())
+        } else {
+            Pos::new(pos.row, std::cmp::min(pos.col, self.grid.last_column()))
+        }
+    }
+
+    fn is_wrapped(&self, line: Line) -> bool {
+        self.grid[line][self.grid.last_column()]
+            .flags
+            .contains(Flags::WRAPLINE)
+    }
+
+    /// Searched line containing `line`, at most [`MAX_SEARCH_LINES`] rows long.
+    fn search_line_at(&self, line: Line) -> SearchLine {
+        let topmost = self.grid.topmost_line();
+        let bottommost = self.grid.bottommost_line();
+        let max_rows = MAX_SEARCH_LINES as i32 - 1;
+
+        let mut start = line;
+        while start > topmost && self.is_wrapped(start - 1) && (line - start).0 < max_rows
+        {
+            start -= 1;
+        }
+
+        let mut end = line;
+        while end < bottommost && self.is_wrapped(end) && (end - start).0 < max_rows {
+            end += 1;
+        }
+
+        SearchLine::new(&self.grid, start..=end)
+    }
+
+    /// Searched line following `line` in `direction`, wrapping around the grid.
+    fn adjacent_search_line(
+        &self,
+        line: &SearchLine,
+        direction: Direction,
+    ) -> SearchLine {
+        let topmost = self.grid.topmost_line();
+        let bottommost = self.grid.bottommost_line();
+
+        match direction {
+            Direction::Right => {
+                let next = *line.rows.end() + 1;
+                let next = if next > bottommost { topmost } else { next };
+
+                // Rows continuing a cut line start a new one.
+                let mut end = next;
+                while end < bottommost
+                    && self.is_wrapped(end)
+                    && (end - next).0 < MAX_SEARCH_LINES as i32 - 1
+                {
+                    end += 1;
+                }
+                SearchLine::new(&self.grid, next..=end)
+            }
+            Direction::Left => {
+                let previous = *line.rows.start() - 1;
+                let previous = if previous < topmost {
+                    bottommost
+                } else {
+                    previous
+                };
+
+                let mut start = previous;
+                while start > topmost
+                    && self.is_wrapped(start - 1)
+                    && (previous - start).0 < MAX_SEARCH_LINES as i32 - 1
+                {
+                    start -= 1;
+                }
+                SearchLine::new(&self.grid, start..=previous)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::VoidListener;
+    use crate::performer::handler::ParserProcessor;
+    use winit::window::WindowId;
+
+    fn terminal(columns: usize, lines: usize, content: &str) -> Crosswords<VoidListener> {
+        let mut term =
+            Crosswords::new(columns, lines, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in content.bytes() {
+            parser.advance(&mut term, byte);
+        }
+        term
+    }
+
+    fn pos(line: i32, column: usize) -> Pos {
+        Pos::new(Line(line), Column(column))
+    }
+
+    #[test]
+    fn match_across_wrapped_rows() {
+        let term = terminal(5, 3, "xxxhello\r\nworld");
+        let regex = RegexSearch::new("hello", false).unwrap();
+
+        assert_eq!(
+            term.search(&regex, pos(0, 0), Direction::Right),
+            Some(pos(0, 3)..=pos(1, 2))
+        );
+        // A hard line break is not joined.
+        let regex = RegexSearch::new("helloworld", false).unwrap();
+        assert_eq!(term.search(&regex, pos(0, 0), Direction::Right), None);
+    }
+
+    #[test]
+    fn match_skips_wide_char_spacers() {
+        let term = terminal(6, 2, "a\u{65e5}\u{672c}b");
+        let regex = RegexSearch::new("\u{65e5}\u{672c}", false).unwrap();
+
+        assert_eq!(
+            term.search(&regex, pos(0, 0), Direction::Right),
+            Some(pos(0, 1)..=pos(0, 3))
+        );
+    }
+
+    #[test]
+    fn case_insensitive_search() {
+        let term = terminal(10, 2, "Rio rio");
+        let regex = RegexSearch::new("RIO", true).unwrap();
+        assert_eq!(
+            term.search(&regex, pos(0, 1), Direction::Right),
+            Some(pos(0, 4)..=pos(0, 6))
+        );
+
+        let regex = RegexSearch::new("RIO", false).unwrap();
+        assert_eq!(term.search(&regex, pos(0, 0), Direction::Right), None);
+    }
+
+    #[test]
+    fn next_and_previous_wrap_around_history() {
+        // Two lines end up in the history.
+        let term = terminal(5, 2, "ab\r\nx\r\nab\r\ny");
+        assert_eq!(term.grid.history_size(), 2);
+        let regex = RegexSearch::new("ab", false).unwrap();
+
+        let first = term.search(&regex, pos(-2, 0), Direction::Right).unwrap();
+        assert_eq!(first, pos(-2, 0)..=pos(-2, 1));
+
+        let second = term.search_next(&regex, &first, Direction::Right).unwrap();
+        assert_eq!(second, pos(0, 0)..=pos(0, 1));
+
+        // Moving past the last match wraps back to the first one, in both directions.
+        assert_eq!(
+            term.search_next(&regex, &second, Direction::Right),
+            Some(first.clone())
+        );
+        assert_eq!(
+            term.search_next(&regex, &first, Direction::Left),
+            Some(second)
+        );
+    }
+
+    #[test]
+    fn search_rows_across_wrap() {
+        let term = terminal(5, 3, "xxxhello\r\nworld");
+        let rows: Vec<Row<Square>> =
+            (0..3).map(|line| term.grid[Line(line)].clone()).collect();
+        let regex = RegexSearch::new("hello|world", false).unwrap();
+
+        assert_eq!(
+            search(&rows, &regex),
+            vec![pos(0, 3)..=pos(1, 2), pos(2, 0)..=pos(2, 4)]
+        );
+    }
+
+    #[test]
+    fn url_at_position() {
+        let term = terminal(40, 2, "see https://example.com/a?b=1 now");
+        let url = Some((
+            pos(0, 4)..=pos(0, 28),
+            "https://example.com/a?b=1".to_owned(),
+        ));
+
+        assert_eq!(term.url_at(pos(0, 4)), url);
+        assert_eq!(term.url_at(pos(0, 28)), url);
+        assert_eq!(term.url_at(pos(0, 3)), None);
+        assert_eq!(term.url_at(pos(0, 29)), None);
+        assert_eq!(term.url_at(pos(1, 0)), None);
+    }
+
+    #[test]
+    fn url_at_across_wrapped_rows() {
+        let term = terminal(10, 4, "> ftp://host/path/file\r\nmore");
+
+        let url = Some((pos(0, 2)..=pos(2, 1), "ftp://host/path/file".to_owned()));
+        assert_eq!(term.url_at(pos(1, 5)), url);
+        assert_eq!(term.url_at(pos(2, 1)), url);
+        assert_eq!(term.url_at(pos(2, 2)), None);
+    }
+
+    #[test]
+    fn url_at_trims_trailing_punctuation() {
+        let url_text = |content: &str| {
+            let term = terminal(80, 1, content);
+            term.url_at(pos(0, 10)).map(|(_, url)| url)
+        };
+
+        let docs = Some("https://rio.dev/docs".to_owned());
+        assert_eq!(url_text("(https://rio.dev/docs)."), docs);
+        assert_eq!(url_text("\"https://rio.dev/docs\"."), docs);
+        assert_eq!(url_text("<https://rio.dev/docs>"), docs);
+        assert_eq!(url_text("https://rio.dev/docs?, see"), docs);
+        assert_eq!(
+            url_text("https://en.wikipedia.org/wiki/Rust_(language))."),
+            Some("https://en.wikipedia.org/wiki/Rust_(language)".into())
+        );
+        assert_eq!(
+            url_text("mailto:someone@rio.dev;"),
+            Some("mailto:someone@rio.dev".into())
+        );
+        assert_eq!(url_text("see https://. ok"), None);
+    }
+
+    #[test]
+    fn url_at_keeps_zerowidth_and_wide_chars() {
+        let term = terminal(40, 1, "https://rio.dev/e\u{301}/字x");
+
+        let url = term.url_at(pos(0, 20));
+        assert_eq!(
+            url,
+            Some((
+                pos(0, 0)..=pos(0, 20),
+                "https://rio.dev/e\u{301}/字x".to_owned()
+            ))
+        );
+        // The spacer of the wide char belongs to the URL too.
+        assert_eq!(term.url_at(pos(0, 19)), url);
+    }
+
+    #[test]
+    fn search_match_highlight_spans_wrapped_rows() {
+        let mut term = terminal(5, 3, "xxxhello\r\nworld");
+        let regex = RegexSearch::new("hello", false).unwrap();
+        let found = term.search(&regex, pos(0, 0), Direction::Right).unwrap();
+
+        term.set_search_match(&found, true);
+
+        let highlighted = |term: &Crosswords<VoidListener>| {
+            (0..2)
+                .flat_map(|line| (0..5).map(move |column| pos(line, column)))
+                .filter(|pos| term.grid[*pos].flags.contains(Flags::SEARCH_MATCH))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            highlighted(&term),
+            vec![pos(0, 3), pos(0, 4), pos(1, 0), pos(1, 1), pos(1, 2)]
+        );
+
+        term.set_search_match(&found, false);
+        assert!(highlighted(&term).is_empty());
+
+        term.set_search_match(&found, true);
+        term.clear_search_matches();
+        assert!(highlighted(&term).is_empty());
+    }
+
+    #[test]
+    fn vi_search_moves_the_vi_cursor_to_the_next_match() {
+        let mut term = terminal(10, 3, "ab ab ab");
+        term.toggle_vi_mode();
+        let regex = RegexSearch::new("ab", false).unwrap();
+
+        // The match under the cursor is skipped.
+        let found = term.vi_search(&regex, pos(0, 0), Direction::Right);
+        assert_eq!(found, Some(pos(0, 3)..=pos(0, 4)));
+        assert_eq!(term.vi_mode_cursor.pos, pos(0, 3));
+        assert!(term.grid[pos(0, 3)].flags.contains(Flags::SEARCH_MATCH));
+
+        let found = term.vi_search(&regex, term.vi_mode_cursor.pos, Direction::Right);
+        assert_eq!(found, Some(pos(0, 6)..=pos(0, 7)));
+        // Only the current match stays highlighted.
+        assert!(!term.grid[pos(0, 3)].flags.contains(Flags::SEARCH_MATCH));
+
+        let found = term.vi_search(&regex, term.vi_mode_cursor.pos, Direction::Left);
+        assert_eq!(found, Some(pos(0, 3)..=pos(0, 4)));
+
+        let regex = RegexSearch::new("cd", false).unwrap();
+        assert_eq!(term.vi_search(&regex, pos(0, 0), Direction::Right), None);
+        assert_eq!(term.vi_mode_cursor.pos, pos(0, 3));
+    }
+}
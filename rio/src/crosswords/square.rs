@@ -1,44 +1,138 @@
 // square.rs was originally taken from Alacritty as cell.rs https://github.com/alacritty/alacritty/blob/e35e5ad14fce8456afdd89f2b392b9924bb27471/alacritty_terminal/src/term/cell.rs
 // which is licensed under Apache 2.0 license.
 
-use crate::crosswords::grid::GridSquare;
+use crate::crosswords::attr::Attr;
+use crate::crosswords::grid::{GridSquare, Rect};
 use crate::crosswords::Column;
 use crate::crosswords::Row;
 use bitflags::bitflags;
 use rio_config::colors::{AnsiColor, NamedColor};
+use rio_config::ResetMode;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use unicode_width::UnicodeWidthChar;
 
 bitflags! {
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
-    pub struct Flags: u16 {
-        const INVERSE                   = 0b0000_0000_0000_0001;
-        const BOLD                      = 0b0000_0000_0000_0010;
-        const ITALIC                    = 0b0000_0000_0000_0100;
-        const BOLD_ITALIC               = 0b0000_0000_0000_0110;
-        const UNDERLINE                 = 0b0000_0000_0000_1000;
-        const WRAPLINE                  = 0b0000_0000_0001_0000;
-        const WIDE_CHAR                 = 0b0000_0000_0010_0000;
-        const WIDE_CHAR_SPACER          = 0b0000_0000_0100_0000;
-        const DIM                       = 0b0000_0000_1000_0000;
-        const DIM_BOLD                  = 0b0000_0000_1000_0010;
-        const HIDDEN                    = 0b0000_0001_0000_0000;
-        const STRIKEOUT                 = 0b0000_0010_0000_0000;
-        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0100_0000_0000;
-        const DOUBLE_UNDERLINE          = 0b0000_1000_0000_0000;
-        const UNDERCURL                 = 0b0001_0000_0000_0000;
-        const DOTTED_UNDERLINE          = 0b0010_0000_0000_0000;
-        const DASHED_UNDERLINE          = 0b0100_0000_0000_0000;
+    pub struct Flags: u32 {
+        const INVERSE                   = 0b0000_0000_0000_0000_0000_0000_0000_0001;
+        const BOLD                      = 0b0000_0000_0000_0000_0000_0000_0000_0010;
+        const ITALIC                    = 0b0000_0000_0000_0000_0000_0000_0000_0100;
+        const BOLD_ITALIC               = 0b0000_0000_0000_0000_0000_0000_0000_0110;
+        const UNDERLINE                 = 0b0000_0000_0000_0000_0000_0000_0000_1000;
+        const WRAPLINE                  = 0b0000_0000_0000_0000_0000_0000_0001_0000;
+        const WIDE_CHAR                 = 0b0000_0000_0000_0000_0000_0000_0010_0000;
+        const WIDE_CHAR_SPACER          = 0b0000_0000_0000_0000_0000_0000_0100_0000;
+        const DIM                       = 0b0000_0000_0000_0000_0000_0000_1000_0000;
+        const DIM_BOLD                  = 0b0000_0000_0000_0000_0000_0000_1000_0010;
+        const HIDDEN                    = 0b0000_0000_0000_0000_0000_0001_0000_0000;
+        const STRIKEOUT                 = 0b0000_0000_0000_0000_0000_0010_0000_0000;
+        const LEADING_WIDE_CHAR_SPACER  = 0b0000_0000_0000_0000_0000_0100_0000_0000;
+        const DOUBLE_UNDERLINE          = 0b0000_0000_0000_0000_0000_1000_0000_0000;
+        const UNDERCURL                 = 0b0000_0000_0000_0000_0001_0000_0000_0000;
+        const DOTTED_UNDERLINE          = 0b0000_0000_0000_0000_0010_0000_0000_0000;
+        const DASHED_UNDERLINE          = 0b0000_0000_0000_0000_0100_0000_0000_0000;
+        const OVERLINE                  = 0b0000_0000_0000_0000_1000_0000_0000_0000;
+        const SLOW_BLINK                = 0b0000_0000_0000_0001_0000_0000_0000_0000;
+        const RAPID_BLINK               = 0b0000_0000_0000_0010_0000_0000_0000_0000;
+        const PROTECTED                 = 0b0000_0000_0000_0100_0000_0000_0000_0000;
+        /// Transient highlight of a search match, never part of the content.
+        const SEARCH_MATCH              = 0b0000_0000_0000_1000_0000_0000_0000_0000;
+        const ALL_BLINKS                = Self::SLOW_BLINK.bits() | Self::RAPID_BLINK.bits();
         const ALL_UNDERLINES            = Self::UNDERLINE.bits() | Self::DOUBLE_UNDERLINE.bits()
                                         | Self::UNDERCURL.bits() | Self::DOTTED_UNDERLINE.bits()
                                         | Self::DASHED_UNDERLINE.bits();
     }
 }
 
+#[cfg(feature = "serde")]
+impl Serialize for Flags {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.difference(Flags::SEARCH_MATCH).bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Flags {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Flags::from_bits_truncate(u32::deserialize(deserializer)?))
+    }
+}
+
+/// Flags which make a square non-empty regardless of its content.
+const EMPTY_CHECK_MASK: Flags = Flags::INVERSE
+    .union(Flags::ALL_UNDERLINES)
+    .union(Flags::STRIKEOUT)
+    .union(Flags::OVERLINE)
+    .union(Flags::WRAPLINE)
+    .union(Flags::WIDE_CHAR_SPACER)
+    .union(Flags::LEADING_WIDE_CHAR_SPACER);
+
+/// Flags which change how a square is drawn, structural ones are left out.
+const STYLE_MASK: Flags = Flags::all().difference(
+    Flags::WRAPLINE
+        .union(Flags::WIDE_CHAR_SPACER)
+        .union(Flags::LEADING_WIDE_CHAR_SPACER)
+        .union(Flags::PROTECTED),
+);
+
+/// Blink rate requested by SGR 5 (slow) or SGR 6 (rapid).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlinkRate {
+    Slow,
+    Rapid,
+}
+
+/// Underline style requested by SGR 4, SGR 4:x or SGR 21.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnderlineStyle {
+    Single,
+    Double,
+    Curly,
+    Dotted,
+    Dashed,
+}
+
+impl UnderlineStyle {
+    const ALL: [UnderlineStyle; 5] = [
+        UnderlineStyle::Single,
+        UnderlineStyle::Double,
+        UnderlineStyle::Curly,
+        UnderlineStyle::Dotted,
+        UnderlineStyle::Dashed,
+    ];
+
+    #[inline]
+    fn flag(self) -> Flags {
+        match self {
+            UnderlineStyle::Single => Flags::UNDERLINE,
+            UnderlineStyle::Double => Flags::DOUBLE_UNDERLINE,
+            UnderlineStyle::Curly => Flags::UNDERCURL,
+            UnderlineStyle::Dotted => Flags::DOTTED_UNDERLINE,
+            UnderlineStyle::Dashed => Flags::DASHED_UNDERLINE,
+        }
+    }
+}
+
+/// Glyph weight requested by SGR 1 (bold) or SGR 2 (dim).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontWeight {
+    Normal,
+    Bold,
+    Dim,
+}
+
 /// Counter for hyperlinks without explicit ID.
 static HYPERLINK_ID_SUFFIX: AtomicU32 = AtomicU32::new(0);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hyperlink {
     inner: Arc<HyperlinkInner>,
 }
@@ -56,9 +150,16 @@ impl Hyperlink {
     pub fn uri(&self) -> &str {
         &self.inner.uri
     }
+
+    /// Address of the shared link, the same for every square of an OSC 8 span.
+    #[inline]
+    fn addr(&self) -> usize {
+        Arc::as_ptr(&self.inner) as usize
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 struct HyperlinkInner {
     /// Identifier for the given hyperlink.
     id: String,
@@ -93,19 +194,74 @@ impl HyperlinkInner {
 /// allocation required ahead of time for every cell, with some additional overhead when the extra
 /// storage is actually required.
 #[derive(Default, Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CellExtra {
     zerowidth: Vec<char>,
     underline_color: Option<rio_config::colors::AnsiColor>,
+    strikeout_color: Option<rio_config::colors::AnsiColor>,
 
     hyperlink: Option<Hyperlink>,
 }
 
+/// Maximum number of recycled extras kept around for reuse.
+const EXTRA_POOL_CAPACITY: usize = 1024;
+
+thread_local! {
+    /// Free-list of uniquely owned extras released by cell resets.
+    ///
+    /// Output heavy on combining characters would otherwise allocate a new `Arc<CellExtra>`
+    /// and zerowidth buffer for every single cell.
+    ///
+    /// The pool is per thread instead of owned by the grid because cells are reset through
+    /// `GridSquare::reset`, `Row` and `Storage`, none of which can reach the grid. The parser
+    /// of each terminal runs on its own thread, so this still gives every grid its own pool.
+    static EXTRA_POOL: RefCell<Vec<Arc<CellExtra>>> = RefCell::new(Vec::new());
+}
+
+/// Return the extra storage of a cell to the pool when nothing else references it.
+#[inline]
+pub fn recycle_extra(extra: Option<Arc<CellExtra>>) {
+    if let Some(mut extra) = extra {
+        if let Some(inner) = Arc::get_mut(&mut extra) {
+            inner.zerowidth.clear();
+            inner.underline_color = None;
+            inner.strikeout_color = None;
+            inner.hyperlink = None;
+
+            EXTRA_POOL.with(|pool| {
+                let mut pool = pool.borrow_mut();
+                if pool.len() < EXTRA_POOL_CAPACITY {
+                    pool.push(extra);
+                }
+            });
+        }
+    }
+}
+
+/// Take an empty extra from the pool, allocating a new one if the pool is exhausted.
+#[inline]
+fn pooled_extra() -> Arc<CellExtra> {
+    EXTRA_POOL
+        .with(|pool| pool.borrow_mut().pop())
+        .unwrap_or_else(|| {
+            Arc::new(CellExtra {
+                zerowidth: Vec::with_capacity(2),
+                ..Default::default()
+            })
+        })
+}
+
 /// Content and attributes of a single cell in the terminal grid.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Square {
     pub c: char,
     pub fg: AnsiColor,
     pub bg: AnsiColor,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub extra: Option<Arc<CellExtra>>,
     pub flags: Flags,
 }
@@ -130,12 +286,222 @@ impl Square {
     }
 
     /// Write a new zerowidth character to this cell.
+    ///
+    /// An extra shared with other cells, like the squares of a hyperlink span, is split
+    /// through the pool. The hyperlink and colors are copied as handles, never deep cloned.
     #[inline]
     pub fn push_zerowidth(&mut self, character: char) {
-        let extra = self.extra.get_or_insert(Default::default());
+        let extra = self.extra.get_or_insert_with(pooled_extra);
+        if Arc::get_mut(extra).is_none() {
+            let mut unique = pooled_extra();
+            let inner = Arc::make_mut(&mut unique);
+            inner.zerowidth.extend_from_slice(&extra.zerowidth);
+            inner.underline_color = extra.underline_color;
+            inner.strikeout_color = extra.strikeout_color;
+            inner.hyperlink = extra.hyperlink.clone();
+            *extra = unique;
+        }
+
         Arc::make_mut(extra).zerowidth.push(character);
     }
 
+    /// Write a new zerowidth character and promote the cell to `WIDE_CHAR` when
+    /// the resulting cluster is wide.
+    ///
+    /// Returns `true` when the cell was promoted, the caller is responsible for
+    /// turning the following cell into a `WIDE_CHAR_SPACER`.
+    #[inline]
+    pub fn push_zerowidth_and_promote(&mut self, character: char) -> bool {
+        self.push_zerowidth(character);
+
+        let should_promote =
+            !self.flags.contains(Flags::WIDE_CHAR) && self.cluster_width() == 2;
+        if should_promote {
+            self.flags.insert(Flags::WIDE_CHAR);
+        }
+
+        should_promote
+    }
+
+    /// Template used to erase cells while this square is the cursor template.
+    pub fn erase_template(&self, mode: ResetMode) -> Square {
+        match mode {
+            ResetMode::NoBce => Square::default(),
+            ResetMode::BcePreserveBg => Square {
+                bg: self.bg,
+                extra: self.extra.clone(),
+                ..Square::default()
+            },
+            ResetMode::BceFull => Square {
+                fg: self.fg,
+                bg: self.bg,
+                flags: self.flags & Flags::INVERSE,
+                extra: self.extra.clone(),
+                ..Square::default()
+            },
+        }
+    }
+
+    /// Apply a graphic rendition (SGR) attribute to this cell.
+    pub fn apply(&mut self, attr: Attr) {
+        match attr {
+            Attr::Foreground(color) => self.fg = color,
+            Attr::Background(color) => self.bg = color,
+            Attr::UnderlineColor(color) => self.set_underline_color(color),
+            Attr::Reset => {
+                self.fg = AnsiColor::Named(NamedColor::Foreground);
+                self.bg = AnsiColor::Named(NamedColor::Background);
+                // Protection is set by DECSCA, not by SGR.
+                self.flags &= Flags::PROTECTED;
+                self.set_underline_color(None);
+            }
+            Attr::Reverse => self.flags.insert(Flags::INVERSE),
+            Attr::CancelReverse => self.flags.remove(Flags::INVERSE),
+            Attr::Bold => self.flags.insert(Flags::BOLD),
+            Attr::CancelBold => self.flags.remove(Flags::BOLD),
+            Attr::Dim => self.flags.insert(Flags::DIM),
+            Attr::CancelBoldDim => self.flags.remove(Flags::BOLD | Flags::DIM),
+            Attr::Italic => self.flags.insert(Flags::ITALIC),
+            Attr::CancelItalic => self.flags.remove(Flags::ITALIC),
+            Attr::Underline => self.set_underline_style(Some(UnderlineStyle::Single)),
+            Attr::DoubleUnderline => {
+                self.set_underline_style(Some(UnderlineStyle::Double))
+            }
+            Attr::Undercurl => self.set_underline_style(Some(UnderlineStyle::Curly)),
+            Attr::DottedUnderline => {
+                self.set_underline_style(Some(UnderlineStyle::Dotted))
+            }
+            Attr::DashedUnderline => {
+                self.set_underline_style(Some(UnderlineStyle::Dashed))
+            }
+            Attr::CancelUnderline => self.set_underline_style(None),
+            Attr::Hidden => self.flags.insert(Flags::HIDDEN),
+            Attr::CancelHidden => self.flags.remove(Flags::HIDDEN),
+            Attr::Strike => self.flags.insert(Flags::STRIKEOUT),
+            Attr::CancelStrike => self.flags.remove(Flags::STRIKEOUT),
+            Attr::BlinkSlow => self.flags.insert(Flags::SLOW_BLINK),
+            Attr::BlinkFast => self.flags.insert(Flags::RAPID_BLINK),
+            Attr::CancelBlink => self.flags.remove(Flags::ALL_BLINKS),
+            Attr::Overline => self.flags.insert(Flags::OVERLINE),
+            Attr::CancelOverline => self.flags.remove(Flags::OVERLINE),
+        }
+    }
+
+    /// Underline styles are mutually exclusive, setting one replaces the others and `None`
+    /// removes the underline.
+    #[inline]
+    pub fn set_underline_style(&mut self, style: Option<UnderlineStyle>) {
+        self.flags.remove(Flags::ALL_UNDERLINES);
+        if let Some(style) = style {
+            self.flags.insert(style.flag());
+        }
+    }
+
+    /// Apply the Kitty `SGR 4:x` underline sub-parameter, unknown values are ignored.
+    #[allow(dead_code)]
+    pub fn set_underline_from_kitty(&mut self, sub: u16) {
+        let style = match sub {
+            0 => None,
+            1 => Some(UnderlineStyle::Single),
+            2 => Some(UnderlineStyle::Double),
+            3 => Some(UnderlineStyle::Curly),
+            4 => Some(UnderlineStyle::Dotted),
+            5 => Some(UnderlineStyle::Dashed),
+            _ => return,
+        };
+
+        self.set_underline_style(style);
+    }
+
+    /// Active underline style of this cell.
+    #[inline]
+    pub fn underline_style(&self) -> Option<UnderlineStyle> {
+        UnderlineStyle::ALL
+            .into_iter()
+            .find(|style| self.flags.contains(style.flag()))
+    }
+
+    /// Append the text of this cell, including any zerowidth characters, to `out`.
+    ///
+    /// Wide char spacers do not carry any content and are skipped entirely. Every text
+    /// extraction, like copying a selection, goes through this.
+    #[inline]
+    pub fn write_text_to(&self, out: &mut String) {
+        if self
+            .flags
+            .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+        {
+            return;
+        }
+
+        out.push(self.c);
+        out.extend(self.zerowidth().into_iter().flatten());
+    }
+
+    /// Display width of the grapheme cluster stored in this cell, either 1 or 2.
+    ///
+    /// The emoji presentation selector (VS16) makes a narrow base wide, while the text
+    /// presentation selector (VS15) keeps it narrow, the last selector of the cluster wins.
+    pub fn cluster_width(&self) -> usize {
+        if self.c.width().unwrap_or(1) >= 2 {
+            return 2;
+        }
+
+        let is_wide = self.presentation_selector() == Some(EMOJI_PRESENTATION)
+            || self.zerowidth().map_or(false, |zerowidth| {
+                zerowidth.iter().any(|&c| {
+                    // Wide zerowidth chars or a regional indicator pair (flags).
+                    c.width().unwrap_or(0) >= 2
+                        || (is_regional_indicator(self.c) && is_regional_indicator(c))
+                })
+            });
+
+        if is_wide {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Last variation selector asking for a text (VS15) or emoji (VS16) presentation, used to
+    /// pick the glyph of the cluster.
+    pub fn presentation_selector(&self) -> Option<char> {
+        self.zerowidth()?
+            .iter()
+            .rev()
+            .copied()
+            .find(|&c| c == TEXT_PRESENTATION || c == EMOJI_PRESENTATION)
+    }
+
+    /// Write a wide glyph, the following cell must be turned into a spacer.
+    #[inline]
+    #[allow(dead_code)]
+    pub fn set_wide(&mut self, c: char) {
+        self.c = c;
+        self.flags.remove(Flags::WIDE_CHAR_SPACER);
+        self.flags.insert(Flags::WIDE_CHAR);
+    }
+
+    /// Turn this cell into the spacer following a wide glyph.
+    #[inline]
+    #[allow(clippy::wrong_self_convention)]
+    pub fn into_spacer(&mut self) {
+        self.c = ' ';
+        self.flags.remove(Flags::WIDE_CHAR);
+        self.flags.insert(Flags::WIDE_CHAR_SPACER);
+        recycle_extra(self.extra.take());
+    }
+
+    #[inline]
+    pub fn is_wide(&self) -> bool {
+        self.flags.contains(Flags::WIDE_CHAR)
+    }
+
+    #[inline]
+    pub fn is_spacer(&self) -> bool {
+        self.flags.contains(Flags::WIDE_CHAR_SPACER)
+    }
+
     #[inline(never)]
     pub fn clear_wide(&mut self) {
         self.flags.remove(Flags::WIDE_CHAR);
@@ -149,7 +515,9 @@ impl Square {
         // If we reset color and we don't have zerowidth we should drop extra storage.
         if color.is_none()
             && self.extra.as_ref().map_or(true, |extra| {
-                extra.zerowidth.is_empty() && extra.hyperlink.is_none()
+                extra.zerowidth.is_empty()
+                    && extra.hyperlink.is_none()
+                    && extra.strikeout_color.is_none()
             })
         {
             self.extra = None;
@@ -165,11 +533,51 @@ impl Square {
         self.extra.as_ref()?.underline_color
     }
 
+    /// Key for caching the rendering of this square.
+    ///
+    /// Squares drawn the same way share a key, whatever their wrapping or protection.
+    #[allow(dead_code)]
+    pub fn style_key(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.c.hash(&mut hasher);
+        self.zerowidth().unwrap_or_default().hash(&mut hasher);
+        self.fg.hash(&mut hasher);
+        self.bg.hash(&mut hasher);
+        (self.flags & STYLE_MASK).bits().hash(&mut hasher);
+        self.underline_color().hash(&mut hasher);
+        self.strikeout_color().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Set strikeout color, only allocating extra storage for an explicit color.
+    pub fn set_strikeout_color(&mut self, color: Option<rio_config::colors::AnsiColor>) {
+        if color.is_none()
+            && self.extra.as_ref().map_or(true, |extra| {
+                extra.zerowidth.is_empty()
+                    && extra.hyperlink.is_none()
+                    && extra.underline_color.is_none()
+            })
+        {
+            self.extra = None;
+        } else {
+            let extra = self.extra.get_or_insert(Default::default());
+            Arc::make_mut(extra).strikeout_color = color;
+        }
+    }
+
+    /// Strikeout color stored in this cell.
+    #[inline]
+    pub fn strikeout_color(&self) -> Option<rio_config::colors::AnsiColor> {
+        self.extra.as_ref()?.strikeout_color
+    }
+
     /// Set hyperlink.
     pub fn set_hyperlink(&mut self, hyperlink: Option<Hyperlink>) {
         let should_drop = hyperlink.is_none()
             && self.extra.as_ref().map_or(true, |extra| {
-                extra.zerowidth.is_empty() && extra.underline_color.is_none()
+                extra.zerowidth.is_empty()
+                    && extra.underline_color.is_none()
+                    && extra.strikeout_color.is_none()
             });
 
         if should_drop {
@@ -180,6 +588,78 @@ impl Square {
         }
     }
 
+    /// Whether this cell has the overline attribute set.
+    #[inline]
+    pub fn is_overlined(&self) -> bool {
+        self.flags.contains(Flags::OVERLINE)
+    }
+
+    /// Whether this cell is protected from selective erase (DECSCA).
+    #[inline]
+    pub fn is_protected(&self) -> bool {
+        self.flags.contains(Flags::PROTECTED)
+    }
+
+    /// Reset this cell from `template` unless it is protected, used by selective erase.
+    #[inline]
+    pub fn reset_keep_protected(&mut self, template: &Square) {
+        if !self.is_protected() {
+            self.reset(template);
+        }
+    }
+
+    /// Blink rate of this cell, rapid blink takes precedence when both are set.
+    #[inline]
+    pub fn blink_state(&self) -> Option<BlinkRate> {
+        if self.flags.contains(Flags::RAPID_BLINK) {
+            Some(BlinkRate::Rapid)
+        } else if self.flags.contains(Flags::SLOW_BLINK) {
+            Some(BlinkRate::Slow)
+        } else {
+            None
+        }
+    }
+
+    /// Glyph weight of this cell.
+    ///
+    /// When both `BOLD` and `DIM` are set bold wins for the glyph, like xterm does, while the
+    /// color is still dimmed from the `DIM` flag.
+    #[inline]
+    pub fn weight(&self) -> FontWeight {
+        if self.flags.contains(Flags::BOLD) {
+            FontWeight::Bold
+        } else if self.flags.contains(Flags::DIM) {
+            FontWeight::Dim
+        } else {
+            FontWeight::Normal
+        }
+    }
+
+    /// Foreground and background colors of this cell once `INVERSE` and `HIDDEN` are applied.
+    ///
+    /// Hidden cells get the background color as foreground so their text is invisible.
+    #[inline]
+    pub fn resolved_colors(&self) -> (AnsiColor, AnsiColor) {
+        self.resolve_colors(self.fg, self.bg)
+    }
+
+    /// Same as [`Square::resolved_colors`], for the `fg` and `bg` of this cell already
+    /// converted to another representation, like the color arrays of the renderer.
+    #[inline]
+    pub fn resolve_colors<C: Copy>(&self, fg: C, bg: C) -> (C, C) {
+        let (fg, bg) = if self.flags.contains(Flags::INVERSE) {
+            (bg, fg)
+        } else {
+            (fg, bg)
+        };
+
+        if self.flags.contains(Flags::HIDDEN) {
+            (bg, bg)
+        } else {
+            (fg, bg)
+        }
+    }
+
     /// Hyperlink stored in this cell.
     #[inline]
     pub fn hyperlink(&self) -> Option<Hyperlink> {
@@ -187,27 +667,55 @@ impl Square {
     }
 }
 
+/// Variation selector 15, requesting the text presentation of the previous char.
+pub const TEXT_PRESENTATION: char = '\u{FE0E}';
+
+/// Variation selector 16, requesting the emoji presentation of the previous char.
+pub const EMOJI_PRESENTATION: char = '\u{FE0F}';
+
+#[inline]
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
 impl GridSquare for Square {
     #[inline]
     fn is_empty(&self) -> bool {
         (self.c == ' ' || self.c == '\t')
             && self.bg == AnsiColor::Named(NamedColor::Background)
             && self.fg == AnsiColor::Named(NamedColor::Foreground)
-            && !self.flags.intersects(
-                Flags::INVERSE
-                    | Flags::ALL_UNDERLINES
-                    | Flags::STRIKEOUT
-                    | Flags::WRAPLINE
-                    | Flags::WIDE_CHAR_SPACER
-                    | Flags::LEADING_WIDE_CHAR_SPACER,
-            )
-            && self.extra.as_ref().map(|extra| extra.zerowidth.is_empty()) != Some(false)
+            && !self.flags.intersects(EMPTY_CHECK_MASK)
+            && self.extra.as_ref().map_or(true, |extra| {
+                extra.zerowidth.is_empty() && extra.hyperlink.is_none()
+            })
     }
 
     #[inline]
     fn reset(&mut self, template: &Self) {
+        // Inherit the pen's hyperlink and underline color, but never its zerowidth chars or
+        // strikeout color.
+        let extra = template.extra.as_ref().and_then(|extra| {
+            if extra.hyperlink.is_none() && extra.underline_color.is_none() {
+                None
+            } else if extra.zerowidth.is_empty() && extra.strikeout_color.is_none() {
+                Some(extra.clone())
+            } else {
+                Some(Arc::new(CellExtra {
+                    zerowidth: Vec::new(),
+                    underline_color: extra.underline_color,
+                    strikeout_color: None,
+                    hyperlink: extra.hyperlink.clone(),
+                }))
+            }
+        });
+
+        recycle_extra(self.extra.take());
+
         *self = Square {
+            fg: template.fg,
             bg: template.bg,
+            flags: template.flags & Flags::INVERSE,
+            extra,
             ..Square::default()
         };
     }
@@ -226,27 +734,178 @@ impl GridSquare for Square {
 pub trait LineLength {
     /// Calculate the occupied line length.
     fn line_length(&self) -> Column;
+
+    /// Column of the last occupied cell, ignoring the `WRAPLINE` flag.
+    fn last_occupied(&self) -> Option<Column>;
+}
+
+/// Overwrite every square of `rect` with a copy of `template`, lines being indices in `rows`.
+///
+/// Used for DECALN and background fills, see [`fill_row`] for the handling of wide chars.
+#[allow(dead_code)]
+pub fn fill_region(rows: &mut [Row<Square>], rect: &Rect, template: &Square) {
+    let start = std::cmp::min(rect.lines.start.0.max(0) as usize, rows.len());
+    let end = std::cmp::min(rect.lines.end.0.max(0) as usize, rows.len());
+    for row in rows[start..end.max(start)].iter_mut() {
+        fill_row(row, rect.columns.clone(), template);
+    }
+}
+
+/// Overwrite the squares of `columns` in `row` with a copy of `template`.
+///
+/// Wide chars cut in half by the edges are cleared, and a wide template is written in pairs
+/// with its spacer.
+pub fn fill_row(row: &mut Row<Square>, columns: Range<Column>, template: &Square) {
+    let left = std::cmp::min(columns.start.0, row.len());
+    let right = std::cmp::min(columns.end.0, row.len());
+    if left >= right {
+        return;
+    }
+
+    // Clear the halves of wide chars left outside of the area.
+    clear_split_wide_char(row, Column(left));
+    clear_split_wide_char(row, Column(right));
+
+    let wide = template.flags.contains(Flags::WIDE_CHAR);
+    let blank = Square {
+        fg: template.fg,
+        bg: template.bg,
+        ..Square::default()
+    };
+    let spacer = Square {
+        flags: Flags::WIDE_CHAR_SPACER,
+        ..blank.clone()
+    };
+
+    for column in left..right {
+        let square = if !wide {
+            template.clone()
+        } else if (column - left) % 2 == 1 {
+            spacer.clone()
+        } else if column + 1 < right {
+            template.clone()
+        } else {
+            // No room left for the second half.
+            blank.clone()
+        };
+
+        recycle_extra(std::mem::replace(&mut row[Column(column)], square).extra);
+    }
+}
+
+/// Shift the squares of `columns` right by `count` from its start, filling the gap with
+/// `template` (ICH).
+///
+/// Squares pushed past the end of `columns` are dropped, and wide chars split by the shift or
+/// by the edges are cleared.
+pub fn insert_chars(
+    row: &mut Row<Square>,
+    columns: Range<Column>,
+    count: usize,
+    template: &Square,
+) {
+    let start = std::cmp::min(columns.start.0, row.len());
+    let end = std::cmp::min(columns.end.0, row.len());
+    let count = std::cmp::min(count, end.saturating_sub(start));
+    if count == 0 {
+        return;
+    }
+
+    clear_split_wide_char(row, Column(start));
+    clear_split_wide_char(row, Column(end - count));
+    clear_split_wide_char(row, Column(end));
+
+    let squares = &mut row[Column(start)..Column(end)];
+    for offset in (0..squares.len() - count).rev() {
+        squares.swap(offset + count, offset);
+    }
+
+    // The dropped squares were moved into the gap.
+    for square in &mut squares[..count] {
+        recycle_extra(std::mem::replace(square, template.clone()).extra);
+    }
+}
+
+/// Shift the squares of `columns` left by `count` onto its start, filling the tail with
+/// `template` (DCH).
+///
+/// Wide chars split by the deleted range or by the edges are cleared.
+pub fn delete_chars(
+    row: &mut Row<Square>,
+    columns: Range<Column>,
+    count: usize,
+    template: &Square,
+) {
+    let start = std::cmp::min(columns.start.0, row.len());
+    let end = std::cmp::min(columns.end.0, row.len());
+    let count = std::cmp::min(count, end.saturating_sub(start));
+    if count == 0 {
+        return;
+    }
+
+    clear_split_wide_char(row, Column(start));
+    clear_split_wide_char(row, Column(start + count));
+    clear_split_wide_char(row, Column(end));
+
+    let squares = &mut row[Column(start)..Column(end)];
+    let len = squares.len();
+    for offset in 0..len - count {
+        squares.swap(offset, offset + count);
+    }
+
+    // The deleted squares were moved to the tail.
+    for square in &mut squares[len - count..] {
+        recycle_extra(std::mem::replace(square, template.clone()).extra);
+    }
+}
+
+/// Clear the wide char ending right before `edge` and the spacer starting at `edge`.
+///
+/// Run on both edges of an area before writing to it, so no wide char outside keeps only one
+/// half, and after copying squares into it, so no half copied alone stays orphaned.
+pub fn clear_split_wide_char(row: &mut Row<Square>, edge: Column) {
+    if edge > 0 && edge <= row.len() && row[edge - 1].flags.contains(Flags::WIDE_CHAR) {
+        row[edge - 1].reset(&Square::default());
+    }
+
+    if edge < row.len() && row[edge].flags.contains(Flags::WIDE_CHAR_SPACER) {
+        row[edge].reset(&Square::default());
+    }
 }
 
 impl LineLength for Row<Square> {
     fn line_length(&self) -> Column {
-        let mut length = Column(0);
-
         if self[Column(self.len() - 1)].flags.contains(Flags::WRAPLINE) {
             return Column(self.len());
         }
 
-        for (index, cell) in self[..].iter().rev().enumerate() {
-            if cell.c != ' '
-                || cell.extra.as_ref().map(|extra| extra.zerowidth.is_empty())
-                    == Some(false)
-            {
-                length = Column(self.len() - index);
-                break;
-            }
-        }
+        self.last_occupied().map_or(Column(0), |column| column + 1)
+    }
+
+    fn last_occupied(&self) -> Option<Column> {
+        // Squares after the occupied length were reset and hold no content.
+        let squares = &self[..];
+        (0..std::cmp::min(self.occ, squares.len()))
+            .rev()
+            .find(|&index| {
+                let cell = &squares[index];
 
-        length
+                // The spacer holds the second half of the preceding wide char.
+                let is_wide_char_spacer = cell.flags.contains(Flags::WIDE_CHAR_SPACER)
+                    && index > 0
+                    && squares[index - 1].flags.contains(Flags::WIDE_CHAR);
+
+                cell.c != ' '
+                    || is_wide_char_spacer
+                    || cell.flags.intersects(
+                        Flags::ALL_UNDERLINES
+                            | Flags::OVERLINE
+                            | Flags::LEADING_WIDE_CHAR_SPACER,
+                    )
+                    || cell.extra.as_ref().map(|extra| extra.zerowidth.is_empty())
+                        == Some(false)
+            })
+            .map(Column)
     }
 }
 
@@ -261,9 +920,32 @@ impl<T: Copy> ResetDiscriminant<T> for T {
     }
 }
 
-impl ResetDiscriminant<AnsiColor> for Square {
-    fn discriminant(&self) -> AnsiColor {
-        self.bg
+/// State which decides whether two squares can be reset interchangeably.
+///
+/// The hyperlink is compared by the address of its shared allocation, so computing the
+/// discriminant never clones or deep compares the link.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SquareDiscriminant {
+    fg: AnsiColor,
+    bg: AnsiColor,
+    inverse: bool,
+    underline_color: Option<AnsiColor>,
+    hyperlink: Option<usize>,
+}
+
+impl ResetDiscriminant<SquareDiscriminant> for Square {
+    fn discriminant(&self) -> SquareDiscriminant {
+        SquareDiscriminant {
+            fg: self.fg,
+            bg: self.bg,
+            inverse: self.flags.contains(Flags::INVERSE),
+            underline_color: self.underline_color(),
+            hyperlink: self
+                .extra
+                .as_ref()
+                .and_then(|extra| extra.hyperlink.as_ref())
+                .map(Hyperlink::addr),
+        }
     }
 }
 
@@ -284,7 +966,7 @@ mod tests {
     use std::mem;
 
     use crate::crosswords::grid::row::Row;
-    use crate::crosswords::pos::Column;
+    use crate::crosswords::pos::{Column, Line};
 
     #[test]
     fn test_square_size_is_below_cap() {
@@ -296,18 +978,970 @@ mod tests {
     }
 
     #[test]
-    fn test_line_length_works() {
-        let mut row = Row::<Square>::new(10);
-        row[Column(5)].c = 'a';
+    fn test_underline_color_allocates_extra_only_when_set() {
+        let mut square = Square::default();
+        square.set_underline_color(None);
+        assert!(square.extra.is_none());
 
-        assert_eq!(row.line_length(), Column(6));
+        let color = AnsiColor::Indexed(1);
+        square.set_underline_color(Some(color));
+        assert_eq!(square.underline_color(), Some(color));
+
+        square.set_underline_color(None);
+        assert_eq!(square.underline_color(), None);
+        assert!(square.extra.is_none());
     }
 
     #[test]
-    fn test_line_length_works_with_wrapline() {
+    fn test_strikeout_color_allocates_extra_only_when_set() {
+        let mut square = Square::default();
+        square.flags.insert(Flags::STRIKEOUT);
+        square.set_strikeout_color(None);
+        assert!(square.extra.is_none());
+        assert_eq!(square.strikeout_color(), None);
+
+        let color = AnsiColor::Indexed(2);
+        square.set_strikeout_color(Some(color));
+        assert_eq!(square.strikeout_color(), Some(color));
+        assert_eq!(square.underline_color(), None);
+
+        square.set_strikeout_color(None);
+        assert!(square.extra.is_none());
+    }
+
+    #[test]
+    fn test_strikeout_color_cleared_by_reset() {
+        let mut template = Square::default();
+        template.set_underline_color(Some(AnsiColor::Indexed(1)));
+        template.set_strikeout_color(Some(AnsiColor::Indexed(2)));
+
+        let mut square = template.clone();
+        square.reset(&template);
+        assert_eq!(square.strikeout_color(), None);
+        assert_eq!(square.underline_color(), Some(AnsiColor::Indexed(1)));
+
+        square.reset(&Square::default());
+        assert!(square.extra.is_none());
+    }
+
+    #[test]
+    fn test_underline_color_is_empty_and_reset() {
+        let mut square = Square::default();
+        square.set_underline_color(Some(AnsiColor::Indexed(1)));
+        assert!(square.is_empty());
+
+        square.reset(&Square::default());
+        assert_eq!(square.underline_color(), None);
+    }
+
+    #[test]
+    fn test_hyperlink_is_dropped_on_reset() {
+        let mut square = Square::default();
+        let hyperlink = Hyperlink::new(Some("id"), "https://rust-lang.org");
+        square.set_hyperlink(Some(hyperlink.clone()));
+        assert_eq!(square.hyperlink(), Some(hyperlink));
+
+        square.reset(&Square::default());
+        assert_eq!(square.hyperlink(), None);
+        assert!(square.extra.is_none());
+    }
+
+    #[test]
+    fn test_hyperlink_square_is_not_empty() {
+        let mut square = Square::default();
+        square.set_hyperlink(Some(Hyperlink::new(None, "https://example.com")));
+        assert!(!square.is_empty());
+    }
+
+    #[test]
+    fn test_hyperlink_clones_share_storage() {
+        let hyperlink = Hyperlink::new(Some("x"), "https://example.com");
+        let mut first = Square::default();
+        let mut second = Square::default();
+        first.set_hyperlink(Some(hyperlink.clone()));
+        second.set_hyperlink(Some(hyperlink));
+
+        assert!(Arc::ptr_eq(
+            &first.hyperlink().unwrap().inner,
+            &second.hyperlink().unwrap().inner
+        ));
+    }
+
+    #[test]
+    fn test_colored_background_space_is_not_empty() {
+        assert!(Square::default().is_empty());
+
+        let square = Square {
+            bg: AnsiColor::Named(NamedColor::Red),
+            ..Square::default()
+        };
+        assert!(!square.is_empty());
+
+        // Clearing with a colored template keeps the padding visible.
+        let mut row = Row::<Square>::new(5);
+        row.reset(&square);
+        assert!(!row.is_clear());
+        assert_eq!(row.occupied().count(), 5);
+    }
+
+    #[test]
+    fn test_overline_square_is_not_empty() {
+        let mut square = Square::default();
+        square.flags.insert(Flags::OVERLINE);
+        assert!(square.is_overlined());
+        assert!(!square.is_empty());
+
+        square.reset(&Square::default());
+        assert!(!square.is_overlined());
+        assert!(square.is_empty());
+
         let mut row = Row::<Square>::new(10);
-        row[Column(9)].flags.insert(super::Flags::WRAPLINE);
+        row[Column(7)].flags.insert(Flags::OVERLINE);
+        assert_eq!(row.line_length(), Column(8));
+    }
 
-        assert_eq!(row.line_length(), Column(10));
+    #[test]
+    fn test_underlined_space_is_not_empty() {
+        for flag in [
+            Flags::UNDERLINE,
+            Flags::DOUBLE_UNDERLINE,
+            Flags::UNDERCURL,
+            Flags::DOTTED_UNDERLINE,
+            Flags::DASHED_UNDERLINE,
+        ] {
+            let mut square = Square::default();
+            square.flags.insert(flag);
+            assert!(!square.is_empty(), "{flag:?} should not be empty");
+
+            let mut row = Row::<Square>::new(10);
+            row[Column(4)].flags.insert(flag);
+            assert_eq!(row.line_length(), Column(5), "{flag:?} was trimmed");
+        }
+    }
+
+    #[test]
+    fn test_blink_flags_coexist_and_reset() {
+        let mut square = Square::default();
+        assert_eq!(square.blink_state(), None);
+
+        square.flags.insert(Flags::SLOW_BLINK);
+        assert_eq!(square.blink_state(), Some(BlinkRate::Slow));
+        assert!(square.is_empty());
+
+        square.flags.insert(Flags::RAPID_BLINK);
+        assert!(square.flags.contains(Flags::ALL_BLINKS));
+        assert_eq!(square.blink_state(), Some(BlinkRate::Rapid));
+
+        square.reset(&Square::default());
+        assert!(!square.flags.intersects(Flags::ALL_BLINKS));
+        assert_eq!(square.blink_state(), None);
+    }
+
+    #[test]
+    fn test_cluster_width_flag_emoji() {
+        let mut square = Square {
+            c: '🇧',
+            ..Square::default()
+        };
+        assert_eq!(square.cluster_width(), 1);
+
+        assert!(square.push_zerowidth_and_promote('🇷'));
+        assert_eq!(square.cluster_width(), 2);
+        assert!(square.flags.contains(Flags::WIDE_CHAR));
+    }
+
+    #[test]
+    fn test_cluster_width_family_emoji() {
+        let mut square = Square {
+            c: '👨',
+            flags: Flags::WIDE_CHAR,
+            ..Square::default()
+        };
+        for c in ['\u{200D}', '👩', '\u{200D}', '👧'] {
+            // Already wide, nothing to promote.
+            assert!(!square.push_zerowidth_and_promote(c));
+        }
+
+        assert_eq!(square.cluster_width(), 2);
+        assert_eq!(square.zerowidth().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_cluster_width_vs16_promotion() {
+        let mut square = Square {
+            c: '❤',
+            ..Square::default()
+        };
+        assert_eq!(square.cluster_width(), 1);
+
+        square.push_zerowidth('\u{FE0F}');
+        assert_eq!(square.cluster_width(), 2);
+        assert!(!square.flags.contains(Flags::WIDE_CHAR));
+
+        let mut square = Square {
+            c: '❤',
+            ..Square::default()
+        };
+        assert!(square.push_zerowidth_and_promote('\u{FE0F}'));
+        assert!(square.flags.contains(Flags::WIDE_CHAR));
+    }
+
+    #[test]
+    fn test_cluster_width_presentation_selectors() {
+        let sun = || Square {
+            c: '\u{2600}',
+            ..Square::default()
+        };
+
+        let mut square = sun();
+        assert!(square.push_zerowidth_and_promote(EMOJI_PRESENTATION));
+        assert!(square.flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(square.presentation_selector(), Some(EMOJI_PRESENTATION));
+
+        let mut square = sun();
+        assert!(!square.push_zerowidth_and_promote(TEXT_PRESENTATION));
+        assert!(!square.flags.contains(Flags::WIDE_CHAR));
+        assert_eq!(square.cluster_width(), 1);
+        assert_eq!(square.zerowidth(), Some(&[TEXT_PRESENTATION][..]));
+        assert_eq!(square.presentation_selector(), Some(TEXT_PRESENTATION));
+
+        // The last selector wins.
+        let mut square = sun();
+        square.push_zerowidth(EMOJI_PRESENTATION);
+        square.push_zerowidth(TEXT_PRESENTATION);
+        assert_eq!(square.cluster_width(), 1);
+        assert_eq!(sun().presentation_selector(), None);
+    }
+
+    #[test]
+    fn test_reset_inherits_template_extra() {
+        let hyperlink = Hyperlink::new(Some("id"), "https://rust-lang.org");
+        let mut template = Square::default();
+        template.set_hyperlink(Some(hyperlink.clone()));
+
+        let mut square = Square {
+            c: 'a',
+            flags: Flags::BOLD,
+            ..Square::default()
+        };
+        square.reset(&template);
+
+        assert_eq!(square.c, ' ');
+        assert!(square.flags.is_empty());
+        assert_eq!(square.hyperlink(), Some(hyperlink));
+        assert!(Arc::ptr_eq(
+            square.extra.as_ref().unwrap(),
+            template.extra.as_ref().unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_reset_does_not_inherit_template_zerowidth() {
+        let mut template = Square::default();
+        template.set_underline_color(Some(AnsiColor::Indexed(1)));
+        template.push_zerowidth('\u{301}');
+
+        let mut square = Square::default();
+        square.reset(&template);
+
+        assert_eq!(square.underline_color(), Some(AnsiColor::Indexed(1)));
+        assert_eq!(square.zerowidth(), Some(&[][..]));
+
+        let mut square = Square::default();
+        square.reset(&Square::from(AnsiColor::Indexed(2)));
+        assert!(square.extra.is_none());
+    }
+
+    #[test]
+    fn test_row_occupied() {
+        let mut row = Row::<Square>::new(10);
+        row[Column(2)].c = 'a';
+        row[Column(7)].c = 'b';
+
+        let occupied: Vec<(Column, char)> = row
+            .occupied()
+            .map(|(column, square)| (column, square.c))
+            .collect();
+        assert_eq!(occupied, vec![(Column(2), 'a'), (Column(7), 'b')]);
+    }
+
+    #[test]
+    fn test_write_text_to_includes_zerowidth() {
+        let mut square = Square {
+            c: 'e',
+            ..Square::default()
+        };
+        square.push_zerowidth('\u{301}');
+
+        let mut text = String::new();
+        square.write_text_to(&mut text);
+        assert_eq!(text, "e\u{301}");
+    }
+
+    #[test]
+    fn test_write_text_to_combining_accent_code_points() {
+        let mut square = Square {
+            c: 'e',
+            ..Square::default()
+        };
+        square.push_zerowidth('\u{301}');
+
+        let mut text = String::new();
+        square.write_text_to(&mut text);
+        assert_eq!(text.chars().collect::<Vec<_>>(), vec!['e', '\u{301}']);
+    }
+
+    #[test]
+    fn test_write_text_to_wide_char_pair() {
+        let mut row = Row::<Square>::new(2);
+        row[Column(0)].c = '\u{4f60}';
+        row[Column(0)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        let mut text = String::new();
+        for square in &row {
+            square.write_text_to(&mut text);
+        }
+        assert_eq!(text.chars().collect::<Vec<_>>(), vec!['\u{4f60}']);
+    }
+
+    #[test]
+    fn test_write_text_to_skips_spacers() {
+        let mut text = String::new();
+        for flag in [Flags::WIDE_CHAR_SPACER, Flags::LEADING_WIDE_CHAR_SPACER] {
+            let square = Square {
+                flags: flag,
+                ..Square::default()
+            };
+            square.write_text_to(&mut text);
+        }
+        assert!(text.is_empty());
+    }
+
+    #[test]
+    fn test_reset_recycles_unique_extra() {
+        let mut square = Square::default();
+        square.push_zerowidth('\u{301}');
+        let ptr = Arc::as_ptr(square.extra.as_ref().unwrap());
+
+        square.reset(&Square::default());
+        assert!(square.extra.is_none());
+
+        let mut other = Square::default();
+        other.push_zerowidth('\u{302}');
+        assert_eq!(Arc::as_ptr(other.extra.as_ref().unwrap()), ptr);
+        assert_eq!(other.zerowidth(), Some(&['\u{302}'][..]));
+        assert!(other.underline_color().is_none());
+        assert!(other.hyperlink().is_none());
+    }
+
+    #[test]
+    fn test_reset_does_not_recycle_shared_extra() {
+        let mut square = Square::default();
+        square.push_zerowidth('\u{301}');
+        let copy = square.clone();
+
+        square.reset(&Square::default());
+
+        let mut other = Square::default();
+        other.push_zerowidth('\u{302}');
+        assert!(!Arc::ptr_eq(
+            other.extra.as_ref().unwrap(),
+            copy.extra.as_ref().unwrap()
+        ));
+        assert_eq!(copy.zerowidth(), Some(&['\u{301}'][..]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_square_serde_round_trip() {
+        let mut square = Square {
+            c: 'e',
+            fg: AnsiColor::Indexed(3),
+            bg: AnsiColor::Spec(rio_config::colors::ColorRgb { r: 1, g: 2, b: 3 }),
+            flags: Flags::BOLD | Flags::UNDERCURL | Flags::WIDE_CHAR | Flags::SLOW_BLINK,
+            ..Square::default()
+        };
+        square.push_zerowidth('\u{301}');
+        square.push_zerowidth('\u{302}');
+        square.set_underline_color(Some(AnsiColor::Named(NamedColor::Red)));
+
+        let json = serde_json::to_string(&square).unwrap();
+        let restored: Square = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, square);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_square_serde_skips_missing_extra() {
+        let square = Square {
+            c: 'a',
+            flags: Flags::ITALIC,
+            ..Square::default()
+        };
+
+        let json = serde_json::to_string(&square).unwrap();
+        assert!(!json.contains("extra"));
+        assert!(json.contains(&format!("\"flags\":{}", Flags::ITALIC.bits())));
+
+        let restored: Square = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, square);
+    }
+
+    #[test]
+    fn test_apply_bold_then_dim() {
+        let mut square = Square::default();
+        square.apply(Attr::Bold);
+        square.apply(Attr::Dim);
+        assert!(square.flags.contains(Flags::DIM_BOLD));
+
+        square.apply(Attr::CancelBoldDim);
+        assert!(!square.flags.intersects(Flags::DIM_BOLD));
+    }
+
+    #[test]
+    fn test_weight_transitions() {
+        let mut square = Square::default();
+        assert_eq!(square.weight(), FontWeight::Normal);
+
+        square.apply(Attr::Bold);
+        assert_eq!(square.weight(), FontWeight::Bold);
+
+        // Bold wins for the glyph when dim is set as well.
+        square.apply(Attr::Dim);
+        assert!(square.flags.contains(Flags::DIM_BOLD));
+        assert_eq!(square.weight(), FontWeight::Bold);
+
+        square.apply(Attr::CancelBoldDim);
+        assert_eq!(square.weight(), FontWeight::Normal);
+    }
+
+    #[test]
+    fn test_resolved_colors() {
+        let fg = AnsiColor::Named(NamedColor::Red);
+        let bg = AnsiColor::Named(NamedColor::Blue);
+        let mut square = Square {
+            fg,
+            bg,
+            ..Square::default()
+        };
+        assert_eq!(square.resolved_colors(), (fg, bg));
+
+        square.flags = Flags::INVERSE;
+        assert_eq!(square.resolved_colors(), (bg, fg));
+
+        square.flags = Flags::HIDDEN;
+        assert_eq!(square.resolved_colors(), (bg, bg));
+
+        square.flags = Flags::INVERSE | Flags::HIDDEN;
+        assert_eq!(square.resolved_colors(), (fg, fg));
+
+        // Raw colors are left untouched.
+        assert_eq!((square.fg, square.bg), (fg, bg));
+    }
+
+    #[test]
+    fn test_weight_dim_only() {
+        let mut square = Square::default();
+        square.apply(Attr::Dim);
+        assert_eq!(square.weight(), FontWeight::Dim);
+
+        square.apply(Attr::CancelBold);
+        assert_eq!(square.weight(), FontWeight::Dim);
+
+        square.apply(Attr::CancelBoldDim);
+        assert_eq!(square.weight(), FontWeight::Normal);
+    }
+
+    #[test]
+    fn test_underline_styles_are_exclusive() {
+        let mut square = Square::default();
+        assert_eq!(square.underline_style(), None);
+
+        for style in UnderlineStyle::ALL
+            .into_iter()
+            .chain(UnderlineStyle::ALL.into_iter().rev())
+        {
+            square.set_underline_style(Some(style));
+            assert_eq!(square.underline_style(), Some(style));
+            assert_eq!(square.flags & Flags::ALL_UNDERLINES, style.flag());
+            assert_eq!(
+                (square.flags & Flags::ALL_UNDERLINES).bits().count_ones(),
+                1
+            );
+        }
+
+        square.set_underline_style(None);
+        assert_eq!(square.underline_style(), None);
+        assert!(!square.flags.intersects(Flags::ALL_UNDERLINES));
+    }
+
+    #[test]
+    fn test_set_underline_from_kitty() {
+        let expected = [
+            None,
+            Some(UnderlineStyle::Single),
+            Some(UnderlineStyle::Double),
+            Some(UnderlineStyle::Curly),
+            Some(UnderlineStyle::Dotted),
+            Some(UnderlineStyle::Dashed),
+        ];
+
+        for (sub, style) in expected.into_iter().enumerate() {
+            let mut square = Square::default();
+            square.set_underline_style(Some(UnderlineStyle::Dashed));
+            square.set_underline_from_kitty(sub as u16);
+            assert_eq!(square.underline_style(), style, "4:{sub}");
+        }
+    }
+
+    #[test]
+    fn test_set_underline_from_kitty_ignores_unknown() {
+        let mut square = Square::default();
+        square.set_underline_style(Some(UnderlineStyle::Curly));
+        square.set_underline_from_kitty(6);
+        assert_eq!(square.underline_style(), Some(UnderlineStyle::Curly));
+    }
+
+    #[test]
+    fn test_apply_cancel_italic_keeps_bold() {
+        let mut square = Square::default();
+        square.apply(Attr::Bold);
+        square.apply(Attr::Italic);
+        assert!(square.flags.contains(Flags::BOLD_ITALIC));
+
+        // SGR 23 only clears ITALIC.
+        square.apply(Attr::CancelItalic);
+        assert_eq!(square.flags, Flags::BOLD);
+
+        // SGR 22 clears both BOLD and DIM, leaving ITALIC alone.
+        square.apply(Attr::Italic);
+        square.apply(Attr::Dim);
+        square.apply(Attr::CancelBoldDim);
+        assert_eq!(square.flags, Flags::ITALIC);
+    }
+
+    #[test]
+    fn test_apply_cancel_each_attribute() {
+        let pairs = [
+            (Attr::Reverse, Attr::CancelReverse),
+            (Attr::Hidden, Attr::CancelHidden),
+            (Attr::Strike, Attr::CancelStrike),
+            (Attr::Undercurl, Attr::CancelUnderline),
+            (Attr::BlinkFast, Attr::CancelBlink),
+            (Attr::Overline, Attr::CancelOverline),
+        ];
+
+        for (set, cancel) in pairs {
+            let mut square = Square::default();
+            square.apply(Attr::Bold);
+            square.apply(set);
+            assert_ne!(square.flags, Flags::BOLD, "{set:?}");
+
+            square.apply(cancel);
+            assert_eq!(square.flags, Flags::BOLD, "{cancel:?}");
+        }
+    }
+
+    #[test]
+    fn test_apply_underline_then_double_underline() {
+        let mut square = Square::default();
+        square.apply(Attr::Underline);
+        square.apply(Attr::DoubleUnderline);
+        assert_eq!(
+            square.flags & Flags::ALL_UNDERLINES,
+            Flags::DOUBLE_UNDERLINE
+        );
+
+        square.apply(Attr::CancelUnderline);
+        assert!(!square.flags.intersects(Flags::ALL_UNDERLINES));
+    }
+
+    #[test]
+    fn test_apply_reset() {
+        let mut square = Square::default();
+        square.apply(Attr::Foreground(AnsiColor::Indexed(1)));
+        square.apply(Attr::Background(AnsiColor::Indexed(2)));
+        square.apply(Attr::UnderlineColor(Some(AnsiColor::Indexed(3))));
+        square.apply(Attr::Italic);
+        square.apply(Attr::Undercurl);
+        square.apply(Attr::BlinkSlow);
+
+        square.apply(Attr::Reset);
+        assert_eq!(square, Square::default());
+    }
+
+    #[test]
+    fn test_empty_check_mask_matches_flag_union() {
+        for bits in 0..=Flags::all().bits() {
+            let flags = Flags::from_bits_truncate(bits);
+            let expected = flags.intersects(
+                Flags::INVERSE
+                    | Flags::ALL_UNDERLINES
+                    | Flags::STRIKEOUT
+                    | Flags::OVERLINE
+                    | Flags::WRAPLINE
+                    | Flags::WIDE_CHAR_SPACER
+                    | Flags::LEADING_WIDE_CHAR_SPACER,
+            );
+            assert_eq!(flags.intersects(EMPTY_CHECK_MASK), expected);
+
+            let square = Square {
+                flags,
+                ..Square::default()
+            };
+            assert_eq!(square.is_empty(), !expected);
+        }
+    }
+
+    #[test]
+    fn test_search_match_is_only_drawn() {
+        let mut square = Square::default();
+        let key = square.style_key();
+
+        square.flags.insert(Flags::SEARCH_MATCH);
+        assert!(square.is_empty());
+        assert_ne!(square.style_key(), key);
+    }
+
+    #[test]
+    fn test_reset_keep_protected() {
+        let template = Square {
+            bg: AnsiColor::Named(NamedColor::Blue),
+            ..Square::default()
+        };
+
+        let mut protected = Square {
+            c: 'a',
+            flags: Flags::PROTECTED | Flags::BOLD,
+            ..Square::default()
+        };
+        protected.reset_keep_protected(&template);
+        assert_eq!(protected.c, 'a');
+        assert_eq!(protected.flags, Flags::PROTECTED | Flags::BOLD);
+
+        let mut unprotected = Square {
+            c: 'b',
+            flags: Flags::BOLD,
+            ..Square::default()
+        };
+        unprotected.reset_keep_protected(&template);
+        assert_eq!(unprotected.c, ' ');
+        assert_eq!(unprotected.bg, template.bg);
+        assert!(unprotected.flags.is_empty());
+    }
+
+    #[test]
+    fn test_reset_clears_protection() {
+        let template = Square {
+            flags: Flags::PROTECTED,
+            ..Square::default()
+        };
+        let mut square = Square {
+            c: 'a',
+            flags: Flags::PROTECTED,
+            ..Square::default()
+        };
+        assert!(square.is_protected());
+
+        square.reset(&template);
+        assert!(!square.is_protected());
+        assert_eq!(square, Square::default());
+    }
+
+    #[test]
+    fn test_sgr_reset_keeps_protection() {
+        let mut square = Square {
+            flags: Flags::PROTECTED | Flags::ITALIC,
+            ..Square::default()
+        };
+        square.apply(Attr::Reset);
+        assert_eq!(square.flags, Flags::PROTECTED);
+    }
+
+    #[test]
+    fn test_set_wide_and_spacer() {
+        let mut row = Row::<Square>::new(2);
+        row[Column(1)].push_zerowidth('\u{301}');
+
+        row[Column(0)].set_wide('\u{4f60}');
+        row[Column(1)].into_spacer();
+
+        assert!(row[Column(0)].is_wide());
+        assert!(!row[Column(0)].is_spacer());
+        assert!(row[Column(1)].is_spacer());
+        assert!(!row[Column(1)].is_wide());
+        assert_eq!(row[Column(1)].c, ' ');
+        assert!(row[Column(1)].extra.is_none());
+    }
+
+    #[test]
+    fn test_line_length_works() {
+        let mut row = Row::<Square>::new(10);
+        row[Column(5)].c = 'a';
+
+        assert_eq!(row.line_length(), Column(6));
+    }
+
+    #[test]
+    fn test_line_length_with_trailing_wide_chars() {
+        let mut row = Row::<Square>::new(10);
+        for (index, c) in "日本語".chars().enumerate() {
+            let column = Column(4 + index * 2);
+            row[column].c = c;
+            row[column].flags.insert(Flags::WIDE_CHAR);
+            row[column + 1].flags.insert(Flags::WIDE_CHAR_SPACER);
+        }
+
+        assert_eq!(row.last_occupied(), Some(Column(9)));
+        assert_eq!(row.line_length(), Column(10));
+    }
+
+    #[test]
+    fn test_line_length_with_single_trailing_wide_char() {
+        let mut row = Row::<Square>::new(10);
+        row[Column(2)].c = '字';
+        row[Column(2)].flags.insert(Flags::WIDE_CHAR);
+        row[Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        assert_eq!(row.line_length(), Column(4));
+
+        // A stray spacer without its wide char is not content.
+        let mut row = Row::<Square>::new(10);
+        row[Column(3)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        assert_eq!(row.line_length(), Column(0));
+    }
+
+    #[test]
+    fn test_line_length_with_leading_wide_char_spacer() {
+        // Wide char that did not fit at the end of the line and was wrapped.
+        let mut row = Row::<Square>::new(5);
+        row[Column(0)].c = 'a';
+        row[Column(4)].flags.insert(Flags::LEADING_WIDE_CHAR_SPACER);
+
+        assert_eq!(row.line_length(), Column(5));
+    }
+
+    #[test]
+    fn test_last_occupied_empty_row() {
+        let row = Row::<Square>::new(10);
+        assert_eq!(row.last_occupied(), None);
+        assert_eq!(row.line_length(), Column(0));
+    }
+
+    #[test]
+    fn test_line_length_works_with_wrapline() {
+        let mut row = Row::<Square>::new(10);
+        row[Column(9)].flags.insert(super::Flags::WRAPLINE);
+
+        assert_eq!(row.line_length(), Column(10));
+    }
+
+    #[test]
+    fn style_key_ignores_structural_flags() {
+        let mut square = Square {
+            c: 'a',
+            ..Square::default()
+        };
+        square.flags.insert(Flags::BOLD);
+        square.push_zerowidth('\u{301}');
+
+        let key = square.style_key();
+        assert_eq!(square.clone().style_key(), key);
+
+        square.flags.insert(Flags::WRAPLINE | Flags::PROTECTED);
+        assert_eq!(square.style_key(), key);
+
+        // An emptied extra draws like no extra at all.
+        let mut plain = Square::default();
+        let key = plain.style_key();
+        plain.extra = Some(Arc::new(CellExtra::default()));
+        assert_eq!(plain.style_key(), key);
+    }
+
+    #[test]
+    fn style_key_changes_with_appearance() {
+        let square = Square {
+            c: 'a',
+            ..Square::default()
+        };
+        let key = square.style_key();
+
+        let mut red = square.clone();
+        red.fg = AnsiColor::Named(NamedColor::Red);
+        assert_ne!(red.style_key(), key);
+
+        let mut italic = square.clone();
+        italic.flags.insert(Flags::ITALIC);
+        assert_ne!(italic.style_key(), key);
+
+        let mut accented = square.clone();
+        accented.push_zerowidth('\u{301}');
+        assert_ne!(accented.style_key(), key);
+    }
+
+    #[test]
+    fn zerowidth_push_keeps_hyperlink_shared() {
+        let link = Hyperlink::new(Some("id"), "https://example.com");
+        let mut square = Square::default();
+        square.set_hyperlink(Some(link.clone()));
+        let mut copy = square.clone();
+        assert_eq!(Arc::strong_count(&link.inner), 2);
+
+        copy.push_zerowidth('\u{301}');
+
+        // The extra is split, the hyperlink itself is not copied.
+        assert!(!Arc::ptr_eq(
+            square.extra.as_ref().unwrap(),
+            copy.extra.as_ref().unwrap()
+        ));
+        assert_eq!(Arc::strong_count(&link.inner), 3);
+        assert_eq!(copy.hyperlink(), Some(link));
+        assert_eq!(square.zerowidth(), Some(&[][..]));
+        assert_eq!(copy.zerowidth(), Some(&['\u{301}'][..]));
+    }
+
+    #[test]
+    fn fill_region_with_alignment_template() {
+        let mut rows: Vec<Row<Square>> = (0..5).map(|_| Row::new(5)).collect();
+        rows[2][Column(2)].push_zerowidth('\u{301}');
+        // A wide char crossing the right edge of the area.
+        rows[1][Column(3)].c = '字';
+        rows[1][Column(3)].flags = Flags::WIDE_CHAR;
+        rows[1][Column(4)].flags = Flags::WIDE_CHAR_SPACER;
+
+        let template = Square {
+            c: 'E',
+            ..Square::default()
+        };
+        let rect = Rect {
+            lines: Line(1)..Line(4),
+            columns: Column(1)..Column(4),
+        };
+        fill_region(&mut rows, &rect, &template);
+
+        for (line, column) in [(1, 1), (1, 3), (2, 2), (3, 1), (3, 3)] {
+            assert_eq!(rows[line][Column(column)], template);
+        }
+        assert!(rows[2][Column(2)].extra.is_none());
+        assert_eq!(rows[1][Column(4)], Square::default());
+        for (line, column) in [(0, 1), (4, 3), (2, 0), (2, 4)] {
+            assert_eq!(rows[line][Column(column)].c, ' ');
+        }
+        assert_eq!(rows[3].occ, 4);
+    }
+
+    #[test]
+    fn fill_region_with_wide_template() {
+        let mut rows = vec![Row::<Square>::new(5)];
+        let template = Square {
+            c: '字',
+            flags: Flags::WIDE_CHAR,
+            ..Square::default()
+        };
+        let rect = Rect {
+            lines: Line(0)..Line(1),
+            columns: Column(0)..Column(5),
+        };
+        fill_region(&mut rows, &rect, &template);
+
+        let flags: Vec<_> = rows[0][..].iter().map(|square| square.flags).collect();
+        assert_eq!(
+            flags,
+            [
+                Flags::WIDE_CHAR,
+                Flags::WIDE_CHAR_SPACER,
+                Flags::WIDE_CHAR,
+                Flags::WIDE_CHAR_SPACER,
+                Flags::empty(),
+            ]
+        );
+        assert_eq!(rows[0][Column(4)].c, ' ');
+    }
+
+    fn row_from(text: &str, columns: usize) -> Row<Square> {
+        let mut row = Row::<Square>::new(columns);
+        let mut column = 0;
+        for c in text.chars() {
+            row[Column(column)].c = c;
+            if c.width() == Some(2) {
+                row[Column(column)].flags.insert(Flags::WIDE_CHAR);
+                row[Column(column + 1)]
+                    .flags
+                    .insert(Flags::WIDE_CHAR_SPACER);
+                column += 1;
+            }
+            column += 1;
+        }
+        row
+    }
+
+    fn text(row: &Row<Square>) -> String {
+        row[..]
+            .iter()
+            .filter(|square| !square.flags.contains(Flags::WIDE_CHAR_SPACER))
+            .map(|square| square.c)
+            .collect()
+    }
+
+    #[test]
+    fn insert_chars_into_middle_of_line() {
+        let mut row = row_from("abcdef", 6);
+        let template = Square {
+            bg: AnsiColor::Named(NamedColor::Red),
+            ..Square::default()
+        };
+        insert_chars(&mut row, Column(2)..Column(6), 2, &template);
+
+        assert_eq!(text(&row), "ab  cd");
+        assert_eq!(row[Column(2)].bg, template.bg);
+        assert_eq!(row[Column(3)].bg, template.bg);
+
+        // Inserting more than fits blanks the rest of the range.
+        insert_chars(&mut row, Column(4)..Column(6), 10, &Square::default());
+        assert_eq!(text(&row), "ab    ");
+    }
+
+    #[test]
+    fn insert_chars_splitting_wide_chars() {
+        // The wide char at the cursor is split and the one at the end is pushed out half way.
+        let mut row = row_from("a字b字", 6);
+        insert_chars(&mut row, Column(2)..Column(6), 1, &Square::default());
+
+        assert_eq!(text(&row), "a   b ");
+        assert!(row[..].iter().all(|square| !square
+            .flags
+            .intersects(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER)));
+
+        // Whole wide chars are shifted with their spacer.
+        let mut row = row_from("a字b", 6);
+        insert_chars(&mut row, Column(1)..Column(6), 2, &Square::default());
+        assert_eq!(text(&row), "a  字b");
+        assert!(row[Column(3)].flags.contains(Flags::WIDE_CHAR));
+        assert!(row[Column(4)].flags.contains(Flags::WIDE_CHAR_SPACER));
+    }
+
+    #[test]
+    fn delete_chars_across_wide_char_boundary() {
+        // Deleting the first half of a wide char leaves its spacer orphaned.
+        let mut row = row_from("ab字cd", 6);
+        delete_chars(&mut row, Column(2)..Column(6), 1, &Square::default());
+
+        assert_eq!(text(&row), "ab cd ");
+        assert!(row[..].iter().all(|square| !square
+            .flags
+            .intersects(Flags::WIDE_CHAR | Flags::WIDE_CHAR_SPACER)));
+
+        // Deleting from the spacer clears the first half left behind.
+        let mut row = row_from("ab字cd", 6);
+        delete_chars(&mut row, Column(3)..Column(6), 1, &Square::default());
+        assert_eq!(text(&row), "ab cd ");
+
+        // Whole wide chars are shifted with their spacer.
+        let mut row = row_from("a字b", 6);
+        delete_chars(&mut row, Column(0)..Column(6), 1, &Square::default());
+        assert_eq!(text(&row), "字b   ");
+        assert!(row[Column(0)].flags.contains(Flags::WIDE_CHAR));
+        assert!(row[Column(1)].flags.contains(Flags::WIDE_CHAR_SPACER));
     }
 }
@@ -0,0 +1,308 @@
+// Export of grid rows as text, used to save and copy the terminal content.
+
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::pos::{Column, Pos};
+use crate::crosswords::square::{Flags, LineLength, Square};
+use rio_config::colors::{AnsiColor, NamedColor};
+
+/// Flags which are reproduced through SGR, with their parameter.
+const SGR_FLAGS: [(Flags, &str); 14] = [
+    (Flags::BOLD, "1"),
+    (Flags::DIM, "2"),
+    (Flags::ITALIC, "3"),
+    (Flags::UNDERLINE, "4"),
+    (Flags::DOUBLE_UNDERLINE, "4:2"),
+    (Flags::UNDERCURL, "4:3"),
+    (Flags::DOTTED_UNDERLINE, "4:4"),
+    (Flags::DASHED_UNDERLINE, "4:5"),
+    (Flags::SLOW_BLINK, "5"),
+    (Flags::RAPID_BLINK, "6"),
+    (Flags::INVERSE, "7"),
+    (Flags::HIDDEN, "8"),
+    (Flags::STRIKEOUT, "9"),
+    (Flags::OVERLINE, "53"),
+];
+
+/// Graphic rendition of a square as far as SGR is concerned.
+#[derive(Clone, Copy, PartialEq)]
+struct Pen {
+    fg: AnsiColor,
+    bg: AnsiColor,
+    flags: Flags,
+}
+
+impl Default for Pen {
+    fn default() -> Pen {
+        Pen {
+            fg: AnsiColor::Named(NamedColor::Foreground),
+            bg: AnsiColor::Named(NamedColor::Background),
+            flags: Flags::empty(),
+        }
+    }
+}
+
+impl From<&Square> for Pen {
+    fn from(square: &Square) -> Pen {
+        let mask = SGR_FLAGS
+            .iter()
+            .fold(Flags::empty(), |mask, (flag, _)| mask | *flag);
+
+        Pen {
+            fg: square.fg,
+            bg: square.bg,
+            flags: square.flags & mask,
+        }
+    }
+}
+
+/// Render `rows` as text with SGR sequences, which looks the same when written to a terminal.
+///
+/// Sequences are only emitted when the style changes from the previous square and the
+/// rendition is reset at the end of every line. Wrapped rows are not followed by a newline.
+#[allow(dead_code)]
+pub fn to_ansi(rows: &[Row<Square>]) -> String {
+    let mut text = String::new();
+
+    for row in rows {
+        let mut current = Pen::default();
+
+        for square in &row[..row.line_length()] {
+            let pen = Pen::from(square);
+            if pen != current {
+                push_sgr(&mut text, &current, &pen);
+                current = pen;
+            }
+
+            square.write_text_to(&mut text);
+        }
+
+        if current != Pen::default() {
+            text.push_str("\x1b[0m");
+        }
+
+        if !is_wrapped(row) {
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+/// Render `rows` as plain text, joining lines with a newline.
+///
+/// Trailing blanks are trimmed and wrapped rows are joined with the following one, so copied
+/// text keeps its logical lines. Tabs are expanded to spaces.
+pub fn to_plain_text<'a>(rows: impl IntoIterator<Item = &'a Row<Square>>) -> String {
+    let mut text = String::new();
+    let mut wrapped = false;
+
+    for (index, row) in rows.into_iter().enumerate() {
+        if index > 0 && !wrapped {
+            text.push('\n');
+        }
+
+        for square in &row[..row.line_length()] {
+            // The squares up to the next tab stop are already blank.
+            if square.c == '\t' {
+                text.push(' ');
+            } else {
+                square.write_text_to(&mut text);
+            }
+        }
+
+        wrapped = is_wrapped(row);
+    }
+
+    text
+}
+
+/// Render the rectangle from `top_left` to `bottom_right` of `rows`, one line per row.
+///
+/// Trailing blanks of every row are trimmed. A wide char cut by the right edge is kept
+/// whole, while one cut by the left edge leaves a space.
+#[allow(dead_code)]
+pub fn extract_block(rows: &[Row<Square>], top_left: Pos, bottom_right: Pos) -> String {
+    let lines = top_left.row.0 as usize..=bottom_right.row.0 as usize;
+
+    let mut text = Vec::new();
+    for row in &rows[lines] {
+        let mut line = String::new();
+        let end = std::cmp::min(bottom_right.col.0 + 1, row.len());
+
+        for column in (top_left.col.0..end).map(Column) {
+            let square = &row[column];
+            // Tabs, and wide chars missing their left half, leave a blank.
+            let cut_wide_char =
+                square.flags.contains(Flags::WIDE_CHAR_SPACER) && column == top_left.col;
+            if cut_wide_char || square.c == '\t' {
+                line.push(' ');
+            } else {
+                square.write_text_to(&mut line);
+            }
+        }
+
+        line.truncate(line.trim_end().len());
+        text.push(line);
+    }
+
+    text.join("\n")
+}
+
+/// Whether `row` continues on the next one.
+pub(crate) fn is_wrapped(row: &Row<Square>) -> bool {
+    row[..]
+        .last()
+        .map_or(false, |square| square.flags.contains(Flags::WRAPLINE))
+}
+
+/// Push the SGR sequence moving from the `from` rendition to `to`.
+fn push_sgr(text: &mut String, from: &Pen, to: &Pen) {
+    let mut params = Vec::new();
+
+    // Removing attributes goes through a full reset.
+    let from = if from.flags.difference(to.flags).is_empty() {
+        *from
+    } else {
+        params.push(String::from("0"));
+        Pen::default()
+    };
+
+    for (flag, param) in SGR_FLAGS {
+        if to.flags.contains(flag) && !from.flags.contains(flag) {
+            params.push(String::from(param));
+        }
+    }
+
+    if to.fg != from.fg {
+        params.push(color_param(to.fg, 30));
+    }
+    if to.bg != from.bg {
+        params.push(color_param(to.bg, 40));
+    }
+
+    text.push_str("\x1b[");
+    text.push_str(&params.join(";"));
+    text.push('m');
+}
+
+/// SGR parameter for a foreground (`base` 30) or background (`base` 40) color.
+fn color_param(color: AnsiColor, base: u16) -> String {
+    match color {
+        AnsiColor::Named(named) => {
+            let index = named as u16;
+            let dim = NamedColor::DimBlack as u16..=NamedColor::DimWhite as u16;
+            let param = match index {
+                0..=7 => base + index,
+                8..=15 => base + 60 + index - 8,
+                index if dim.contains(&index) => base + index - *dim.start(),
+                // Foreground, background and the other special colors are the default.
+                _ => base + 9,
+            };
+            param.to_string()
+        }
+        AnsiColor::Indexed(index) => format!("{};5;{index}", base + 8),
+        AnsiColor::Spec(rgb) => format!("{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crosswords::pos::Line;
+    use rio_config::colors::ColorRgb;
+
+    fn row(text: &str) -> Row<Square> {
+        let mut row = Row::<Square>::new(text.chars().count());
+        for (column, c) in text.chars().enumerate() {
+            row[Column(column)].c = c;
+        }
+        row
+    }
+
+    #[test]
+    fn same_style_run_emits_sgr_once() {
+        let mut row = row("abc d");
+        for column in 0..3 {
+            row[Column(column)].fg = AnsiColor::Named(NamedColor::Red);
+            row[Column(column)].flags = Flags::BOLD;
+        }
+
+        assert_eq!(to_ansi(&[row]), "\x1b[1;31mabc\x1b[0m d\n");
+    }
+
+    #[test]
+    fn removed_attribute_resets_rendition() {
+        let mut row = row("abc");
+        row[Column(0)].flags = Flags::BOLD | Flags::ITALIC;
+        row[Column(1)].flags = Flags::ITALIC;
+        row[Column(1)].bg = AnsiColor::Spec(ColorRgb { r: 1, g: 2, b: 3 });
+        row[Column(2)].flags = Flags::ITALIC;
+        row[Column(2)].bg = AnsiColor::Indexed(200);
+
+        assert_eq!(
+            to_ansi(&[row]),
+            "\x1b[1;3ma\x1b[0;3;48;2;1;2;3mb\x1b[48;5;200mc\x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn wrapped_row_and_wide_char() {
+        let mut first = row("ab");
+        first[Column(1)].flags.insert(Flags::WRAPLINE);
+
+        let mut second = row("\u{4f60} ");
+        second[Column(0)].flags.insert(Flags::WIDE_CHAR);
+        second[Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        assert_eq!(to_ansi(&[first, second]), "ab\u{4f60}\n");
+    }
+
+    #[test]
+    fn plain_text_joins_wrapped_rows() {
+        let mut first = row("ab c");
+        first[Column(3)].flags.insert(Flags::WRAPLINE);
+        first[Column(1)].push_zerowidth('\u{301}');
+
+        assert_eq!(
+            to_plain_text(&[first, row("de  "), row("f   ")]),
+            "ab\u{301} cde\nf"
+        );
+    }
+
+    #[test]
+    fn plain_text_trims_trailing_spaces() {
+        let mut wide = row("\u{4f60}  ");
+        wide[Column(0)].flags.insert(Flags::WIDE_CHAR);
+        wide[Column(1)].flags.insert(Flags::WIDE_CHAR_SPACER);
+
+        assert_eq!(to_plain_text(&[row("a b   "), wide]), "a b\n\u{4f60}");
+    }
+
+    #[test]
+    fn block_of_plain_text() {
+        let rows = [row("abcdef"), row("ab"), row("abcd  ")];
+        let top_left = Pos::new(Line(0), Column(1));
+        let bottom_right = Pos::new(Line(2), Column(4));
+
+        assert_eq!(extract_block(&rows, top_left, bottom_right), "bcde\nb\nbcd");
+    }
+
+    #[test]
+    fn block_clipping_wide_chars() {
+        let mut wide = row("a\u{4f60} b\u{597d} ");
+        wide[Column(1)].flags.insert(Flags::WIDE_CHAR);
+        wide[Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        wide[Column(4)].flags.insert(Flags::WIDE_CHAR);
+        wide[Column(5)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        let rows = [row("xyzwvu"), wide];
+
+        // The left half of the first wide char and the right half of the second are cut.
+        let top_left = Pos::new(Line(0), Column(2));
+        let bottom_right = Pos::new(Line(1), Column(4));
+
+        assert_eq!(
+            extract_block(&rows, top_left, bottom_right),
+            "zwv\n b\u{597d}"
+        );
+    }
+}
@@ -0,0 +1,109 @@
+// Display width of the chars written to the grid, cached since every printed char asks for it.
+
+use unicode_width::UnicodeWidthChar;
+
+/// Number of code points remembered by the cache, must be a power of two.
+const CACHE_SIZE: usize = 256;
+
+/// Marks a cached char without width, like a control char.
+const NO_WIDTH: u8 = u8::MAX;
+
+/// Remembers the width of recently printed chars.
+///
+/// Entries are indexed by the low bits of the code point, so a run of neighbouring chars like
+/// CJK text fills distinct slots, and a collision only costs a new lookup.
+#[derive(Debug, Clone)]
+pub struct WidthCache {
+    entries: Box<[(char, u8); CACHE_SIZE]>,
+}
+
+impl Default for WidthCache {
+    fn default() -> Self {
+        // NUL has a zero width, so the initial entries are already correct.
+        WidthCache {
+            entries: Box::new([('\0', 0); CACHE_SIZE]),
+        }
+    }
+}
+
+impl WidthCache {
+    /// Width of `c` in cells, `None` for control chars, same as [`UnicodeWidthChar::width`].
+    #[inline]
+    pub fn char_width(&mut self, c: char) -> Option<u8> {
+        // Printable ASCII is always one cell wide.
+        if (' '..='~').contains(&c) {
+            return Some(1);
+        }
+
+        let entry = &mut self.entries[c as usize & (CACHE_SIZE - 1)];
+        if entry.0 != c {
+            *entry = (c, c.width().map_or(NO_WIDTH, |width| width as u8));
+        }
+
+        match entry.1 {
+            NO_WIDTH => None,
+            width => Some(width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    const SAMPLE: &str =
+        "\0\x07\x1b\x7f\u{85}\u{9f} az~\u{a0}é\u{301}\u{200b}\u{ad}ñ你好世界\
+        한국어カタカナ\u{3000}\u{ff21}🦀👍🏽\u{fe0f}\u{1f1e7}\u{10ffff}";
+
+    #[test]
+    fn char_width_matches_unicode_width() {
+        let mut cache = WidthCache::default();
+
+        // Twice, so the second pass is answered from the cache.
+        for _ in 0..2 {
+            for c in SAMPLE.chars().chain('\0'..='\u{3000}') {
+                let expected = c.width().map(|width| width as u8);
+                assert_eq!(cache.char_width(c), expected, "{:?}", c);
+            }
+        }
+    }
+
+    #[test]
+    fn char_width_handles_colliding_chars() {
+        let mut cache = WidthCache::default();
+
+        // Both land in the same slot.
+        let (wide, control) = ('\u{4e80}', '\u{80}');
+        assert_eq!(wide as usize % CACHE_SIZE, control as usize % CACHE_SIZE);
+
+        for _ in 0..2 {
+            assert_eq!(cache.char_width(wide), Some(2));
+            assert_eq!(cache.char_width(control), None);
+            assert_eq!(cache.char_width('\0'), Some(0));
+        }
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture bench_char_width`.
+    #[test]
+    #[ignore]
+    fn bench_char_width() {
+        let text = "let value = 42; // 値を返す 你好，世界 🦀\n".repeat(100_000);
+
+        let start = Instant::now();
+        let uncached: usize = text.chars().filter_map(|c| c.width()).sum();
+        let uncached_time = start.elapsed();
+
+        let mut cache = WidthCache::default();
+        let start = Instant::now();
+        let cached: usize = text
+            .chars()
+            .filter_map(|c| cache.char_width(c))
+            .map(usize::from)
+            .sum();
+        let cached_time = start.elapsed();
+
+        assert_eq!(cached, uncached);
+        println!("unicode-width: {uncached_time:?}, cached: {cached_time:?}");
+    }
+}
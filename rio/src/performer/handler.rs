@@ -1,7 +1,10 @@
 use crate::ansi::CursorShape;
 use crate::ansi::{mode::Mode, KeyboardModes, KeyboardModesApplyBehavior};
+use crate::crosswords::grid::row::LineAttr;
 use crate::crosswords::pos::{CharsetIndex, Column, Line, StandardCharset};
 use crate::crosswords::square::Hyperlink;
+use crate::crosswords::PromptKind;
+use crate::performer::x11_colors;
 use cursor_icon::CursorIcon;
 use log::{debug, warn};
 use rio_config::colors::{AnsiColor, ColorRgb, NamedColor};
@@ -11,7 +14,7 @@ use std::time::Instant;
 use crate::crosswords::attr::Attr;
 
 use crate::ansi::control::C0;
-use crate::ansi::{ClearMode, LineClearMode, TabulationClearMode};
+use crate::ansi::{ClearMode, LineClearMode, Rectangle, TabulationClearMode};
 use std::fmt::Write;
 
 // https://vt100.net/emu/dec_ansi_parser
@@ -37,7 +40,7 @@ fn xparse_color(color: &[u8]) -> Option<ColorRgb> {
     } else if color.len() >= 4 && &color[..4] == b"rgb:" {
         parse_rgb_color(&color[4..])
     } else {
-        None
+        x11_colors::lookup(color)
     }
 }
 
@@ -72,6 +75,10 @@ fn parse_rgb_color(color: &[u8]) -> Option<ColorRgb> {
 
 /// Parse colors in `#r(rrr)g(ggg)b(bbb)` format.
 fn parse_legacy_color(color: &[u8]) -> Option<ColorRgb> {
+    if !matches!(color.len(), 3 | 6 | 9 | 12) {
+        return None;
+    }
+
     let item_len = color.len() / 3;
 
     // Truncate/Fill to two byte precision.
@@ -247,6 +254,27 @@ pub trait Handler {
     /// Clear tab stops.
     fn clear_tabs(&mut self, _mode: TabulationClearMode) {}
 
+    /// Set whether written characters are protected from selective erase (DECSCA).
+    fn set_protected(&mut self, _protected: bool) {}
+
+    /// Clear unprotected characters in the current line (DECSEL).
+    fn selective_clear_line(&mut self, _mode: LineClearMode) {}
+
+    /// Clear unprotected characters in the screen (DECSED).
+    fn selective_clear_screen(&mut self, _mode: ClearMode) {}
+
+    /// Fill a rectangular area with a character (DECFRA).
+    fn fill_rect(&mut self, _c: char, _rect: Rectangle) {}
+
+    /// Erase a rectangular area (DECERA).
+    fn erase_rect(&mut self, _rect: Rectangle) {}
+
+    /// Erase the unprotected characters of a rectangular area (DECSERA).
+    fn selective_erase_rect(&mut self, _rect: Rectangle) {}
+
+    /// Copy a rectangular area so its top left corner lands on `line` and `col` (DECCRA).
+    fn copy_rect(&mut self, _rect: Rectangle, _line: Line, _col: Column) {}
+
     /// Reset terminal state.
     fn reset_state(&mut self) {}
 
@@ -266,9 +294,20 @@ pub trait Handler {
     /// Unset mode.
     fn unset_mode(&mut self, _: Mode) {}
 
+    /// DECRQM - Report whether a mode is set.
+    fn report_mode(&mut self, _intermediate: Option<&u8>, _mode: u16) {}
+
     /// DECSTBM - Set the terminal scrolling region.
     fn set_scrolling_region(&mut self, _top: usize, _bottom: Option<usize>) {}
 
+    /// DECSLRM - Set the left and right margins.
+    ///
+    /// `CSI s` is shared with saving the cursor position, which is what it does unless
+    /// left and right margin mode (DECLRMM) is enabled.
+    fn set_left_right_margins(&mut self, _left: usize, _right: Option<usize>) {
+        self.save_cursor_position();
+    }
+
     /// DECKPAM - Set keypad to applications mode (ESCape instead of digits).
     fn set_keypad_application_mode(&mut self) {}
 
@@ -305,6 +344,9 @@ pub trait Handler {
     /// Run the decaln routine.
     fn decaln(&mut self) {}
 
+    /// Set the double-width or double-height attribute of the cursor line.
+    fn set_line_attr(&mut self, _attr: LineAttr) {}
+
     /// Push a title onto the stack.
     fn push_title(&mut self) {}
 
@@ -320,6 +362,9 @@ pub trait Handler {
     /// Set hyperlink.
     fn set_hyperlink(&mut self, _: Option<Hyperlink>) {}
 
+    /// Mark the cursor line with a shell integration prompt zone (OSC 133).
+    fn set_prompt_mark(&mut self, _kind: PromptKind, _exit_code: Option<i32>) {}
+
     /// Set mouse cursor icon.
     fn set_mouse_cursor_icon(&mut self, _: CursorIcon) {}
 
@@ -621,6 +666,27 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 self.handler.set_hyperlink(Some(Hyperlink::new(id, uri)));
             }
 
+            // Shell integration (FinalTerm) semantic prompts.
+            b"133" if params.len() > 1 => {
+                let kind = match params[1] {
+                    b"A" => PromptKind::PromptStart,
+                    b"B" => PromptKind::CommandStart,
+                    b"C" => PromptKind::OutputStart,
+                    b"D" => PromptKind::CommandEnd,
+                    _ => return unhandled(params),
+                };
+
+                let exit_code = match kind {
+                    PromptKind::CommandEnd => params
+                        .get(2)
+                        .and_then(|code| std::str::from_utf8(code).ok())
+                        .and_then(|code| code.parse().ok()),
+                    _ => None,
+                };
+
+                self.handler.set_prompt_mark(kind, exit_code);
+            }
+
             b"10" | b"11" | b"12" => {
                 if params.len() >= 2 {
                     if let Some(mut dynamic_code) = parse_number(params[0]) {
@@ -747,7 +813,7 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             }};
         }
 
-        if should_ignore || intermediates.len() > 1 {
+        if should_ignore || intermediates.len() > 2 {
             return;
         }
 
@@ -832,6 +898,19 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
 
                 handler.clear_screen(mode);
             }
+            ('J', [b'?']) => {
+                let mode = match next_param_or(0) {
+                    0 => ClearMode::Below,
+                    1 => ClearMode::Above,
+                    2 => ClearMode::All,
+                    _ => {
+                        csi_unhandled!();
+                        return;
+                    }
+                };
+
+                handler.selective_clear_screen(mode);
+            }
             ('K', []) => {
                 let mode = match next_param_or(0) {
                     0 => LineClearMode::Right,
@@ -845,6 +924,19 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
 
                 handler.clear_line(mode);
             }
+            ('K', [b'?']) => {
+                let mode = match next_param_or(0) {
+                    0 => LineClearMode::Right,
+                    1 => LineClearMode::Left,
+                    2 => LineClearMode::All,
+                    _ => {
+                        csi_unhandled!();
+                        return;
+                    }
+                };
+
+                handler.selective_clear_line(mode);
+            }
             ('L', []) => handler.insert_blank_lines(next_param_or(1) as usize),
             ('l', intermediates) => {
                 for param in params_iter.map(|param| param[0]) {
@@ -869,6 +961,16 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
             }
             ('n', []) => handler.device_status(next_param_or(0) as usize),
             ('P', []) => handler.delete_chars(next_param_or(1) as usize),
+            ('p', [b'$']) => handler.report_mode(None, next_param_or(0)),
+            ('p', [b'?', b'$']) => handler.report_mode(Some(&b'?'), next_param_or(0)),
+            ('q', [b'"']) => {
+                // DECSCA (CSI Ps " q) -- Select Character Protection Attribute.
+                match next_param_or(0) {
+                    0 | 2 => handler.set_protected(false),
+                    1 => handler.set_protected(true),
+                    _ => csi_unhandled!(),
+                }
+            }
             ('q', [b' ']) => {
                 // DECSCUSR (CSI Ps SP q) -- Set Cursor Style.
                 let cursor_style_id = next_param_or(0);
@@ -895,7 +997,15 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 handler.set_scrolling_region(top, bottom);
             }
             ('S', []) => handler.scroll_up(next_param_or(1) as usize),
-            ('s', []) => handler.save_cursor_position(),
+            ('s', []) => {
+                let left = next_param_or(1) as usize;
+                let right = params_iter
+                    .next()
+                    .map(|param| param[0] as usize)
+                    .filter(|&param| param != 0);
+
+                handler.set_left_right_margins(left, right);
+            }
             ('T', []) => handler.scroll_down(next_param_or(1) as usize),
             ('t', []) => match next_param_or(1) as usize {
                 14 => handler.text_area_size_pixels(),
@@ -924,8 +1034,35 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 handler.pop_keyboard_modes(next_param_or(1));
             }
             ('u', []) => handler.restore_cursor_position(),
+            ('v', [b'$']) => {
+                // DECCRA (CSI Pts ; Pls ; Pbs ; Prs ; Pps ; Ptd ; Pld ; Ppd $ v).
+                // There is a single page, so both page numbers are ignored.
+                let rect = rectangle_from_params(&mut next_param_or);
+                let _page = next_param_or(1);
+                let line = next_param_or(1) as i32;
+                let col = next_param_or(1) as usize;
+                handler.copy_rect(rect, Line(line - 1), Column(col - 1));
+            }
             ('X', []) => handler.erase_chars(Column(next_param_or(1) as usize)),
+            ('x', [b'$']) => {
+                // DECFRA (CSI Pch ; Pt ; Pl ; Pb ; Pr $ x), only printable characters.
+                let c = next_param_or(0) as u32;
+                match char::from_u32(c) {
+                    Some(c) if matches!(c as u32, 32..=126 | 160..=255) => {
+                        handler.fill_rect(c, rectangle_from_params(&mut next_param_or))
+                    }
+                    _ => csi_unhandled!(),
+                }
+            }
             ('Z', []) => handler.move_backward_tabs(next_param_or(1)),
+            ('z', [b'$']) => {
+                // DECERA (CSI Pt ; Pl ; Pb ; Pr $ z).
+                handler.erase_rect(rectangle_from_params(&mut next_param_or))
+            }
+            ('{', [b'$']) => {
+                // DECSERA (CSI Pt ; Pl ; Pb ; Pr $ {).
+                handler.selective_erase_rect(rectangle_from_params(&mut next_param_or))
+            }
             _ => csi_unhandled!(),
         };
     }
@@ -976,6 +1113,10 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
                 )
             }
             (b'7', []) => self.handler.save_cursor_position(),
+            (b'3', [b'#']) => self.handler.set_line_attr(LineAttr::DoubleHeightTop),
+            (b'4', [b'#']) => self.handler.set_line_attr(LineAttr::DoubleHeightBottom),
+            (b'5', [b'#']) => self.handler.set_line_attr(LineAttr::Normal),
+            (b'6', [b'#']) => self.handler.set_line_attr(LineAttr::DoubleWidth),
             (b'8', [b'#']) => self.handler.decaln(),
             (b'8', []) => self.handler.restore_cursor_position(),
             (b'=', []) => self.handler.set_keypad_application_mode(),
@@ -987,6 +1128,18 @@ impl<U: Handler> copa::Perform for Performer<'_, U> {
     }
 }
 
+/// Read the top, left, bottom and right parameters of a rectangular area, zero meaning omitted.
+#[inline]
+fn rectangle_from_params(mut next_param_or: impl FnMut(u16) -> u16) -> Rectangle {
+    let mut next = || Some(next_param_or(0) as usize).filter(|&param| param != 0);
+    Rectangle {
+        top: next(),
+        left: next(),
+        bottom: next(),
+        right: next(),
+    }
+}
+
 #[inline]
 fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
     let mut attrs = Vec::with_capacity(params.size_hint().0);
@@ -1002,7 +1155,9 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
             [4, 3] => Some(Attr::Undercurl),
             [4, 4] => Some(Attr::DottedUnderline),
             [4, 5] => Some(Attr::DashedUnderline),
-            [4, ..] => Some(Attr::Underline),
+            [4] | [4, 1, ..] => Some(Attr::Underline),
+            // Unknown underline styles are ignored.
+            [4, ..] => None,
             [5] => Some(Attr::BlinkSlow),
             [6] => Some(Attr::BlinkFast),
             [7] => Some(Attr::Reverse),
@@ -1044,6 +1199,8 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
             }
             [48, params @ ..] => handle_colon_rgb(params).map(Attr::Background),
             [49] => Some(Attr::Background(AnsiColor::Named(NamedColor::Background))),
+            [53] => Some(Attr::Overline),
+            [55] => Some(Attr::CancelOverline),
             [58] => {
                 let mut iter = params.map(|param| param[0]);
                 parse_sgr_color(&mut iter).map(|color| Attr::UnderlineColor(Some(color)))
@@ -1075,3 +1232,44 @@ fn attrs_from_sgr_parameters(params: &mut ParamsIter<'_>) -> Vec<Option<Attr>> {
 
     attrs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u8, g: u8, b: u8) -> Option<ColorRgb> {
+        Some(ColorRgb { r, g, b })
+    }
+
+    #[test]
+    fn parse_rgb_color_specs() {
+        assert_eq!(xparse_color(b"rgb:f/8/0"), rgb(255, 136, 0));
+        assert_eq!(xparse_color(b"rgb:ff/80/00"), rgb(255, 128, 0));
+        assert_eq!(xparse_color(b"rgb:fff/800/000"), rgb(255, 127, 0));
+        assert_eq!(xparse_color(b"rgb:ffff/8080/0000"), rgb(255, 128, 0));
+
+        assert_eq!(xparse_color(b"rgb:ff/80"), None);
+        assert_eq!(xparse_color(b"rgb:fffff/0/0"), None);
+        assert_eq!(xparse_color(b"rgb:gg/00/00"), None);
+    }
+
+    #[test]
+    fn parse_legacy_color_specs() {
+        assert_eq!(xparse_color(b"#f80"), rgb(240, 128, 0));
+        assert_eq!(xparse_color(b"#ff8000"), rgb(255, 128, 0));
+        assert_eq!(xparse_color(b"#fff888000"), rgb(255, 136, 0));
+        assert_eq!(xparse_color(b"#ffff80800000"), rgb(255, 128, 0));
+
+        assert_eq!(xparse_color(b"#"), None);
+        assert_eq!(xparse_color(b"#ff80"), None);
+        assert_eq!(xparse_color(b"#gg8000"), None);
+    }
+
+    #[test]
+    fn parse_named_color_specs() {
+        assert_eq!(xparse_color(b"red"), rgb(255, 0, 0));
+        assert_eq!(xparse_color(b"Dark Orange"), rgb(255, 140, 0));
+        assert_eq!(xparse_color(b"grey100"), rgb(255, 255, 255));
+        assert_eq!(xparse_color(b"?"), None);
+    }
+}
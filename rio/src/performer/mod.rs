@@ -1,4 +1,5 @@
 pub mod handler;
+mod x11_colors;
 
 use crate::crosswords::Crosswords;
 use crate::event::sync::FairMutex;
@@ -169,6 +169,8 @@ impl From<String> for Action {
             "paste" => Some(Action::Paste),
             "quit" => Some(Action::Quit),
             "copy" => Some(Action::Copy),
+            "copyall" => Some(Action::CopyAll),
+            "togglevimode" => Some(Action::ToggleViMode),
             "resetfontsize" => Some(Action::ResetFontSize),
             "increasefontsize" => Some(Action::IncreaseFontSize),
             "decreasefontsize" => Some(Action::DecreaseFontSize),
@@ -195,6 +197,8 @@ impl From<String> for Action {
             "scrollhalfpagedown" => Some(Action::ScrollHalfPageDown),
             "scrolltotop" => Some(Action::ScrollToTop),
             "scrolltobottom" => Some(Action::ScrollToBottom),
+            "clearhistory" => Some(Action::ClearHistory),
+            "logmemorystats" => Some(Action::LogMemoryStats),
             "none" => Some(Action::None),
             _ => None,
         };
@@ -260,7 +264,8 @@ pub enum Action {
     ViMotion(ViMotion),
 
     // Perform vi mode action.
-    // Vi(ViAction),
+    Vi(ViAction),
+
     /// Perform mouse binding exclusive action.
     Mouse(MouseAction),
 
@@ -315,6 +320,12 @@ pub enum Action {
     /// Scroll all the way to the bottom.
     ScrollToBottom,
 
+    /// Log memory accounting of the current grid.
+    LogMemoryStats,
+
+    /// Copy the whole scrollback and screen as text.
+    CopyAll,
+
     /// Clear the display buffer(s) to remove history.
     #[allow(dead_code)]
     ClearHistory,
@@ -417,6 +428,45 @@ impl From<ViMotion> for Action {
     }
 }
 
+/// Vi mode specific actions.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViAction {
+    /// Toggle normal vi selection.
+    ToggleNormalSelection,
+    /// Toggle line vi selection.
+    ToggleLineSelection,
+    /// Toggle block vi selection.
+    ToggleBlockSelection,
+    /// Toggle semantic vi selection.
+    ToggleSemanticSelection,
+    /// Jump to the next typed character in the line.
+    InlineSearchForward,
+    /// Jump to the previous typed character in the line.
+    InlineSearchBackward,
+    /// Jump right before the next typed character in the line.
+    InlineSearchForwardShort,
+    /// Jump right after the previous typed character in the line.
+    InlineSearchBackwardShort,
+    /// Repeat the last inline search.
+    InlineSearchNext,
+    /// Repeat the last inline search in the opposite direction.
+    InlineSearchPrevious,
+    /// Type a regex to search forward from the vi cursor.
+    SearchForward,
+    /// Type a regex to search backward from the vi cursor.
+    SearchBackward,
+    /// Jump to the next match of the last search.
+    SearchNext,
+    /// Jump to the next match of the last search in the opposite direction.
+    SearchPrevious,
+}
+
+impl From<ViAction> for Action {
+    fn from(action: ViAction) -> Self {
+        Self::Vi(action)
+    }
+}
+
 macro_rules! bindings {
     (
         $ty:ident;
@@ -628,6 +678,34 @@ pub fn default_key_bindings(
             ViMotion::WordRightEnd;
         "5",   ModifiersState::SHIFT, +BindingMode::VI;
             ViMotion::Bracket;
+        "v",                             +BindingMode::VI;
+            ViAction::ToggleNormalSelection;
+        "v",      ModifiersState::SHIFT, +BindingMode::VI;
+            ViAction::ToggleLineSelection;
+        "v",      ModifiersState::CONTROL, +BindingMode::VI;
+            ViAction::ToggleBlockSelection;
+        "v",      ModifiersState::ALT,   +BindingMode::VI;
+            ViAction::ToggleSemanticSelection;
+        "f",                             +BindingMode::VI;
+            ViAction::InlineSearchForward;
+        "f",      ModifiersState::SHIFT, +BindingMode::VI;
+            ViAction::InlineSearchBackward;
+        "t",                             +BindingMode::VI;
+            ViAction::InlineSearchForwardShort;
+        "t",      ModifiersState::SHIFT, +BindingMode::VI;
+            ViAction::InlineSearchBackwardShort;
+        ";",                             +BindingMode::VI;
+            ViAction::InlineSearchNext;
+        ",",                             +BindingMode::VI;
+            ViAction::InlineSearchPrevious;
+        "/",                             +BindingMode::VI;
+            ViAction::SearchForward;
+        "/",      ModifiersState::SHIFT, +BindingMode::VI;
+            ViAction::SearchBackward;
+        "n",                             +BindingMode::VI;
+            ViAction::SearchNext;
+        "n",      ModifiersState::SHIFT, +BindingMode::VI;
+            ViAction::SearchPrevious;
     );
 
     //   Code     Modifiers
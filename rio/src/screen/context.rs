@@ -6,7 +6,7 @@ use crate::router::assistant::AssistantReport::{FontsNotFound, InitializationErr
 use crate::router::assistant::{AssistantReportLevel, ErrorReport};
 use crate::screen::Crosswords;
 use crate::screen::Messenger;
-use rio_config::Shell;
+use rio_config::{ResetMode, Shell};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::error::Error;
@@ -41,6 +41,12 @@ pub struct ContextManagerConfig {
     pub is_collapsed: bool,
     pub is_native: bool,
     pub should_update_titles: bool,
+    pub reset_mode: ResetMode,
+    pub history_size: usize,
+    pub word_separators: String,
+    pub expand_tabs_on_copy: bool,
+    pub osc52_max_size: usize,
+    pub osc52_read: bool,
 }
 
 pub struct ContextManagerTitles {
@@ -116,6 +122,12 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             Crosswords::new(cols_rows.0, cols_rows.1, event_proxy, window_id);
         terminal.cursor_shape = cursor_state.0.content;
         terminal.blinking_cursor = cursor_state.1;
+        terminal.reset_mode = config.reset_mode;
+        terminal.update_history(config.history_size);
+        terminal.set_semantic_escape_chars(&config.word_separators);
+        terminal.expand_tabs_on_copy = config.expand_tabs_on_copy;
+        terminal.osc52_max_size = config.osc52_max_size;
+        terminal.osc52_read = config.osc52_read;
         let terminal: Arc<FairMutex<Crosswords<T>>> = Arc::new(FairMutex::new(terminal));
 
         let pty;
@@ -272,6 +284,12 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             is_native: false,
             should_update_titles: false,
             use_current_path: false,
+            reset_mode: ResetMode::default(),
+            history_size: 0,
+            word_separators: String::new(),
+            expand_tabs_on_copy: false,
+            osc52_max_size: 0,
+            osc52_read: false,
         };
         let initial_context = ContextManager::create_context(
             (100, 100),
@@ -301,6 +319,12 @@ impl<T: EventListener + Clone + std::marker::Send + 'static> ContextManager<T> {
             .send_event(RioEvent::PrepareRender(800), self.window_id);
     }
 
+    #[inline]
+    pub fn schedule_text_blinking_render(&self) {
+        self.event_proxy
+            .send_event(RioEvent::PrepareRender(250), self.window_id);
+    }
+
     #[inline]
     pub fn report_error_fonts_not_found(&self, fonts_not_found: Vec<SugarloafFont>) {
         if !fonts_not_found.is_empty() {
@@ -20,6 +20,7 @@ use crate::screen::bindings::MouseBinding;
 use core::fmt::Debug;
 use std::borrow::Cow;
 use std::ffi::OsStr;
+use std::sync::Arc;
 use winit::event::KeyEvent;
 use winit::event::Modifiers;
 use winit::event::MouseButton;
@@ -30,7 +31,8 @@ use crate::crosswords::grid::Dimensions;
 use crate::crosswords::pos::{Column, Line};
 use crate::crosswords::{
     grid::Scroll,
-    pos::{Pos, Side},
+    pos::{Direction, Pos, Side},
+    search::RegexSearch,
     Crosswords, Mode, MIN_COLUMNS, MIN_LINES,
 };
 use crate::event::{ClickState, EventProxy};
@@ -39,13 +41,13 @@ use crate::router;
 #[cfg(target_os = "macos")]
 use crate::screen::constants::{DEADZONE_END_Y, DEADZONE_START_X, DEADZONE_START_Y};
 use crate::screen::{
-    bindings::{Action as Act, BindingKey, BindingMode, FontSizeAction},
+    bindings::{Action as Act, BindingKey, BindingMode, FontSizeAction, ViAction},
     context::ContextManager,
     mouse::Mouse,
 };
 use crate::selection::{Selection, SelectionType};
 use messenger::Messenger;
-use rio_config::colors::{term::List, ColorWGPU};
+use rio_config::colors::{term::TermColors, ColorWGPU};
 use state::State;
 use std::cmp::max;
 use std::cmp::min;
@@ -81,8 +83,51 @@ impl Dimensions for SugarloafLayout {
     }
 }
 
+/// Vi mode inline search (`f`, `F`, `t` and `T`), kept to be repeated.
+struct InlineSearchState {
+    /// Whether the next typed character is the one to search for.
+    char_pending: bool,
+    character: Option<char>,
+    direction: Direction,
+    stop_short: bool,
+}
+
+impl Default for InlineSearchState {
+    fn default() -> Self {
+        Self {
+            char_pending: false,
+            character: None,
+            direction: Direction::Right,
+            stop_short: false,
+        }
+    }
+}
+
+/// Vi mode regex search (`/` and `?`), kept to be repeated with `n` and `N`.
+struct SearchState {
+    /// Query being typed, until it is confirmed with enter or cancelled with escape.
+    input: Option<String>,
+    /// Vi cursor position when the search started, where the cursor goes back on cancel.
+    origin: Pos,
+    regex: Option<RegexSearch>,
+    direction: Direction,
+}
+
+impl Default for SearchState {
+    fn default() -> Self {
+        Self {
+            input: None,
+            origin: Pos::default(),
+            regex: None,
+            direction: Direction::Right,
+        }
+    }
+}
+
 pub struct Screen {
     bindings: bindings::KeyBindings,
+    inline_search: InlineSearchState,
+    search: SearchState,
     mouse_bindings: Vec<MouseBinding>,
     clipboard: Clipboard,
     pub modifiers: Modifiers,
@@ -177,6 +222,12 @@ impl Screen {
             // does not make sense fetch for foreground process names
             should_update_titles: !(is_collapsed
                 && config.navigation.color_automation.is_empty()),
+            reset_mode: config.reset_mode,
+            history_size: config.history_size,
+            word_separators: config.word_separators.to_owned(),
+            expand_tabs_on_copy: config.expand_tabs_on_copy,
+            osc52_max_size: config.osc52_max_size,
+            osc52_read: config.osc52_read,
         };
         let context_manager = context::ContextManager::start(
             (sugarloaf.layout.width_u32, sugarloaf.layout.height_u32),
@@ -197,6 +248,8 @@ impl Screen {
             mouse: Mouse::default(),
             state,
             bindings,
+            inline_search: InlineSearchState::default(),
+            search: SearchState::default(),
             clipboard,
         })
     }
@@ -315,6 +368,12 @@ impl Screen {
             let mut terminal = context.terminal.lock();
             terminal.cursor_shape = self.state.get_cursor_state_from_ref().content;
             terminal.blinking_cursor = config.blinking_cursor;
+            terminal.reset_mode = config.reset_mode;
+            terminal.update_history(config.history_size);
+            terminal.set_semantic_escape_chars(&config.word_separators);
+            terminal.expand_tabs_on_copy = config.expand_tabs_on_copy;
+            terminal.osc52_max_size = config.osc52_max_size;
+            terminal.osc52_read = config.osc52_read;
         }
 
         let width = self.sugarloaf.layout.width_u32 as u16;
@@ -399,6 +458,11 @@ impl Screen {
         }
     }
 
+    #[inline]
+    pub fn clipboard_store(&mut self, clipboard_type: ClipboardType, text: String) {
+        self.clipboard.set(clipboard_type, text);
+    }
+
     #[inline]
     pub fn clipboard_get(&mut self, clipboard_type: ClipboardType) -> String {
         self.clipboard.get(clipboard_type)
@@ -437,11 +501,11 @@ impl Screen {
 
     #[inline]
     #[allow(unused)]
-    pub fn colors(&mut self) -> List {
+    pub fn colors(&mut self) -> TermColors {
         let terminal = self.ctx().current().terminal.lock();
-        let mode = terminal.colors();
+        let colors = *terminal.colors();
         drop(terminal);
-        mode
+        colors
     }
 
     #[inline]
@@ -474,6 +538,22 @@ impl Screen {
             return;
         }
 
+        // Keys edit the search query until it is confirmed or cancelled.
+        if mode.contains(Mode::VI) && self.search.input.is_some() {
+            self.search_input(key);
+            return;
+        }
+
+        // The key after `f`, `F`, `t` or `T` is the character to search for.
+        if mode.contains(Mode::VI) && self.inline_search.char_pending {
+            if let Some(c) = key.text.as_ref().and_then(|text| text.chars().next()) {
+                self.inline_search.char_pending = false;
+                self.inline_search.character = Some(c);
+                self.vi_inline_search(self.inline_search.direction);
+            }
+            return;
+        }
+
         let binding_mode = BindingMode::new(&mode);
         let mut ignore_chars = None;
 
@@ -522,11 +602,45 @@ impl Screen {
                     Act::Copy => {
                         self.copy_selection(ClipboardType::Clipboard);
                     }
+                    Act::CopyAll => {
+                        let terminal = self.ctx().current().terminal.lock();
+                        let text = terminal.to_text(None);
+                        drop(terminal);
+                        self.clipboard.set(ClipboardType::Clipboard, text);
+                    }
                     Act::ViMotion(motion) => {
                         let mut terminal =
                             self.context_manager.current_mut().terminal.lock();
                         terminal.vi_motion(*motion);
+                        let selection = terminal
+                            .selection
+                            .as_ref()
+                            .and_then(|selection| selection.to_range(&terminal));
                         drop(terminal);
+                        self.state.set_selection(selection);
+                    }
+                    Act::Vi(action) => {
+                        let action = *action;
+                        self.vi_action(action);
+                    }
+                    Act::ToggleViMode => {
+                        let mut terminal =
+                            self.context_manager.current_mut().terminal.lock();
+                        terminal.toggle_vi_mode();
+                        let vi_mode = terminal.mode().contains(Mode::VI);
+                        if !vi_mode {
+                            terminal.clear_search_matches();
+                        }
+                        drop(terminal);
+
+                        self.inline_search.char_pending = false;
+                        self.search = SearchState::default();
+                        if !vi_mode {
+                            self.clear_selection();
+                        }
+                    }
+                    Act::ClearSelection => {
+                        self.clear_selection();
                     }
                     Act::ConfigEditor => {
                         self.context_manager.switch_to_settings();
@@ -670,6 +784,12 @@ impl Screen {
                         drop(terminal);
                         self.render();
                     }
+                    Act::LogMemoryStats => {
+                        let terminal = self.context_manager.current().terminal.lock();
+                        let stats = terminal.memory_stats();
+                        drop(terminal);
+                        log::info!("Grid memory stats: {stats:?}");
+                    }
                     Act::ToggleFullscreen => self.context_manager.toggle_full_screen(),
                     Act::Minimize => {
                         self.context_manager.minimize();
@@ -1114,6 +1234,173 @@ impl Screen {
         self.state.set_selection(None);
     }
 
+    /// Start, change or stop a selection of type `ty` from the vi mode cursor.
+    fn toggle_vi_selection(&mut self, ty: SelectionType) {
+        let mut terminal = self.context_manager.current().terminal.lock();
+        let mut selection = match terminal.selection.take() {
+            Some(selection) if selection.ty == ty && !selection.is_empty() => {
+                drop(terminal);
+                self.clear_selection();
+                return;
+            }
+            Some(mut selection) if !selection.is_empty() => {
+                selection.ty = ty;
+                selection
+            }
+            _ => Selection::new(ty, terminal.vi_mode_cursor.pos, Side::Left),
+        };
+
+        // Make sure initial selection is not empty.
+        selection.include_all();
+
+        self.state.set_selection(selection.to_range(&terminal));
+        terminal.selection = Some(selection);
+        drop(terminal);
+    }
+
+    fn vi_action(&mut self, action: ViAction) {
+        match action {
+            ViAction::ToggleNormalSelection => {
+                self.toggle_vi_selection(SelectionType::Simple)
+            }
+            ViAction::ToggleLineSelection => {
+                self.toggle_vi_selection(SelectionType::Lines)
+            }
+            ViAction::ToggleBlockSelection => {
+                self.toggle_vi_selection(SelectionType::Block)
+            }
+            ViAction::ToggleSemanticSelection => {
+                self.toggle_vi_selection(SelectionType::Semantic)
+            }
+            ViAction::InlineSearchForward => {
+                self.start_inline_search(Direction::Right, false)
+            }
+            ViAction::InlineSearchBackward => {
+                self.start_inline_search(Direction::Left, false)
+            }
+            ViAction::InlineSearchForwardShort => {
+                self.start_inline_search(Direction::Right, true)
+            }
+            ViAction::InlineSearchBackwardShort => {
+                self.start_inline_search(Direction::Left, true)
+            }
+            ViAction::InlineSearchNext => {
+                self.vi_inline_search(self.inline_search.direction)
+            }
+            ViAction::InlineSearchPrevious => {
+                self.vi_inline_search(self.inline_search.direction.opposite())
+            }
+            ViAction::SearchForward => self.start_search(Direction::Right),
+            ViAction::SearchBackward => self.start_search(Direction::Left),
+            ViAction::SearchNext => self.vi_search_next(self.search.direction),
+            ViAction::SearchPrevious => {
+                self.vi_search_next(self.search.direction.opposite())
+            }
+        }
+    }
+
+    /// Start typing a search query, searched from the vi cursor in `direction`.
+    fn start_search(&mut self, direction: Direction) {
+        let terminal = self.context_manager.current().terminal.lock();
+        self.search.origin = terminal.vi_mode_cursor.pos;
+        drop(terminal);
+
+        self.search.input = Some(String::new());
+        self.search.direction = direction;
+        self.search.regex = None;
+    }
+
+    /// Edit the search query with `key`, moving the vi cursor to the first match as the
+    /// query is typed.
+    fn search_input(&mut self, key: &KeyEvent) {
+        let input = match self.search.input.as_mut() {
+            Some(input) => input,
+            None => return,
+        };
+
+        match key.logical_key.as_ref() {
+            Key::Enter => {
+                self.search.input = None;
+                return;
+            }
+            Key::Escape => {
+                let origin = self.search.origin;
+                self.search = SearchState::default();
+                let mut terminal = self.context_manager.current_mut().terminal.lock();
+                terminal.clear_search_matches();
+                terminal.vi_goto_pos(origin);
+                drop(terminal);
+                return;
+            }
+            Key::Backspace => {
+                input.pop();
+            }
+            _ => match key.text.as_ref() {
+                Some(text) if !text.chars().any(char::is_control) => input.push_str(text),
+                _ => return,
+            },
+        }
+
+        // Smart case, the query is case insensitive unless it has an uppercase letter.
+        let case_insensitive = !input.chars().any(char::is_uppercase);
+        self.search.regex = match input.as_str() {
+            "" => None,
+            query => RegexSearch::new(query, case_insensitive).ok(),
+        };
+
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        terminal.clear_search_matches();
+        terminal.vi_goto_pos(self.search.origin);
+        if let Some(regex) = &self.search.regex {
+            terminal.vi_search(regex, self.search.origin, self.search.direction);
+        }
+        let selection = terminal
+            .selection
+            .as_ref()
+            .and_then(|selection| selection.to_range(&terminal));
+        drop(terminal);
+        self.state.set_selection(selection);
+    }
+
+    fn vi_search_next(&mut self, direction: Direction) {
+        let regex = match &self.search.regex {
+            Some(regex) => regex,
+            None => return,
+        };
+
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        let origin = terminal.vi_mode_cursor.pos;
+        terminal.vi_search(regex, origin, direction);
+        let selection = terminal
+            .selection
+            .as_ref()
+            .and_then(|selection| selection.to_range(&terminal));
+        drop(terminal);
+        self.state.set_selection(selection);
+    }
+
+    fn start_inline_search(&mut self, direction: Direction, stop_short: bool) {
+        self.inline_search.char_pending = true;
+        self.inline_search.direction = direction;
+        self.inline_search.stop_short = stop_short;
+    }
+
+    fn vi_inline_search(&mut self, direction: Direction) {
+        let c = match self.inline_search.character {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut terminal = self.context_manager.current_mut().terminal.lock();
+        terminal.vi_inline_search(c, direction, self.inline_search.stop_short);
+        let selection = terminal
+            .selection
+            .as_ref()
+            .and_then(|selection| selection.to_range(&terminal));
+        drop(terminal);
+        self.state.set_selection(selection);
+    }
+
     fn start_selection(&mut self, ty: SelectionType, point: Pos, side: Side) {
         self.copy_selection(ClipboardType::Selection);
         let mut terminal = self.context_manager.current().terminal.lock();
@@ -1280,33 +1567,11 @@ impl Screen {
 
     #[inline]
     pub fn paste(&mut self, text: &str, bracketed: bool) {
-        if bracketed && self.get_mode().contains(Mode::BRACKETED_PASTE) {
-            self.ctx_mut()
-                .current_mut()
-                .messenger
-                .send_bytes(b"\x1b[200~"[..].to_vec());
-
-            // Write filtered escape sequences.
-            //
-            // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
-            // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
-            // bracketed paste on its receival.
-            let filtered = text.replace(['\x1b', '\x03'], "");
-            self.ctx_mut()
-                .current_mut()
-                .messenger
-                .send_bytes(filtered.into_bytes());
-
-            self.ctx_mut()
-                .current_mut()
-                .messenger
-                .send_bytes(b"\x1b[201~"[..].to_vec());
-        } else {
-            self.ctx_mut()
-                .current_mut()
-                .messenger
-                .send_bytes(text.replace("\r\n", "\r").replace('\n', "\r").into_bytes());
-        }
+        let bracketed = bracketed && self.get_mode().contains(Mode::BRACKETED_PASTE);
+        self.ctx_mut()
+            .current_mut()
+            .messenger
+            .send_bytes(paste_bytes(text, bracketed));
     }
 
     #[inline]
@@ -1356,14 +1621,20 @@ impl Screen {
 
     #[inline]
     pub fn render(&mut self) {
-        let mut terminal = self.ctx().current().terminal.lock();
+        let terminal_id = Arc::as_ptr(&self.ctx().current().terminal) as usize;
+        let mut terminal = self.context_manager.current().terminal.lock();
         let visible_rows = terminal.visible_rows();
         let cursor = terminal.cursor();
         let display_offset = terminal.display_offset();
-        let terminal_has_blinking_enabled = terminal.blinking_cursor;
+        let terminal_has_blinking_enabled = terminal.cursor_style().blinking;
+        let term_colors = *terminal.colors();
+        self.state.set_damage(terminal_id, terminal.take_damage());
         drop(terminal);
         self.context_manager.update_titles();
 
+        if let Some(background) = self.state.set_term_colors(term_colors) {
+            self.sugarloaf.set_background_color(background);
+        }
         self.state.set_ime(self.ime.preedit());
 
         self.state.prepare_term(
@@ -1377,24 +1648,15 @@ impl Screen {
 
         self.sugarloaf.render();
 
-        // In this case the configuration of blinking cursor is enabled
-        // and the terminal also have instructions of blinking enabled
-        if self.state.has_blinking_enabled && terminal_has_blinking_enabled {
-            self.context_manager.schedule_cursor_blinking_render();
+        // Keep rendering only while there is blinking text on the screen
+        if self.state.should_animate_blinking_text() {
+            self.context_manager.schedule_text_blinking_render();
         }
-    }
-
-    fn sgr_mouse_report(&mut self, pos: Pos, button: u8, state: ElementState) {
-        let c = match state {
-            ElementState::Pressed => 'M',
-            ElementState::Released => 'm',
-        };
 
-        let msg = format!("\x1b[<{};{};{}{}", button, pos.col + 1, pos.row + 1, c);
-        self.ctx_mut()
-            .current_mut()
-            .messenger
-            .send_bytes(msg.into_bytes());
+        // The configured blinking applies unless the terminal asked otherwise
+        if terminal_has_blinking_enabled {
+            self.context_manager.schedule_cursor_blinking_render();
+        }
     }
 
     #[inline]
@@ -1422,64 +1684,12 @@ impl Screen {
             return;
         }
 
-        // Calculate modifiers value.
-        let mut mods = 0;
-        let mod_state = self.modifiers.state();
-        if mod_state.shift_key() {
-            mods += 4;
-        }
-        if mod_state.alt_key() {
-            mods += 8;
-        }
-        if mod_state.control_key() {
-            mods += 16;
-        }
-
-        // Report mouse events.
-        if mode.contains(Mode::SGR_MOUSE) {
-            self.sgr_mouse_report(pos, button + mods, state);
-        } else if let ElementState::Released = state {
-            self.normal_mouse_report(pos, 3 + mods);
-        } else {
-            self.normal_mouse_report(pos, button + mods);
+        let mods = mouse::modifier_bits(self.modifiers.state());
+        if let Some(report) = mouse::report_bytes(pos, button, mods, state, mode) {
+            self.ctx_mut().current_mut().messenger.send_bytes(report);
         }
     }
 
-    #[inline]
-    fn normal_mouse_report(&mut self, position: Pos, button: u8) {
-        let Pos { row, col } = position;
-        let utf8 = self.get_mode().contains(Mode::UTF8_MOUSE);
-
-        let max_point = if utf8 { 2015 } else { 223 };
-
-        if row >= max_point || col >= max_point {
-            return;
-        }
-
-        let mut msg = vec![b'\x1b', b'[', b'M', 32 + button];
-
-        let mouse_pos_encode = |pos: usize| -> Vec<u8> {
-            let pos = 32 + 1 + pos;
-            let first = 0xC0 + pos / 64;
-            let second = 0x80 + (pos & 63);
-            vec![first as u8, second as u8]
-        };
-
-        if utf8 && col >= Column(95) {
-            msg.append(&mut mouse_pos_encode(col.0));
-        } else {
-            msg.push(32 + 1 + col.0 as u8);
-        }
-
-        if utf8 && row >= 95 {
-            msg.append(&mut mouse_pos_encode(row.0 as usize));
-        } else {
-            msg.push(32 + 1 + row.0 as u8);
-        }
-
-        self.ctx_mut().current_mut().messenger.send_bytes(msg);
-    }
-
     #[inline]
     pub fn scroll(&mut self, new_scroll_x_px: f64, new_scroll_y_px: f64) {
         let width = self.sugarloaf.layout.width as f64;
@@ -1568,3 +1778,53 @@ impl Screen {
         self.mouse.accumulated_scroll.y %= height;
     }
 }
+
+/// Bytes written to the PTY when pasting `text`.
+///
+/// With bracketed paste (mode 2004) the text is wrapped in `\x1b[200~` and `\x1b[201~` and its
+/// newlines are kept. Otherwise newlines are sent as carriage returns, like typing Enter.
+fn paste_bytes(text: &str, bracketed: bool) -> Vec<u8> {
+    if !bracketed {
+        return text.replace("\r\n", "\r").replace('\n', "\r").into_bytes();
+    }
+
+    // We remove `\x1b` to ensure it's impossible for the pasted text to write the bracketed
+    // paste end escape `\x1b[201~` and `\x03` since some shells incorrectly terminate
+    // bracketed paste on its receival.
+    let filtered = text.replace(['\x1b', '\x03'], "");
+
+    let mut bytes = Vec::with_capacity(filtered.len() + 12);
+    bytes.extend_from_slice(b"\x1b[200~");
+    bytes.extend_from_slice(filtered.as_bytes());
+    bytes.extend_from_slice(b"\x1b[201~");
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paste_bracketed_wraps_and_keeps_newlines() {
+        assert_eq!(
+            paste_bytes("echo one\necho two\r\n", true),
+            b"\x1b[200~echo one\necho two\r\n\x1b[201~".to_vec()
+        );
+    }
+
+    #[test]
+    fn paste_unbracketed_sends_carriage_returns() {
+        assert_eq!(
+            paste_bytes("echo one\necho two\r\n", false),
+            b"echo one\recho two\r".to_vec()
+        );
+    }
+
+    #[test]
+    fn paste_bracketed_cannot_end_the_bracket() {
+        assert_eq!(
+            paste_bytes("ls\x1b[201~\nrm -rf ~\x03", true),
+            b"\x1b[200~ls[201~\nrm -rf ~\x1b[201~".to_vec()
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use crate::crosswords::pos::Side;
+use crate::crosswords::grid::row::Row;
+use crate::crosswords::pos::{Column, Line, Pos, Side};
+use crate::crosswords::square::{Flags, Square};
+use crate::crosswords::Mode;
 use crate::event::ClickState;
 use std::time::Instant;
 use winit::event::ElementState;
 use winit::event::MouseButton;
+use winit::keyboard::ModifiersState;
 
 #[derive(Default, Debug)]
 pub struct AccumulatedScroll {
@@ -49,3 +53,327 @@ impl Default for Mouse {
         }
     }
 }
+
+/// Grid cell under the pixel `(x, y)`, relative to the top-left of the text area.
+///
+/// Positions outside of the grid are clamped to the nearest edge cell, and the right half
+/// of a wide char reports the column of the char itself instead of its spacer.
+#[allow(dead_code)]
+pub fn cell_at(
+    rows: &[Row<Square>],
+    x: f32,
+    y: f32,
+    cell_width: f32,
+    cell_height: f32,
+) -> Pos {
+    let last_line = match rows.len().checked_sub(1) {
+        Some(last_line) => last_line,
+        None => return Pos::default(),
+    };
+
+    // Negative and NaN values saturate to zero when cast.
+    let line = ((y / cell_height) as usize).min(last_line);
+    let row = &rows[line];
+    let mut column = ((x / cell_width) as usize).min(row.len().saturating_sub(1));
+
+    if column > 0 && row[Column(column)].flags.contains(Flags::WIDE_CHAR_SPACER) {
+        column -= 1;
+    }
+
+    Pos::new(Line(line as i32), Column(column))
+}
+
+/// Modifier bits added to the button code of a mouse report.
+pub fn modifier_bits(modifiers: ModifiersState) -> u8 {
+    let mut mods = 0;
+    if modifiers.shift_key() {
+        mods += 4;
+    }
+    if modifiers.alt_key() {
+        mods += 8;
+    }
+    if modifiers.control_key() {
+        mods += 16;
+    }
+    mods
+}
+
+/// Bytes reporting a mouse `button` event at `pos` to the PTY, in the encoding set by `mode`.
+///
+/// The SGR encoding (mode 1006) reports releases with the button that was released. The
+/// normal encoding can only report a release as button 3, and gives up on positions that don't
+/// fit a byte, or two bytes with the UTF-8 encoding (mode 1005).
+pub fn report_bytes(
+    pos: Pos,
+    button: u8,
+    mods: u8,
+    state: ElementState,
+    mode: Mode,
+) -> Option<Vec<u8>> {
+    if mode.contains(Mode::SGR_MOUSE) {
+        let c = match state {
+            ElementState::Pressed => 'M',
+            ElementState::Released => 'm',
+        };
+        let msg = format!(
+            "\x1b[<{};{};{}{}",
+            button + mods,
+            pos.col + 1,
+            pos.row + 1,
+            c
+        );
+        return Some(msg.into_bytes());
+    }
+
+    let button = match state {
+        ElementState::Pressed => button + mods,
+        ElementState::Released => 3 + mods,
+    };
+
+    let Pos { row, col } = pos;
+    let utf8 = mode.contains(Mode::UTF8_MOUSE);
+    let max_point = if utf8 { 2015 } else { 223 };
+
+    if row >= max_point || col >= max_point {
+        return None;
+    }
+
+    let mut msg = vec![b'\x1b', b'[', b'M', 32 + button];
+
+    let mouse_pos_encode = |pos: usize| -> Vec<u8> {
+        let pos = 32 + 1 + pos;
+        let first = 0xC0 + pos / 64;
+        let second = 0x80 + (pos & 63);
+        vec![first as u8, second as u8]
+    };
+
+    if utf8 && col >= Column(95) {
+        msg.append(&mut mouse_pos_encode(col.0));
+    } else {
+        msg.push(32 + 1 + col.0 as u8);
+    }
+
+    if utf8 && row >= 95 {
+        msg.append(&mut mouse_pos_encode(row.0 as usize));
+    } else {
+        msg.push(32 + 1 + row.0 as u8);
+    }
+
+    Some(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<Row<Square>> {
+        let mut rows = vec![Row::<Square>::new(4), Row::new(4), Row::new(4)];
+        rows[1][Column(1)].c = '你';
+        rows[1][Column(1)].flags.insert(Flags::WIDE_CHAR);
+        rows[1][Column(2)].flags.insert(Flags::WIDE_CHAR_SPACER);
+        rows
+    }
+
+    #[test]
+    fn cell_at_cell_center() {
+        let rows = rows();
+        assert_eq!(
+            cell_at(&rows, 5.0, 10.0, 10.0, 20.0),
+            Pos::new(Line(0), Column(0))
+        );
+        assert_eq!(
+            cell_at(&rows, 25.0, 50.0, 10.0, 20.0),
+            Pos::new(Line(2), Column(2))
+        );
+        assert_eq!(
+            cell_at(&rows, 35.0, 30.0, 10.0, 20.0),
+            Pos::new(Line(1), Column(3))
+        );
+    }
+
+    #[test]
+    fn cell_at_wide_char_right_half() {
+        let rows = rows();
+        assert_eq!(
+            cell_at(&rows, 15.0, 30.0, 10.0, 20.0),
+            Pos::new(Line(1), Column(1))
+        );
+        assert_eq!(
+            cell_at(&rows, 25.0, 30.0, 10.0, 20.0),
+            Pos::new(Line(1), Column(1))
+        );
+    }
+
+    #[test]
+    fn cell_at_clamps_out_of_range() {
+        let rows = rows();
+        assert_eq!(
+            cell_at(&rows, -15.0, -3.0, 10.0, 20.0),
+            Pos::new(Line(0), Column(0))
+        );
+        assert_eq!(
+            cell_at(&rows, 400.0, 900.0, 10.0, 20.0),
+            Pos::new(Line(2), Column(3))
+        );
+        assert_eq!(
+            cell_at(&rows, 400.0, -3.0, 10.0, 20.0),
+            Pos::new(Line(0), Column(3))
+        );
+        assert_eq!(cell_at(&[], 5.0, 5.0, 10.0, 20.0), Pos::default());
+    }
+
+    fn report(
+        pos: Pos,
+        button: u8,
+        mods: u8,
+        state: ElementState,
+        mode: Mode,
+    ) -> Vec<u8> {
+        report_bytes(pos, button, mods, state, mode).unwrap()
+    }
+
+    #[test]
+    fn modifier_bits_for_shift_alt_and_control() {
+        assert_eq!(modifier_bits(ModifiersState::empty()), 0);
+        assert_eq!(modifier_bits(ModifiersState::SHIFT), 4);
+        assert_eq!(modifier_bits(ModifiersState::ALT), 8);
+        assert_eq!(modifier_bits(ModifiersState::CONTROL), 16);
+        assert_eq!(
+            modifier_bits(ModifiersState::SHIFT | ModifiersState::CONTROL),
+            20
+        );
+    }
+
+    #[test]
+    fn report_normal_encoding() {
+        let mode = Mode::MOUSE_REPORT_CLICK;
+        let pos = Pos::new(Line(4), Column(9));
+
+        // Left press, then its release reported as button 3.
+        assert_eq!(
+            report(pos, 0, 0, ElementState::Pressed, mode),
+            b"\x1b[M *%".to_vec()
+        );
+        assert_eq!(
+            report(pos, 0, 0, ElementState::Released, mode),
+            b"\x1b[M#*%".to_vec()
+        );
+        // Drag with the right button and control held.
+        assert_eq!(
+            report(pos, 34, 16, ElementState::Pressed, Mode::MOUSE_DRAG),
+            b"\x1b[MR*%".to_vec()
+        );
+        // Wheel up.
+        assert_eq!(
+            report(Pos::default(), 64, 0, ElementState::Pressed, mode),
+            b"\x1b[M`!!".to_vec()
+        );
+        // The position doesn't fit in a byte anymore.
+        assert_eq!(
+            report_bytes(
+                Pos::new(Line(0), Column(223)),
+                0,
+                0,
+                ElementState::Pressed,
+                mode
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn report_sgr_encoding() {
+        let mode = Mode::MOUSE_REPORT_CLICK | Mode::SGR_MOUSE;
+        let pos = Pos::new(Line(4), Column(9));
+
+        assert_eq!(
+            report(pos, 0, 0, ElementState::Pressed, mode),
+            b"\x1b[<0;10;5M".to_vec()
+        );
+        assert_eq!(
+            report(pos, 2, 4, ElementState::Released, mode),
+            b"\x1b[<6;10;5m".to_vec()
+        );
+        // Motion without buttons, with alt held.
+        assert_eq!(
+            report(pos, 35, 8, ElementState::Pressed, Mode::MOUSE_MOTION | mode),
+            b"\x1b[<43;10;5M".to_vec()
+        );
+        assert_eq!(
+            report(pos, 65, 0, ElementState::Pressed, mode),
+            b"\x1b[<65;10;5M".to_vec()
+        );
+        // No limit on the position.
+        assert_eq!(
+            report(
+                Pos::new(Line(300), Column(2500)),
+                0,
+                0,
+                ElementState::Pressed,
+                mode
+            ),
+            b"\x1b[<0;2501;301M".to_vec()
+        );
+    }
+
+    #[test]
+    fn report_wheel_with_shift() {
+        let mode = Mode::MOUSE_REPORT_CLICK | Mode::SGR_MOUSE;
+        let pos = Pos::new(Line(4), Column(9));
+        let shift = modifier_bits(ModifiersState::SHIFT);
+
+        // The wheel is still reported while shift is held, with the shift bit set.
+        assert_eq!(
+            report(pos, 64, shift, ElementState::Pressed, mode),
+            b"\x1b[<68;10;5M".to_vec()
+        );
+        assert_eq!(
+            report(
+                pos,
+                65,
+                shift,
+                ElementState::Pressed,
+                Mode::MOUSE_REPORT_CLICK
+            ),
+            b"\x1b[Me*%".to_vec()
+        );
+    }
+
+    #[test]
+    fn report_utf8_encoding() {
+        let mode = Mode::MOUSE_REPORT_CLICK | Mode::UTF8_MOUSE;
+
+        // Small positions are encoded like the normal encoding.
+        assert_eq!(
+            report(
+                Pos::new(Line(4), Column(9)),
+                0,
+                0,
+                ElementState::Pressed,
+                mode
+            ),
+            b"\x1b[M *%".to_vec()
+        );
+        // Column 100 is sent as U+0085, as two bytes.
+        assert_eq!(
+            report(
+                Pos::new(Line(4), Column(100)),
+                1,
+                0,
+                ElementState::Pressed,
+                mode
+            ),
+            vec![0x1b, b'[', b'M', b'!', 0xC2, 0x85, b'%']
+        );
+        assert_eq!(
+            report_bytes(
+                Pos::new(Line(2015), Column(0)),
+                0,
+                0,
+                ElementState::Pressed,
+                mode
+            ),
+            None
+        );
+    }
+}
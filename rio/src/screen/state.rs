@@ -2,14 +2,16 @@ use crate::ansi::CursorShape;
 use crate::crosswords::grid::row::Row;
 use crate::crosswords::pos;
 use crate::crosswords::pos::CursorState;
-use crate::crosswords::square::{Flags, Square};
+use crate::crosswords::square::{BlinkRate, Flags, FontWeight, Square};
+use crate::crosswords::LineDamageBounds;
 use crate::ime::Preedit;
 use crate::screen::navigation::ScreenNavigation;
 use crate::screen::{context, EventProxy};
 use crate::selection::SelectionRange;
 use rio_config::colors::{
     term::{List, TermColors},
-    AnsiColor, ColorArray, Colors, NamedColor,
+    AnsiColor, ColorArray, ColorBuilder, ColorComposition, ColorRgb, ColorWGPU, Colors,
+    Format, NamedColor,
 };
 use rio_config::Config;
 use std::collections::HashMap;
@@ -33,6 +35,7 @@ pub struct State {
     pub named_colors: Colors,
     font_size: f32,
     pub colors: List,
+    term_colors: TermColors,
     navigation: ScreenNavigation,
     cursor: Cursor,
     pub selection_range: Option<SelectionRange>,
@@ -40,6 +43,65 @@ pub struct State {
     pub is_blinking: bool,
     ignore_selection_fg_color: bool,
     dynamic_background: ([f32; 4], wgpu::Color),
+    has_background_image: bool,
+    visual_blink: bool,
+    bold_is_bright: bool,
+    blink_start: Instant,
+    has_blinking_text: bool,
+    /// Sugar stacks of the rows of the last frame, reused for the rows without damage.
+    row_stacks: Vec<SugarStack>,
+    /// Terminal the row stacks were built for, the rows of another tab can't be reused.
+    row_stacks_terminal: usize,
+    /// Row of the cursor in the last frame.
+    row_stacks_cursor: Option<usize>,
+    /// Damage reported by the terminal since the last frame.
+    damaged_rows: Vec<LineDamageBounds>,
+}
+
+/// Window background drawn with `rgb`, keeping the opacity of `background`.
+#[inline]
+fn background_from_rgb(background: ColorComposition, rgb: ColorRgb) -> ColorComposition {
+    let mut array = rgb.to_arr();
+    array[3] = background.0[3];
+    let mut wgpu = ColorBuilder::from_rgb(rgb, Format::SRGB0_1).to_wgpu();
+    wgpu.a = background.1.a;
+    (array, wgpu)
+}
+
+/// Underline color precedence: the cell's explicit color (SGR 58), then the theme
+/// undercurl color for undercurled cells, then the cell foreground.
+#[inline]
+fn resolve_underline_color(
+    explicit: Option<ColorArray>,
+    undercurl: Option<ColorArray>,
+    flags: Flags,
+    foreground: ColorArray,
+) -> ColorArray {
+    match (explicit, undercurl) {
+        (Some(color), _) => color,
+        (None, Some(color)) if flags.contains(Flags::UNDERCURL) => color,
+        _ => foreground,
+    }
+}
+
+/// Damage covering every square of `line`.
+#[inline]
+fn whole_line(line: usize) -> LineDamageBounds {
+    LineDamageBounds {
+        line,
+        left: 0,
+        right: usize::MAX,
+    }
+}
+
+/// Bright variant of a color, used for blinking text when `visual-blink` is disabled.
+#[inline]
+fn to_bright(color: AnsiColor) -> AnsiColor {
+    match color {
+        AnsiColor::Named(named) => AnsiColor::Named(named.to_light()),
+        AnsiColor::Indexed(index @ 0..=7) => AnsiColor::Indexed(index + 8),
+        color => color,
+    }
 }
 
 // TODO: Finish from
@@ -49,7 +111,7 @@ impl From<Square> for Sugar {
         let mut style: Option<SugarStyle> = None;
         let is_italic = square.flags.contains(Flags::ITALIC);
         let is_bold_italic = square.flags.contains(Flags::BOLD_ITALIC);
-        let is_bold = square.flags.contains(Flags::BOLD);
+        let is_bold = square.weight() == FontWeight::Bold;
 
         if is_bold || is_bold_italic || is_italic {
             style = Some(SugarStyle {
@@ -65,6 +127,7 @@ impl From<Square> for Sugar {
             background_color: [0.0, 0.0, 0.0, 1.0],
             style,
             decoration: None,
+            extra_decorations: [None, None],
         }
     }
 }
@@ -106,7 +169,16 @@ impl State {
             last_typing: None,
             has_blinking_enabled: config.blinking_cursor,
             ignore_selection_fg_color: config.ignore_selection_fg_color,
+            visual_blink: config.visual_blink,
+            bold_is_bright: config.bold_is_bright,
+            blink_start: Instant::now(),
+            has_blinking_text: false,
+            row_stacks: vec![],
+            row_stacks_terminal: 0,
+            row_stacks_cursor: None,
+            damaged_rows: vec![],
             colors,
+            term_colors,
             navigation: ScreenNavigation::new(
                 config.navigation.mode,
                 [
@@ -123,6 +195,7 @@ impl State {
             selection_range: None,
             named_colors,
             dynamic_background,
+            has_background_image: config.background.mode.is_image(),
             cursor: Cursor {
                 content: config.cursor,
                 content_ref: config.cursor,
@@ -146,19 +219,28 @@ impl State {
     fn create_sugar(&self, square: &Square) -> Sugar {
         let flags = square.flags;
 
-        let mut foreground_color = self.compute_fg_color(square);
-        let mut background_color = self.compute_bg_color(square);
-
-        let content = if square.c == '\t' || flags.contains(Flags::HIDDEN) {
-            ' '
+        let blink_state = square.blink_state();
+        let foreground_color = if blink_state.is_some() && !self.visual_blink {
+            self.compute_color(to_bright(square.fg), flags)
         } else {
-            square.c
+            self.compute_fg_color(square)
         };
+        let (mut foreground_color, mut background_color) =
+            square.resolve_colors(foreground_color, self.compute_bg_color(square));
+
+        let is_blink_hidden = self.visual_blink
+            && blink_state.map_or(false, |rate| !self.is_blink_visible(rate));
+        let content =
+            if square.c == '\t' || flags.contains(Flags::HIDDEN) || is_blink_hidden {
+                ' '
+            } else {
+                square.c
+            };
 
         let mut style: Option<SugarStyle> = None;
         let is_italic = flags.contains(Flags::ITALIC);
         let is_bold_italic = flags.contains(Flags::BOLD_ITALIC);
-        let is_bold = flags.contains(Flags::BOLD);
+        let is_bold = square.weight() == FontWeight::Bold;
 
         if is_bold || is_bold_italic || is_italic {
             style = Some(SugarStyle {
@@ -168,24 +250,35 @@ impl State {
             });
         }
 
-        if flags.contains(Flags::INVERSE) {
-            std::mem::swap(&mut background_color, &mut foreground_color);
+        if flags.contains(Flags::SEARCH_MATCH) {
+            foreground_color = self.named_colors.search_match_foreground;
+            background_color = self.named_colors.search_match_background;
         }
 
-        let mut decoration = None;
-        if flags.contains(Flags::UNDERLINE) {
-            decoration = Some(SugarDecoration {
-                relative_position: (0.0, self.font_size - 1.),
-                size: (1.0, 0.005),
-                color: self.named_colors.foreground,
-            });
-        } else if flags.contains(Flags::STRIKEOUT) {
-            decoration = Some(SugarDecoration {
-                relative_position: (0.0, self.font_size / 2.),
-                size: (1.0, 0.025),
-                color: self.named_colors.foreground,
-            });
-        }
+        let underline = square.underline_style().map(|_| SugarDecoration {
+            relative_position: (0.0, self.font_size - 1.),
+            size: (1.0, 0.005),
+            color: self.compute_underline_color(square),
+        });
+        let strikeout = flags.contains(Flags::STRIKEOUT).then(|| SugarDecoration {
+            relative_position: (0.0, self.font_size / 2.),
+            size: (1.0, 0.025),
+            color: square
+                .strikeout_color()
+                .map_or(self.named_colors.foreground, |color| {
+                    self.compute_color(color, flags)
+                }),
+        });
+        let overline = square.is_overlined().then(|| SugarDecoration {
+            relative_position: (0.0, 0.0),
+            size: (1.0, 0.005),
+            color: self.compute_fg_color(square),
+        });
+
+        // Text decorations can be combined, the first one present is the main decoration.
+        let mut decorations = [underline, strikeout, overline].into_iter().flatten();
+        let decoration = decorations.next();
+        let extra_decorations = [decorations.next(), decorations.next()];
 
         Sugar {
             content,
@@ -193,26 +286,49 @@ impl State {
             background_color,
             style,
             decoration,
+            extra_decorations,
         }
     }
 
+    /// Blinking text is visible during the first half of each blink period.
+    #[inline]
+    fn is_blink_visible(&self, rate: BlinkRate) -> bool {
+        let period = match rate {
+            BlinkRate::Rapid => 500,
+            BlinkRate::Slow => 1200,
+        };
+        self.blink_start.elapsed().as_millis() % period < period / 2
+    }
+
+    #[inline]
+    pub fn should_animate_blinking_text(&self) -> bool {
+        self.visual_blink && self.has_blinking_text
+    }
+
+    /// Cursor color, as set through OSC 12 or else from the theme.
+    #[inline]
+    fn cursor_color(&self) -> ColorArray {
+        self.term_colors[NamedColor::Cursor]
+            .map_or(self.named_colors.cursor, |rgb| rgb.to_arr())
+    }
+
     #[inline]
     fn cursor_to_decoration(&self) -> Option<SugarDecoration> {
         match self.cursor.state.content {
             CursorShape::Block => Some(SugarDecoration {
                 relative_position: (0.0, 0.0),
                 size: (1.0, 1.0),
-                color: self.named_colors.cursor,
+                color: self.cursor_color(),
             }),
             CursorShape::Underline => Some(SugarDecoration {
                 relative_position: (0.0, self.font_size - 2.5),
                 size: (1.0, 0.08),
-                color: self.named_colors.cursor,
+                color: self.cursor_color(),
             }),
             CursorShape::Beam => Some(SugarDecoration {
                 relative_position: (0.0, 0.0),
                 size: (0.1, 1.0),
-                color: self.named_colors.cursor,
+                color: self.cursor_color(),
             }),
             CursorShape::Hidden => None,
         }
@@ -228,6 +344,7 @@ impl State {
                 background_color: self.named_colors.background.0,
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             })
         }
         stack
@@ -273,6 +390,7 @@ impl State {
                     background_color: self.named_colors.selection_background,
                     style: None,
                     decoration: None,
+                    extra_decorations: [None, None],
                 };
                 stack.push(selected_sugar);
             } else {
@@ -290,7 +408,41 @@ impl State {
 
     #[inline]
     fn compute_fg_color(&self, square: &Square) -> ColorArray {
+        // Bold text uses the bright standard colors, dim wins over bold for the color.
+        let flags = square.flags;
+        let bold = self.bold_is_bright
+            && flags.contains(Flags::BOLD)
+            && !flags.contains(Flags::DIM);
+
         match square.fg {
+            AnsiColor::Named(named) if bold && (named as usize) < 8 => {
+                self.compute_color(AnsiColor::Named(named.to_light()), flags)
+            }
+            fg => self.compute_color(fg, flags),
+        }
+    }
+
+    #[inline]
+    fn compute_underline_color(&self, square: &Square) -> ColorArray {
+        resolve_underline_color(
+            square
+                .underline_color()
+                .map(|color| self.compute_color(color, square.flags)),
+            self.named_colors.undercurl,
+            square.flags,
+            self.compute_fg_color(square),
+        )
+    }
+
+    #[inline]
+    fn compute_color(&self, color: AnsiColor, flags: Flags) -> ColorArray {
+        if let AnsiColor::Named(named) = color {
+            if let Some(rgb) = self.term_colors[named] {
+                return rgb.to_arr();
+            }
+        }
+
+        match color {
             AnsiColor::Named(NamedColor::Black) => self.named_colors.black,
             AnsiColor::Named(NamedColor::Background) => self.named_colors.background.0,
             AnsiColor::Named(NamedColor::Blue) => self.named_colors.blue,
@@ -325,26 +477,40 @@ impl State {
             AnsiColor::Named(NamedColor::White) => self.named_colors.white,
             AnsiColor::Named(NamedColor::Yellow) => self.named_colors.yellow,
             AnsiColor::Spec(rgb) => {
-                if !square.flags.contains(Flags::DIM) {
+                if !flags.contains(Flags::DIM) {
                     rgb.to_arr()
                 } else {
                     rgb.to_arr_with_dim()
                 }
             }
             AnsiColor::Indexed(index) => {
-                let index = match (square.flags & Flags::DIM_BOLD, index) {
-                    (Flags::DIM, 8..=15) => index as usize - 8,
-                    (Flags::DIM, 0..=7) => NamedColor::DimBlack as usize + index as usize,
+                // Dim wins over bold for the color.
+                let index = match index {
+                    8..=15 if flags.contains(Flags::DIM) => index as usize - 8,
+                    0..=7 if flags.contains(Flags::DIM) => {
+                        NamedColor::DimBlack as usize + index as usize
+                    }
                     _ => index as usize,
                 };
 
-                self.colors[index]
+                self.term_colors[index].map_or(self.colors[index], |rgb| rgb.to_arr())
             }
         }
     }
 
     #[inline]
     fn compute_bg_color(&self, square: &Square) -> ColorArray {
+        // The background follows the window one, see `set_term_colors`.
+        match square.bg {
+            AnsiColor::Named(NamedColor::Background) => (),
+            AnsiColor::Named(named) => {
+                if let Some(rgb) = self.term_colors[named] {
+                    return rgb.to_arr();
+                }
+            }
+            _ => (),
+        }
+
         match square.bg {
             AnsiColor::Named(NamedColor::Black) => self.named_colors.black,
             AnsiColor::Named(NamedColor::Background) => self.dynamic_background.0,
@@ -380,7 +546,8 @@ impl State {
             AnsiColor::Named(NamedColor::White) => self.named_colors.white,
             AnsiColor::Named(NamedColor::Yellow) => self.named_colors.yellow,
             AnsiColor::Spec(rgb) => rgb.to_arr(),
-            AnsiColor::Indexed(idx) => self.colors[idx as usize],
+            AnsiColor::Indexed(idx) => self.term_colors[idx as usize]
+                .map_or(self.colors[idx as usize], |rgb| rgb.to_arr()),
         }
     }
 
@@ -427,9 +594,45 @@ impl State {
 
         let mut sugar = self.create_sugar(&cloned_square);
         sugar.decoration = self.cursor_to_decoration();
+        sugar.extra_decorations = [None, None];
         sugar
     }
 
+    /// Colors changed at runtime by the terminal, through OSC 4 or OSC 11 for example.
+    ///
+    /// Returns the new window background when the background color changed, so the
+    /// padding area can be cleared with it too.
+    pub fn set_term_colors(&mut self, term_colors: TermColors) -> Option<ColorWGPU> {
+        let background = term_colors[NamedColor::Background];
+        let changed = background != self.term_colors[NamedColor::Background];
+        self.term_colors = term_colors;
+
+        if !changed || self.has_background_image {
+            return None;
+        }
+
+        self.dynamic_background = match background {
+            Some(rgb) => background_from_rgb(self.named_colors.background, rgb),
+            None => self.named_colors.background,
+        };
+        Some(self.dynamic_background.1)
+    }
+
+    /// Configured color at `index` of the color table, used to answer color queries.
+    pub fn configured_color(&self, index: usize) -> ColorArray {
+        match index {
+            0..=255 => {
+                self.compute_color(AnsiColor::Indexed(index as u8), Flags::empty())
+            }
+            _ if index == NamedColor::Foreground as usize => self.named_colors.foreground,
+            _ if index == NamedColor::Background as usize => {
+                self.named_colors.background.0
+            }
+            _ if index == NamedColor::Cursor as usize => self.named_colors.cursor,
+            _ => self.colors[index],
+        }
+    }
+
     #[inline]
     pub fn set_ime(&mut self, ime_preedit: Option<&Preedit>) {
         if let Some(preedit) = ime_preedit {
@@ -449,6 +652,72 @@ impl State {
         self.selection_range = selection_range;
     }
 
+    /// Squares of `terminal` changed since the last frame, as reported by its damage.
+    #[inline]
+    pub fn set_damage(
+        &mut self,
+        terminal: usize,
+        damage: impl Iterator<Item = LineDamageBounds>,
+    ) {
+        if terminal != self.row_stacks_terminal {
+            self.row_stacks_terminal = terminal;
+            self.row_stacks.clear();
+        }
+
+        self.damaged_rows.extend(damage);
+    }
+
+    /// Build the sugars of the damaged squares and the rows the cursor left or entered,
+    /// everything else keeps the sugars of the last frame.
+    fn update_row_stacks(&mut self, rows: &[Row<Square>], cursor_row: Option<usize>) {
+        let mut damaged_rows = std::mem::take(&mut self.damaged_rows);
+
+        // Blinking text changes without any damage.
+        if self.row_stacks.len() != rows.len() || self.has_blinking_text {
+            self.row_stacks = (0..rows.len()).map(|_| Vec::new()).collect();
+            damaged_rows.clear();
+            damaged_rows.extend((0..rows.len()).map(whole_line));
+        }
+
+        let last_cursor_row = std::mem::replace(&mut self.row_stacks_cursor, cursor_row);
+        damaged_rows.extend(
+            last_cursor_row
+                .into_iter()
+                .chain(cursor_row)
+                .map(whole_line),
+        );
+
+        for damage in &damaged_rows {
+            let row = match rows.get(damage.line) {
+                Some(row) => row,
+                None => continue,
+            };
+            let has_cursor = cursor_row == Some(damage.line);
+
+            // Sugars match the columns one to one, unless a wide char skipped its spacer.
+            let is_patchable = !has_cursor
+                && damage.right < row.len()
+                && self.row_stacks[damage.line].len() == row.len()
+                && !row
+                    .inner
+                    .iter()
+                    .any(|square| square.flags.contains(Flags::WIDE_CHAR_SPACER));
+
+            if is_patchable {
+                for column in damage.left..=damage.right {
+                    let sugar = self.create_sugar(&row.inner[column]);
+                    self.row_stacks[damage.line][column] = sugar;
+                }
+            } else {
+                let sugar_stack = self.create_sugar_stack(row, has_cursor);
+                self.row_stacks[damage.line] = sugar_stack;
+            }
+        }
+
+        damaged_rows.clear();
+        self.damaged_rows = damaged_rows;
+    }
+
     #[inline]
     pub fn prepare_term(
         &mut self,
@@ -461,9 +730,20 @@ impl State {
     ) {
         self.cursor.state = cursor;
         let mut is_cursor_visible = self.cursor.state.is_visible();
+        self.has_blinking_text = rows.iter().any(|row| {
+            row.occupied()
+                .any(|(_, square)| square.flags.intersects(Flags::ALL_BLINKS))
+        });
+
+        if self.font_size != sugarloaf.layout.font_size {
+            self.row_stacks.clear();
+        }
 
         self.font_size = sugarloaf.layout.font_size;
         if let Some(active_selection) = self.selection_range {
+            // The selection is drawn over the rows, so they can't be reused afterwards.
+            self.row_stacks.clear();
+            self.damaged_rows.clear();
             for (i, row) in rows.iter().enumerate() {
                 let has_cursor = is_cursor_visible && self.cursor.state.pos.row == i;
                 let sugar_stack = self.create_sugar_stack_with_selection(
@@ -473,11 +753,11 @@ impl State {
                     pos::Line(i as i32),
                     display_offset,
                 );
-                sugarloaf.stack(sugar_stack);
+                sugarloaf.stack(&sugar_stack);
             }
         } else {
             // Only blink cursor if does not contain selection
-            if self.has_blinking_enabled && terminal_has_blinking_enabled {
+            if terminal_has_blinking_enabled {
                 let mut should_blink = true;
                 if let Some(last_typing_time) = self.last_typing {
                     if last_typing_time.elapsed() < Duration::from_secs(1) {
@@ -491,9 +771,10 @@ impl State {
                 }
             }
 
-            for (i, row) in rows.iter().enumerate() {
-                let has_cursor = is_cursor_visible && self.cursor.state.pos.row == i;
-                let sugar_stack = self.create_sugar_stack(row, has_cursor);
+            let cursor_row =
+                is_cursor_visible.then_some(self.cursor.state.pos.row.0 as usize);
+            self.update_row_stacks(&rows, cursor_row);
+            for sugar_stack in &self.row_stacks {
                 sugarloaf.stack(sugar_stack);
             }
         }
@@ -501,7 +782,7 @@ impl State {
         // This is a fake row created only for visual purposes
         let empty_last_line =
             self.create_empty_sugar_stack_from_columns(sugarloaf.layout.columns);
-        sugarloaf.stack(empty_last_line);
+        sugarloaf.stack(&empty_last_line);
 
         self.navigation.content(
             (sugarloaf.layout.width, sugarloaf.layout.height),
@@ -526,3 +807,245 @@ impl State {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FG: ColorArray = [1.0, 1.0, 1.0, 1.0];
+    const THEME: ColorArray = [1.0, 0.0, 0.0, 1.0];
+    const EXPLICIT: ColorArray = [0.0, 0.0, 1.0, 1.0];
+
+    #[test]
+    fn test_explicit_underline_color_wins() {
+        let color =
+            resolve_underline_color(Some(EXPLICIT), Some(THEME), Flags::UNDERCURL, FG);
+        assert_eq!(color, EXPLICIT);
+    }
+
+    #[test]
+    fn test_theme_undercurl_color_over_foreground() {
+        let color = resolve_underline_color(None, Some(THEME), Flags::UNDERCURL, FG);
+        assert_eq!(color, THEME);
+
+        // Only undercurls use the theme color.
+        let color = resolve_underline_color(None, Some(THEME), Flags::UNDERLINE, FG);
+        assert_eq!(color, FG);
+    }
+
+    #[test]
+    fn test_underline_color_falls_back_to_foreground() {
+        let color = resolve_underline_color(None, None, Flags::UNDERCURL, FG);
+        assert_eq!(color, FG);
+    }
+
+    #[test]
+    fn test_background_from_rgb_keeps_opacity() {
+        let mut background = rio_config::colors::defaults::background();
+        background.0[3] = 0.5;
+        background.1.a = 0.5;
+
+        let rgb = ColorRgb { r: 255, g: 0, b: 0 };
+        let (array, wgpu) = background_from_rgb(background, rgb);
+        assert_eq!(array, [1.0, 0.0, 0.0, 0.5]);
+        assert_eq!((wgpu.r, wgpu.g, wgpu.b, wgpu.a), (1.0, 0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn test_term_background_updates_window_background() {
+        let config = Rc::new(Config::default());
+        let mut state = State::new(&config, None);
+
+        let mut term_colors = TermColors::default();
+        term_colors[NamedColor::Background] = Some(ColorRgb { r: 0, g: 0, b: 255 });
+        let background = state.set_term_colors(term_colors).unwrap();
+        assert_eq!((background.r, background.g, background.b), (0.0, 0.0, 1.0));
+        assert_eq!(state.dynamic_background.1, background);
+
+        // Nothing to clear again while the background stays the same.
+        assert_eq!(state.set_term_colors(term_colors), None);
+
+        // Resetting goes back to the theme.
+        let background = state.set_term_colors(TermColors::default());
+        assert_eq!(background, Some(config.colors.background.1));
+    }
+
+    #[test]
+    fn test_configured_colors_come_from_the_theme() {
+        let config = Rc::new(Config::default());
+        let mut state = State::new(&config, None);
+
+        let mut term_colors = TermColors::default();
+        term_colors[NamedColor::Foreground] = Some(ColorRgb { r: 1, g: 2, b: 3 });
+        term_colors[NamedColor::Cursor] = Some(ColorRgb { r: 0, g: 0, b: 0 });
+        state.set_term_colors(term_colors);
+
+        let foreground = NamedColor::Foreground as usize;
+        assert_eq!(state.configured_color(foreground), config.colors.foreground);
+        let background = NamedColor::Background as usize;
+        assert_eq!(
+            state.configured_color(background),
+            config.colors.background.0
+        );
+        assert_eq!(state.cursor_color(), [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_underline_and_overline_are_both_drawn() {
+        use crate::crosswords::pos::{Column, Line};
+        use crate::crosswords::Crosswords;
+        use crate::event::VoidListener;
+        use crate::performer::handler::ParserProcessor;
+        use winit::window::WindowId;
+
+        let config = Rc::new(Config::default());
+        let state = State::new(&config, None);
+
+        let mut cw = Crosswords::new(3, 1, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        for byte in b"\x1b[4;53ma\x1b[9mb" {
+            parser.advance(&mut cw, *byte);
+        }
+
+        let sugar = state.create_sugar(&cw.grid[Line(0)][Column(0)]);
+        let positions: Vec<_> = sugar
+            .decorations()
+            .map(|decoration| decoration.relative_position)
+            .collect();
+        assert_eq!(positions, [(0.0, state.font_size - 1.), (0.0, 0.0)]);
+
+        let sugar = state.create_sugar(&cw.grid[Line(0)][Column(1)]);
+        let positions: Vec<_> = sugar
+            .decorations()
+            .map(|decoration| decoration.relative_position)
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (0.0, state.font_size - 1.),
+                (0.0, state.font_size / 2.),
+                (0.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_damaged_rows_are_built_again() {
+        let config = Rc::new(Config::default());
+        let mut state = State::new(&config, None);
+        let content = |state: &State, row: usize| state.row_stacks[row][0].content;
+
+        let mut rows = vec![Row::<Square>::new(2); 3];
+        rows[0][pos::Column(0)].c = 'a';
+        rows[1][pos::Column(0)].c = 'b';
+        state.set_damage(1, std::iter::empty());
+        state.update_row_stacks(&rows, None);
+        assert_eq!((content(&state, 0), content(&state, 1)), ('a', 'b'));
+
+        // Only the damaged row picks up the change.
+        rows[0][pos::Column(0)].c = 'c';
+        rows[1][pos::Column(0)].c = 'd';
+        state.set_damage(1, std::iter::once(whole_line(1)));
+        state.update_row_stacks(&rows, None);
+        assert_eq!((content(&state, 0), content(&state, 1)), ('a', 'd'));
+
+        // Only the damaged columns are built again.
+        rows[1][pos::Column(0)].c = 'x';
+        rows[1][pos::Column(1)].c = 'y';
+        let damage = LineDamageBounds {
+            line: 1,
+            left: 1,
+            right: 1,
+        };
+        state.set_damage(1, std::iter::once(damage));
+        state.update_row_stacks(&rows, None);
+        assert_eq!(state.row_stacks[1][0].content, 'd');
+        assert_eq!(state.row_stacks[1][1].content, 'y');
+
+        // The rows the cursor enters and leaves are built again too.
+        state.set_damage(1, std::iter::empty());
+        state.update_row_stacks(&rows, Some(0));
+        assert_eq!(content(&state, 0), 'c');
+        rows[0][pos::Column(0)].c = 'e';
+        state.set_damage(1, std::iter::empty());
+        state.update_row_stacks(&rows, None);
+        assert_eq!(content(&state, 0), 'e');
+
+        // The rows of another terminal can't be reused.
+        rows[1][pos::Column(0)].c = 'f';
+        state.set_damage(2, std::iter::empty());
+        state.update_row_stacks(&rows, None);
+        assert_eq!(content(&state, 1), 'f');
+    }
+
+    #[test]
+    fn test_terminal_damage_matches_a_full_rebuild() {
+        use crate::crosswords::Crosswords;
+        use crate::event::VoidListener;
+        use crate::performer::handler::ParserProcessor;
+        use winit::window::WindowId;
+
+        let config = Rc::new(Config::default());
+        let mut state = State::new(&config, None);
+        let mut cw = Crosswords::new(6, 3, VoidListener {}, WindowId::from(0));
+        let mut parser = ParserProcessor::new();
+        let mut feed = |cw: &mut Crosswords<VoidListener>, bytes: &[u8]| {
+            for byte in bytes {
+                parser.advance(cw, *byte);
+            }
+        };
+
+        feed(&mut cw, b"abc\r\ndef");
+        state.set_damage(1, cw.take_damage());
+        state.update_row_stacks(&cw.visible_rows(), None);
+
+        // A single square changed in place on one row and a colored one on the next.
+        feed(&mut cw, b"\x1b[1;2Hx\x1b[2;3H\x1b[31my");
+        state.set_damage(1, cw.take_damage());
+        let rows = cw.visible_rows();
+        state.update_row_stacks(&rows, None);
+
+        let sugars = |stack: &SugarStack| {
+            stack
+                .iter()
+                .map(|sugar| {
+                    (
+                        sugar.content,
+                        sugar.foreground_color,
+                        sugar.background_color,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        for (line, row) in rows.iter().enumerate() {
+            let built = sugars(&state.row_stacks[line]);
+            assert_eq!(built, sugars(&state.create_sugar_stack(row, false)));
+        }
+        assert_eq!(state.row_stacks[0][1].content, 'x');
+        assert_eq!(state.row_stacks[1][2].content, 'y');
+        assert_eq!(state.row_stacks[1][2].foreground_color, config.colors.red);
+    }
+
+    #[test]
+    fn test_inverse_and_hidden_colors() {
+        let config = Rc::new(Config::default());
+        let state = State::new(&config, None);
+        let mut square = Square {
+            c: 'a',
+            fg: AnsiColor::Named(NamedColor::Red),
+            bg: AnsiColor::Named(NamedColor::Blue),
+            ..Square::default()
+        };
+
+        square.flags = Flags::INVERSE;
+        let sugar = state.create_sugar(&square);
+        assert_eq!(sugar.foreground_color, config.colors.blue);
+        assert_eq!(sugar.background_color, config.colors.red);
+
+        square.flags = Flags::INVERSE | Flags::HIDDEN;
+        let sugar = state.create_sugar(&square);
+        assert_eq!(sugar.foreground_color, config.colors.red);
+        assert_eq!(sugar.background_color, config.colors.red);
+        assert_eq!(sugar.content, ' ');
+    }
+}
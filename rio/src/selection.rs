@@ -7,7 +7,6 @@
 //! when text is added/removed/scrolled on the screen. The selection should
 //! also be cleared if the user clicks off of the selection.
 
-use std::cmp::min;
 use std::mem;
 use std::ops::{Bound, Range, RangeBounds};
 
@@ -144,13 +143,11 @@ impl Selection {
         self.region.end = Anchor::new(point, side);
     }
 
-    pub fn rotate<D: Dimensions>(
-        mut self,
-        dimensions: &D,
-        range: &Range<Line>,
-        delta: i32,
-    ) -> Option<Selection> {
-        let bottommost_line = dimensions.bottommost_line();
+    /// Move the selection with the text when the lines in `range` scroll by `delta`.
+    ///
+    /// The selection is dropped once one of its ends scrolls out of the range, since the
+    /// text it pointed to is gone.
+    pub fn rotate(mut self, range: &Range<Line>, delta: i32) -> Option<Selection> {
         let range_bottom = range.end;
         let range_top = range.start;
 
@@ -159,45 +156,30 @@ impl Selection {
             mem::swap(&mut start, &mut end);
         }
 
+        // Lines scrolled above a region starting at the top go into the history.
+        let out_of_range =
+            |line: Line| line >= range_bottom || (line < range_top && range_top != 0);
+
         // Rotate start of selection.
         if (start.point.row >= range_top || range_top == 0)
             && start.point.row < range_bottom
         {
-            start.point.row = min(start.point.row - delta, bottommost_line);
+            start.point.row -= delta;
 
-            // If end is within the same region, delete selection once start rotates out.
-            if start.point.row >= range_bottom && end.point.row < range_bottom {
+            if out_of_range(start.point.row) {
                 return None;
             }
-
-            // Clamp selection to start of region.
-            if start.point.row < range_top && range_top != 0 {
-                if self.ty != SelectionType::Block {
-                    start.point.col = Column(0);
-                    start.side = Side::Left;
-                }
-                start.point.row = range_top;
-            }
         }
 
         // Rotate end of selection.
         if (end.point.row >= range_top || range_top == 0) && end.point.row < range_bottom
         {
-            end.point.row = min(end.point.row - delta, bottommost_line);
+            end.point.row -= delta;
 
             // Delete selection if end has overtaken the start.
-            if end.point.row < start.point.row {
+            if out_of_range(end.point.row) || end.point.row < start.point.row {
                 return None;
             }
-
-            // Clamp selection to end of region.
-            if end.point.row >= range_bottom {
-                if self.ty != SelectionType::Block {
-                    end.point.col = dimensions.last_column();
-                    end.side = Side::Right;
-                }
-                end.point.row = range_bottom - 1;
-            }
         }
 
         Some(self)
@@ -303,8 +285,12 @@ impl Selection {
         start.point = start.point.grid_clamp(&term.grid, Boundary::Grid);
 
         match self.ty {
-            SelectionType::Simple => self.range_simple(start, end, columns),
-            SelectionType::Block => self.range_block(start, end),
+            SelectionType::Simple => self
+                .range_simple(start, end, columns)
+                .map(|range| Self::expand_wide_chars(term, range)),
+            SelectionType::Block => self
+                .range_block(start, end)
+                .map(|range| Self::expand_wide_chars(term, range)),
             SelectionType::Lines => Some(Self::range_lines(term, start.point, end.point)),
             SelectionType::Semantic => {
                 Some(Self::range_semantic(term, start.point, end.point))
@@ -312,6 +298,61 @@ impl Selection {
         }
     }
 
+    /// Text of the selection, following the same rules as copying it.
+    pub fn to_string<T: EventListener>(&self, term: &Crosswords<T>) -> Option<String> {
+        let SelectionRange { start, end, .. } = self.to_range(term)?;
+
+        let text = match self.ty {
+            SelectionType::Block => {
+                let mut text = String::new();
+                for line in (start.row.0..end.row.0).map(Line::from) {
+                    text += term
+                        .line_to_string(line, start.col..end.col, start.col.0 != 0)
+                        .trim_end();
+                    text += "\n";
+                }
+
+                text += term
+                    .line_to_string(end.row, start.col..end.col, true)
+                    .trim_end();
+                text
+            }
+            SelectionType::Lines => term.bounds_to_string(start, end) + "\n",
+            _ => term.bounds_to_string(start, end),
+        };
+
+        Some(text)
+    }
+
+    /// Grow the range so it never holds only one half of a wide char.
+    fn expand_wide_chars<T: EventListener>(
+        term: &Crosswords<T>,
+        mut range: SelectionRange,
+    ) -> SelectionRange {
+        let grid = &term.grid;
+        let in_grid = |point: Pos| {
+            point.row >= grid.topmost_line()
+                && point.row <= grid.bottommost_line()
+                && point.col <= grid.last_column()
+        };
+
+        if in_grid(range.start)
+            && range.start.col > 0
+            && grid[range.start].flags.contains(Flags::WIDE_CHAR_SPACER)
+        {
+            range.start.col -= 1;
+        }
+
+        if in_grid(range.end)
+            && range.end.col < grid.last_column()
+            && grid[range.end].flags.contains(Flags::WIDE_CHAR)
+        {
+            range.end.col += 1;
+        }
+
+        range
+    }
+
     fn range_semantic<T: EventListener>(
         term: &Crosswords<T>,
         mut start: Pos,
@@ -445,6 +486,7 @@ mod tests {
     use crate::crosswords::test::CrosswordsSize;
     use crate::crosswords::Crosswords;
     use crate::event::VoidListener;
+    use crate::performer::handler::ParserProcessor;
     use winit::window::WindowId;
 
     fn term(height: usize, width: usize) -> Crosswords<VoidListener> {
@@ -457,6 +499,15 @@ mod tests {
         )
     }
 
+    fn term_with(height: usize, width: usize, content: &str) -> Crosswords<VoidListener> {
+        let mut term = term(height, width);
+        let mut parser = ParserProcessor::new();
+        for byte in content.bytes() {
+            parser.advance(&mut term, byte);
+        }
+        term
+    }
+
     /// Test case of single cell selection.
     ///
     /// 1. [  ]
@@ -600,7 +651,7 @@ mod tests {
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
         selection = selection
-            .rotate(&size, &(Line(0)..Line(size.0 as i32)), 4)
+            .rotate(&(Line(0)..Line(size.0 as i32)), 4)
             .unwrap();
 
         assert_eq!(
@@ -623,7 +674,7 @@ mod tests {
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
         selection = selection
-            .rotate(&size, &(Line(0)..Line(size.0 as i32)), 4)
+            .rotate(&(Line(0)..Line(size.0 as i32)), 4)
             .unwrap();
 
         assert_eq!(
@@ -646,7 +697,7 @@ mod tests {
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
         selection = selection
-            .rotate(&size, &(Line(0)..Line(size.0 as i32)), 4)
+            .rotate(&(Line(0)..Line(size.0 as i32)), 4)
             .unwrap();
 
         assert_eq!(
@@ -669,7 +720,7 @@ mod tests {
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
         selection = selection
-            .rotate(&size, &(Line(0)..Line(size.0 as i32)), 4)
+            .rotate(&(Line(0)..Line(size.0 as i32)), 4)
             .unwrap();
 
         assert_eq!(
@@ -725,18 +776,24 @@ mod tests {
             Side::Right,
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
-        selection = selection
-            .rotate(&size, &(Line(1)..Line(size.0 as i32 - 1)), 4)
-            .unwrap();
+        let region = Line(1)..Line(size.0 as i32 - 1);
 
         assert_eq!(
-            selection.to_range(&term(size.0, size.1)).unwrap(),
+            selection
+                .clone()
+                .rotate(&region, 2)
+                .unwrap()
+                .to_range(&term(size.0, size.1))
+                .unwrap(),
             SelectionRange {
-                start: Pos::new(Line(1), Column(0)),
-                end: Pos::new(Line(3), Column(3)),
+                start: Pos::new(Line(2), Column(2)),
+                end: Pos::new(Line(5), Column(3)),
                 is_block: false,
             }
         );
+
+        // The start scrolls out of the top of the region.
+        assert_eq!(selection.rotate(&region, 4), None);
     }
 
     #[test]
@@ -748,18 +805,24 @@ mod tests {
             Side::Right,
         );
         selection.update(Pos::new(Line(1), Column(1)), Side::Left);
-        selection = selection
-            .rotate(&size, &(Line(1)..Line(size.0 as i32 - 1)), -5)
-            .unwrap();
+        let region = Line(1)..Line(size.0 as i32 - 1);
 
         assert_eq!(
-            selection.to_range(&term(size.0, size.1)).unwrap(),
+            selection
+                .clone()
+                .rotate(&region, -3)
+                .unwrap()
+                .to_range(&term(size.0, size.1))
+                .unwrap(),
             SelectionRange {
-                start: Pos::new(Line(6), Column(1)),
-                end: Pos::new(Line(8), size.last_column()),
+                start: Pos::new(Line(4), Column(1)),
+                end: Pos::new(Line(7), Column(3)),
                 is_block: false,
             }
         );
+
+        // The end scrolls out of the bottom of the region.
+        assert_eq!(selection.rotate(&region, -5), None);
     }
 
     #[test]
@@ -771,18 +834,23 @@ mod tests {
             Side::Right,
         );
         selection.update(Pos::new(Line(4), Column(1)), Side::Right);
-        selection = selection
-            .rotate(&size, &(Line(1)..Line(size.0 as i32 - 1)), 4)
-            .unwrap();
+        let region = Line(1)..Line(size.0 as i32 - 1);
 
         assert_eq!(
-            selection.to_range(&term(size.0, size.1)).unwrap(),
+            selection
+                .clone()
+                .rotate(&region, 2)
+                .unwrap()
+                .to_range(&term(size.0, size.1))
+                .unwrap(),
             SelectionRange {
-                start: Pos::new(Line(1), Column(2)),
-                end: Pos::new(Line(3), Column(3)),
+                start: Pos::new(Line(2), Column(2)),
+                end: Pos::new(Line(5), Column(3)),
                 is_block: true,
             }
         );
+
+        assert_eq!(selection.rotate(&region, 4), None);
     }
 
     #[test]
@@ -805,4 +873,134 @@ mod tests {
         assert!(!selection.intersects_range(..=Line(2)));
         assert!(!selection.intersects_range(Line(7)..=Line(8)));
     }
+
+    #[test]
+    fn simple_selection_backwards() {
+        let term = term_with(1, 12, "hello world");
+        let mut selection = Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(7)),
+            Side::Right,
+        );
+        selection.update(Pos::new(Line(0), Column(2)), Side::Left);
+
+        assert_eq!(
+            selection.to_range(&term).unwrap(),
+            SelectionRange::new(
+                Pos::new(Line(0), Column(2)),
+                Pos::new(Line(0), Column(7)),
+                false
+            )
+        );
+        assert_eq!(selection.to_string(&term).unwrap(), "llo wo");
+    }
+
+    #[test]
+    fn block_selection_over_uneven_lines() {
+        let term = term_with(3, 8, "abcdef\r\nab\r\nabcd");
+        let mut selection = Selection::new(
+            SelectionType::Block,
+            Pos::new(Line(2), Column(4)),
+            Side::Right,
+        );
+        selection.update(Pos::new(Line(0), Column(1)), Side::Left);
+
+        assert_eq!(selection.to_string(&term).unwrap(), "bcde\nb\nbcd");
+    }
+
+    #[test]
+    fn semantic_selection_stops_at_punctuation() {
+        let mut term = term_with(1, 14, "foo(bar),baz");
+        let selection = Selection::new(
+            SelectionType::Semantic,
+            Pos::new(Line(0), Column(5)),
+            Side::Left,
+        );
+
+        assert_eq!(selection.to_string(&term).unwrap(), "bar");
+
+        term.set_semantic_escape_chars(" ");
+        assert_eq!(selection.to_string(&term).unwrap(), "foo(bar),baz");
+    }
+
+    #[test]
+    fn semantic_selection_with_wide_chars() {
+        let mut term = term_with(1, 12, "a\u{4f60}b\u{3001}cd");
+        let semantic = |column| {
+            Selection::new(
+                SelectionType::Semantic,
+                Pos::new(Line(0), Column(column)),
+                Side::Left,
+            )
+        };
+
+        // Wide chars and their spacers are part of the word.
+        assert_eq!(
+            semantic(2).to_string(&term).unwrap(),
+            "a\u{4f60}b\u{3001}cd"
+        );
+
+        // A wide delimiter ends the word on either side of it.
+        term.set_semantic_escape_chars("\u{3001} ");
+        assert_eq!(semantic(0).to_string(&term).unwrap(), "a\u{4f60}b");
+        assert_eq!(
+            semantic(0).to_range(&term).unwrap().end,
+            Pos::new(Line(0), Column(3))
+        );
+        assert_eq!(semantic(6).to_string(&term).unwrap(), "cd");
+        assert_eq!(
+            semantic(6).to_range(&term).unwrap().start,
+            Pos::new(Line(0), Column(6))
+        );
+    }
+
+    #[test]
+    fn semantic_selection_follows_wrapped_lines() {
+        let term = term_with(3, 4, "ab cdefg\r\nhi");
+        let selection = Selection::new(
+            SelectionType::Semantic,
+            Pos::new(Line(1), Column(0)),
+            Side::Left,
+        );
+        assert_eq!(selection.to_string(&term).unwrap(), "cdefg");
+
+        // Lines not joined by a wrap are separate words.
+        let selection = Selection::new(
+            SelectionType::Semantic,
+            Pos::new(Line(2), Column(0)),
+            Side::Left,
+        );
+        assert_eq!(selection.to_string(&term).unwrap(), "hi");
+    }
+
+    #[test]
+    fn simple_selection_expands_over_wide_chars() {
+        let term = term_with(1, 6, "a\u{4f60}b");
+
+        // Ending on the wide char also takes its spacer.
+        let mut selection = Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(0)),
+            Side::Left,
+        );
+        selection.update(Pos::new(Line(0), Column(1)), Side::Right);
+        assert_eq!(
+            selection.to_range(&term).unwrap().end,
+            Pos::new(Line(0), Column(2))
+        );
+        assert_eq!(selection.to_string(&term).unwrap(), "a\u{4f60}");
+
+        // Starting on the spacer takes the wide char.
+        let mut selection = Selection::new(
+            SelectionType::Simple,
+            Pos::new(Line(0), Column(2)),
+            Side::Left,
+        );
+        selection.update(Pos::new(Line(0), Column(3)), Side::Right);
+        assert_eq!(
+            selection.to_range(&term).unwrap().start,
+            Pos::new(Line(0), Column(1))
+        );
+        assert_eq!(selection.to_string(&term).unwrap(), "\u{4f60}b");
+    }
 }
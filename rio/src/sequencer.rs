@@ -191,6 +191,19 @@ impl Sequencer {
                                     drop(terminal);
                                 }
                             }
+                            RioEventType::Rio(RioEvent::ClipboardStore(
+                                clipboard_type,
+                                content,
+                            )) => {
+                                if let Some(route) =
+                                    self.router.routes.get_mut(&window_id)
+                                {
+                                    route
+                                        .window
+                                        .screen
+                                        .clipboard_store(clipboard_type, content);
+                                }
+                            }
                             RioEventType::Rio(RioEvent::ClipboardLoad(
                                 clipboard_type,
                                 format,
@@ -230,17 +243,13 @@ impl Sequencer {
                                 }
                             }
                             RioEventType::Rio(RioEvent::ColorRequest(index, format)) => {
-                                // TODO: colors could be coming terminal as well
-                                // if colors has been declaratively changed
-                                // Rio doesn't cover this case yet.
-                                //
-                                // In the future should try first get
-                                // from Crosrouteords then state colors
-                                // screen.colors()[index] or screen.state.colors[index]
+                                // Colors changed by the terminal are answered by
+                                // Crosswords, only configured ones get here.
                                 if let Some(route) =
                                     self.router.routes.get_mut(&window_id)
                                 {
-                                    let color = route.window.screen.state.colors[index];
+                                    let color =
+                                        route.window.screen.state.configured_color(index);
                                     let rgb = ColorRgb::from_color_arr(color);
                                     route
                                         .window
@@ -61,6 +61,7 @@ fn bench_sugar_pile(c: &mut Criterion) {
                     background_color: [0.0, 1.0, 1.0, 1.0],
                     style: None,
                     decoration: None,
+                    extra_decorations: [None, None],
                 });
 
                 pile2.push(Sugar {
@@ -69,6 +70,7 @@ fn bench_sugar_pile(c: &mut Criterion) {
                     background_color: [0.0, 1.0, 1.0, 1.0],
                     style: None,
                     decoration: None,
+                    extra_decorations: [None, None],
                 });
 
                 pile3.push(Sugar {
@@ -77,12 +79,13 @@ fn bench_sugar_pile(c: &mut Criterion) {
                     background_color: [0.0, 1.0, 1.0, 1.0],
                     style: None,
                     decoration: None,
+                    extra_decorations: [None, None],
                 });
             }
 
-            sugarloaf.stack(pile);
-            sugarloaf.stack(pile2);
-            sugarloaf.stack(pile3);
+            sugarloaf.stack(&pile);
+            sugarloaf.stack(&pile2);
+            sugarloaf.stack(&pile3);
 
             sugarloaf.render();
         })
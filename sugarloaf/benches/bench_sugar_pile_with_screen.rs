@@ -68,10 +68,11 @@ fn bench_sugar_pile_with_screen(c: &mut Criterion) {
                                 background_color: [0.0, 1.0, 1.0, 1.0],
                                 style: None,
                                 decoration: None,
+                                extra_decorations: [None, None],
                             });
                         }
 
-                        sugarloaf.stack(pile);
+                        sugarloaf.stack(&pile);
                         sugarloaf.render();
                     })
                 });
@@ -69,6 +69,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '',
@@ -76,6 +77,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -87,6 +89,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -98,6 +101,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -105,6 +109,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -112,6 +117,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -123,6 +129,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -134,6 +141,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'u',
@@ -141,6 +149,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -148,6 +157,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -155,6 +165,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -162,6 +173,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -169,6 +181,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -176,6 +189,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -183,6 +197,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -190,6 +205,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -206,6 +222,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -213,6 +230,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -220,6 +238,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -227,6 +246,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¼',
@@ -234,6 +254,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¬',
@@ -241,6 +262,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -248,6 +270,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -255,6 +278,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'f',
@@ -262,6 +286,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -269,6 +294,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // // Font Unicode (unicode font)
             Sugar {
@@ -277,6 +303,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Symbol (apple symbols font)
             Sugar {
@@ -285,6 +312,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Regular (firamono)
             Sugar {
@@ -293,6 +321,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // // Font Emojis
             Sugar {
@@ -301,6 +330,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '👷',
@@ -308,6 +338,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -319,6 +350,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '➜',
@@ -326,6 +358,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -333,6 +366,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '％',
@@ -340,6 +374,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '',
@@ -347,6 +382,7 @@ async fn main() {
                 background_color: [0.5, 0.5, 0.5, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -354,6 +390,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 // content: '',
@@ -362,6 +399,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '🥇',
@@ -369,6 +407,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '',
@@ -376,6 +415,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -409,9 +449,9 @@ async fn main() {
                 _ => (),
             },
             Event::RedrawRequested { .. } => {
-                sugarloaf.stack(sugar);
-                sugarloaf.stack(rio);
-                sugarloaf.stack(special);
+                sugarloaf.stack(&sugar);
+                sugarloaf.stack(&rio);
+                sugarloaf.stack(&special);
                 sugarloaf.render();
             }
             _ => {
@@ -58,6 +58,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'u',
@@ -65,6 +66,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -72,6 +74,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -79,6 +82,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -86,6 +90,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -93,6 +98,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -100,6 +106,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -110,6 +117,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -117,6 +125,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -124,6 +133,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'f',
@@ -131,6 +141,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -138,6 +149,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -145,6 +157,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -161,6 +174,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -168,6 +182,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -175,6 +190,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -182,6 +198,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: Some(underline),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -189,6 +206,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¼',
@@ -196,6 +214,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¬',
@@ -203,6 +222,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -214,6 +234,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Symbol (apple symbols font)
             Sugar {
@@ -222,6 +243,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Regular (firamono)
             Sugar {
@@ -230,6 +252,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Emojis
             Sugar {
@@ -238,6 +261,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '👷',
@@ -245,6 +269,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -256,6 +281,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '％',
@@ -263,6 +289,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '',
@@ -270,6 +297,7 @@ async fn main() {
                 background_color: [0.5, 0.5, 0.5, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -277,6 +305,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '',
@@ -284,6 +313,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -317,11 +347,11 @@ async fn main() {
                 _ => (),
             },
             Event::RedrawRequested { .. } => {
-                sugarloaf.stack(sugar);
-                sugarloaf.stack(loaf);
-                sugarloaf.stack(special_2);
-                sugarloaf.stack(rio);
-                sugarloaf.stack(special);
+                sugarloaf.stack(&sugar);
+                sugarloaf.stack(&loaf);
+                sugarloaf.stack(&special_2);
+                sugarloaf.stack(&rio);
+                sugarloaf.stack(&special);
                 sugarloaf.render();
             }
             _ => {
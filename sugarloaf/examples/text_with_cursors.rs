@@ -66,6 +66,7 @@ async fn main() {
                     size: (1.0, 0.050),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'n',
@@ -77,6 +78,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'd',
@@ -88,6 +90,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -99,6 +102,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -110,6 +114,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -121,6 +126,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '!',
@@ -128,6 +134,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -139,6 +146,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'n',
@@ -150,6 +158,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -161,6 +170,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -172,6 +182,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -183,6 +194,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -190,6 +202,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -204,6 +217,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 't',
@@ -215,6 +229,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -226,6 +241,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -237,6 +253,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -248,6 +265,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'c',
@@ -259,6 +277,7 @@ async fn main() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -270,6 +289,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'b',
@@ -281,6 +301,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -292,6 +313,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -303,6 +325,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'd',
@@ -314,6 +337,7 @@ async fn main() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -328,6 +352,7 @@ async fn main() {
                     size: (1.0, 0.05),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -335,6 +360,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -342,6 +368,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'u',
@@ -349,6 +376,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -356,6 +384,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -363,6 +392,7 @@ async fn main() {
                 background_color: [1.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -370,6 +400,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -384,6 +415,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 't',
@@ -395,6 +427,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -406,6 +439,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -417,6 +451,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'k',
@@ -428,6 +463,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -439,6 +475,7 @@ async fn main() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
         ];
 
@@ -467,6 +504,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: block,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -474,6 +512,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -481,6 +520,7 @@ async fn main() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: underline,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -488,6 +528,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -495,6 +536,7 @@ async fn main() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: beam,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -529,11 +571,11 @@ async fn main() {
                 _ => (),
             },
             Event::RedrawRequested { .. } => {
-                sugarloaf.stack(sugar);
-                sugarloaf.stack(italic_and_bold);
-                sugarloaf.stack(rio);
-                sugarloaf.stack(strike);
-                sugarloaf.stack(cursors);
+                sugarloaf.stack(&sugar);
+                sugarloaf.stack(&italic_and_bold);
+                sugarloaf.stack(&rio);
+                sugarloaf.stack(&strike);
+                sugarloaf.stack(&cursors);
                 sugarloaf.render();
             }
             _ => {
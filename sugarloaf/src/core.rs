@@ -7,6 +7,19 @@ pub struct Sugar {
     pub background_color: [f32; 4],
     pub style: Option<SugarStyle>,
     pub decoration: Option<SugarDecoration>,
+    /// Decorations drawn along with `decoration`, since text decorations like underline,
+    /// strikeout and overline can be combined.
+    pub extra_decorations: [Option<SugarDecoration>; 2],
+}
+
+impl Sugar {
+    /// Every decoration of this sugar, in drawing order.
+    #[inline]
+    pub fn decorations(&self) -> impl Iterator<Item = &SugarDecoration> {
+        self.decoration
+            .iter()
+            .chain(self.extra_decorations.iter().flatten())
+    }
 }
 
 #[derive(Debug)]
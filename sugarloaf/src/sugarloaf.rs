@@ -3,7 +3,7 @@ use crate::components::layer::{self, LayerBrush};
 use crate::components::rect::{Rect, RectBrush};
 use crate::components::text;
 use crate::context::Context;
-use crate::core::{ImageProperties, RepeatedSugar, Sugar, SugarStack};
+use crate::core::{ImageProperties, RepeatedSugar, Sugar};
 use crate::font::fonts::{SugarloafFont, SugarloafFonts};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::font::loader::Database;
@@ -233,7 +233,7 @@ impl Sugarloaf {
     }
 
     #[inline]
-    pub fn get_font_id(&mut self, sugar: &mut Sugar) -> CachedSugar {
+    pub fn get_font_id(&mut self, sugar: &Sugar) -> CachedSugar {
         if let Some(cached_sugar) = self.sugar_cache.get(&sugar.content) {
             return *cached_sugar;
         }
@@ -312,7 +312,7 @@ impl Sugarloaf {
     }
 
     #[inline]
-    pub fn stack(&mut self, mut stack: SugarStack) {
+    pub fn stack(&mut self, stack: &[Sugar]) {
         let mut x = 0.;
         let mut sections = vec![];
         let mod_pos_y = self.layout.style.screen_position.1;
@@ -334,14 +334,14 @@ impl Sugarloaf {
             let mut sugar_char_width = 1.;
             let rect_pos_x = self.layout.style.screen_position.0 + x;
 
-            let cached_sugar: CachedSugar = self.get_font_id(&mut stack[i]);
+            let cached_sugar: CachedSugar = self.get_font_id(&stack[i]);
             if i < size - 1
                 && cached_sugar.char_width <= 1.
                 && stack[i].content == stack[i + 1].content
                 && stack[i].foreground_color == stack[i + 1].foreground_color
                 && stack[i].background_color == stack[i + 1].background_color
-                && stack[i].decoration.is_none()
-                && stack[i + 1].decoration.is_none()
+                && stack[i].decorations().next().is_none()
+                && stack[i + 1].decorations().next().is_none()
             {
                 repeated.set(&stack[i], rect_pos_x, mod_text_y + self.text_y + mod_pos_y);
                 x += add_pos_x;
@@ -440,7 +440,7 @@ impl Sugarloaf {
                 size: [width_bound * quantity as f32, self.layout.sugarheight],
             });
 
-            if let Some(decoration) = &stack[i].decoration {
+            for decoration in stack[i].decorations() {
                 let dec_pos_y = (scaled_rect_pos_y)
                     + (decoration.relative_position.1 * self.layout.line_height);
                 self.rects.push(Rect {
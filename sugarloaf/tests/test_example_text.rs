@@ -76,6 +76,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'u',
@@ -83,6 +84,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -90,6 +92,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -97,6 +100,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -104,6 +108,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -111,6 +116,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -118,6 +124,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -128,6 +135,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -135,6 +143,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -142,6 +151,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'f',
@@ -149,6 +159,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -156,6 +167,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '|',
@@ -163,6 +175,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -173,6 +186,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -180,6 +194,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -187,6 +202,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -194,6 +210,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -201,6 +218,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¼',
@@ -208,6 +226,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '¬',
@@ -215,6 +234,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -226,6 +246,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Symbol (apple symbols font)
             Sugar {
@@ -234,6 +255,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Regular
             Sugar {
@@ -242,6 +264,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             // Font Emojis
             Sugar {
@@ -250,6 +273,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '👷',
@@ -257,6 +281,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -90,6 +90,7 @@ async fn run() {
                     size: (1.0, 0.050),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'n',
@@ -101,6 +102,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'd',
@@ -112,6 +114,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -123,6 +126,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -134,6 +138,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -145,6 +150,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: '!',
@@ -152,6 +158,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -163,6 +170,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'n',
@@ -174,6 +182,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -185,6 +194,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -196,6 +206,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -207,6 +218,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -214,6 +226,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -228,6 +241,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 't',
@@ -239,6 +253,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -250,6 +265,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -261,6 +277,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -272,6 +289,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'c',
@@ -283,6 +301,7 @@ async fn run() {
                     is_bold: false,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -294,6 +313,7 @@ async fn run() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'b',
@@ -305,6 +325,7 @@ async fn run() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'o',
@@ -316,6 +337,7 @@ async fn run() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -327,6 +349,7 @@ async fn run() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'd',
@@ -338,6 +361,7 @@ async fn run() {
                     is_bold: true,
                 }),
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -352,6 +376,7 @@ async fn run() {
                     size: (1.0, 0.05),
                     color: [0.0, 0.0, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -359,6 +384,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'g',
@@ -366,6 +392,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'u',
@@ -373,6 +400,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'l',
@@ -380,6 +408,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'a',
@@ -387,6 +416,7 @@ async fn run() {
                 background_color: [1.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -394,6 +424,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 0.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -408,6 +439,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 't',
@@ -419,6 +451,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'r',
@@ -430,6 +463,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'i',
@@ -441,6 +475,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'k',
@@ -452,6 +487,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: 'e',
@@ -463,6 +499,7 @@ async fn run() {
                     size: (1.0, 0.025),
                     color: [0.5, 0.5, 0.0, 1.0],
                 }),
+                extra_decorations: [None, None],
             },
         ];
 
@@ -491,6 +528,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: block,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -498,6 +536,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -505,6 +544,7 @@ async fn run() {
                 background_color: [0.0, 0.0, 0.0, 1.0],
                 style: None,
                 decoration: underline,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -512,6 +552,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: None,
+                extra_decorations: [None, None],
             },
             Sugar {
                 content: ' ',
@@ -519,6 +560,7 @@ async fn run() {
                 background_color: [0.0, 1.0, 1.0, 1.0],
                 style: None,
                 decoration: beam,
+                extra_decorations: [None, None],
             },
         ];
 
@@ -541,11 +583,11 @@ async fn run() {
                 _ => (),
             },
             Event::RedrawRequested { .. } => {
-                sugarloaf.stack(sugar);
-                sugarloaf.stack(italic_and_bold);
-                sugarloaf.stack(rio);
-                sugarloaf.stack(strike);
-                sugarloaf.stack(cursors);
+                sugarloaf.stack(&sugar);
+                sugarloaf.stack(&italic_and_bold);
+                sugarloaf.stack(&rio);
+                sugarloaf.stack(&strike);
+                sugarloaf.stack(&cursors);
                 sugarloaf.render();
             }
             _ => {